@@ -0,0 +1,28 @@
+use async_trait::async_trait;
+use fluct_core::{Block, ConsensusApi, ForkChoiceState};
+use fluct_service::Caller;
+
+use crate::{ApiError, ApiRequest, ApiResponse};
+
+#[derive(Clone)]
+pub struct BftConsensusApi {
+    pub(crate) caller: Caller<ApiRequest, ApiResponse>,
+}
+
+#[async_trait]
+impl ConsensusApi for BftConsensusApi {
+    type Error = ApiError;
+
+    /// Blocks are only committed with votes of validators
+    async fn add_block(&mut self, _block: Block) -> Result<(), Self::Error> {
+        Err(ApiError::BlockWithoutCommit)
+    }
+
+    async fn chain_state(&self) -> Result<ForkChoiceState, Self::Error> {
+        let resp = self.caller.call(ApiRequest::GetChainState).await?;
+
+        match resp {
+            ApiResponse::GetChainState(v) => Ok(v),
+        }
+    }
+}
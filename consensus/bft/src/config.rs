@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use crate::{Round, Step};
+
+/// Default time to wait for proposal
+pub const DEFAULT_TIMEOUT_PROPOSE: Duration = Duration::from_secs(3);
+
+/// Default time to wait for more prevotes after `2f + 1` arrived
+pub const DEFAULT_TIMEOUT_PREVOTE: Duration = Duration::from_secs(1);
+
+/// Default time to wait for more precommits after `2f + 1` arrived
+pub const DEFAULT_TIMEOUT_PRECOMMIT: Duration = Duration::from_secs(1);
+
+/// Default increase of timeouts per round
+pub const DEFAULT_TIMEOUT_DELTA: Duration = Duration::from_millis(500);
+
+/// Config of BFT consensus
+#[derive(Debug, Clone)]
+pub struct BftConfig {
+    pub timeout_propose: Duration,
+    pub timeout_prevote: Duration,
+    pub timeout_precommit: Duration,
+
+    /// Timeouts grow by it each round, so validators eventually wait long enough
+    pub timeout_delta: Duration,
+}
+
+impl Default for BftConfig {
+    fn default() -> Self {
+        Self {
+            timeout_propose: DEFAULT_TIMEOUT_PROPOSE,
+            timeout_prevote: DEFAULT_TIMEOUT_PREVOTE,
+            timeout_precommit: DEFAULT_TIMEOUT_PRECOMMIT,
+            timeout_delta: DEFAULT_TIMEOUT_DELTA,
+        }
+    }
+}
+
+impl BftConfig {
+    /// Timeout of step in round
+    pub fn timeout(&self, step: Step, round: Round) -> Duration {
+        let base = match step {
+            Step::Propose => self.timeout_propose,
+            Step::Prevote => self.timeout_prevote,
+            Step::Precommit => self.timeout_precommit,
+        };
+
+        base + self.timeout_delta * round
+    }
+}
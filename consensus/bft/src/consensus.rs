@@ -0,0 +1,666 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, VecDeque},
+    io,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use ethers_core::types::H160;
+use ethers_signers::{LocalWallet, Signer};
+use fluct_core::{
+    ConsensusGenesis, ConsensusService, EngineApi, ForkChoiceState, SequencerApi, Service,
+    Transaction, Web3Api,
+};
+use fluct_service::{
+    local_rpc, AsyncStepService, AsyncStepServiceWapper1, CallError, Caller, Hander,
+};
+use futures_util::future::{select, Either};
+use tokio::{
+    sync::oneshot,
+    time::{self, Instant},
+};
+
+use crate::{
+    ApiRequest, ApiResponse, BftConfig, BftConsensusApi, BlockExecutor, EngineExecutor, Error,
+    Message, MessageTransport, Output, Result, SignedMessage, Tendermint, Timeout, ValidatorSet,
+};
+
+/// Max count of messages held for later heights, per validator
+const MAX_FUTURE_MESSAGES: usize = 256;
+
+/// Wake up interval when no timeout scheduled
+const IDLE_INTERVAL: Duration = Duration::from_secs(60);
+
+enum Event {
+    Request(ApiRequest, Option<oneshot::Sender<ApiResponse>>),
+    Message(SignedMessage),
+    Tick,
+}
+
+/// Drives Tendermint state machine with executor, transport and timers
+pub struct BftConsensus<E, T> {
+    handler: Hander<ApiRequest, ApiResponse>,
+    caller: Caller<ApiRequest, ApiResponse>,
+    config: BftConfig,
+    signer: Option<LocalWallet>,
+    chain_id: u64,
+    validators: Option<ValidatorSet>,
+    machine: Option<Tendermint>,
+    executor: E,
+    transport: T,
+    timers: Vec<(Instant, Timeout)>,
+    /// Verified messages of later heights by signer, passed again after commit
+    future: HashMap<H160, Vec<SignedMessage>>,
+}
+
+impl<E, T> BftConsensus<E, T>
+where
+    E: BlockExecutor,
+    T: MessageTransport,
+{
+    /// Create consensus, node only follows commits of validators without signer
+    pub fn new(config: BftConfig, signer: Option<LocalWallet>, executor: E, transport: T) -> Self {
+        let (handler, caller) = local_rpc();
+
+        Self {
+            handler,
+            caller,
+            config,
+            signer,
+            chain_id: 0,
+            validators: None,
+            machine: None,
+            executor,
+            transport,
+            timers: Vec::new(),
+            future: HashMap::new(),
+        }
+    }
+
+    /// Validators signing messages of chain
+    pub fn set_validators(&mut self, chain_id: u64, validators: ValidatorSet) {
+        self.chain_id = chain_id;
+        self.validators = Some(validators);
+    }
+
+    pub fn executor(&self) -> &E {
+        &self.executor
+    }
+
+    pub fn api(&self) -> BftConsensusApi {
+        BftConsensusApi {
+            caller: self.caller.clone(),
+        }
+    }
+
+    fn machine(&mut self) -> Result<&mut Tendermint> {
+        self.machine.as_mut().ok_or(Error::NotInited)
+    }
+
+    /// Start from block after last committed one
+    async fn load(&mut self) -> Result<()> {
+        let validators = self.validators.clone().ok_or(Error::NotInited)?;
+
+        let mut machine = Tendermint::new(
+            self.chain_id,
+            validators,
+            self.signer.clone(),
+            self.config.clone(),
+        )?;
+
+        let height = self.executor.last_height().await? + 1;
+
+        let out = machine.start(height)?;
+        self.machine = Some(machine);
+
+        self.apply(out).await
+    }
+
+    async fn next_event(&mut self) -> Result<Event> {
+        let deadline = self
+            .timers
+            .iter()
+            .map(|(at, _)| *at)
+            .min()
+            .unwrap_or_else(|| Instant::now() + IDLE_INTERVAL);
+
+        let request = Box::pin(self.handler.recv());
+        let message = Box::pin(self.transport.recv());
+        let tick = Box::pin(time::sleep_until(deadline));
+
+        let event = match select(request, select(message, tick)).await {
+            Either::Left((r, _)) => {
+                let (req, resper) = r?;
+                Event::Request(req, resper)
+            }
+            Either::Right((Either::Left((message, _)), _)) => Event::Message(message?),
+            Either::Right((Either::Right(_), _)) => Event::Tick,
+        };
+
+        Ok(event)
+    }
+
+    /// Pass message of current height to state machine, proposal is executed first
+    ///
+    /// Messages of later heights are verified and held, up to `MAX_FUTURE_MESSAGES` of
+    /// each validator, so a faulty validator only drops its own messages.
+    async fn on_message(&mut self, msg: SignedMessage) -> Result<Vec<Output>> {
+        let machine = self.machine()?;
+        let height = machine.height();
+
+        let ordering = msg.message.height().cmp(&height);
+
+        match ordering {
+            Ordering::Less => return Ok(Vec::new()),
+            // Too many rounds ahead
+            Ordering::Equal if !machine.is_current(&msg) => return Ok(Vec::new()),
+            _ => {}
+        }
+
+        let signer = match machine.verify(&msg) {
+            Ok(v) => v,
+            Err(e) => {
+                log::warn!("Drop message: {}", e);
+                return Ok(Vec::new());
+            }
+        };
+
+        // Own messages are taken when sent
+        if Some(signer) == machine.address() {
+            return Ok(Vec::new());
+        }
+
+        if ordering == Ordering::Greater {
+            let held = self.future.entry(signer).or_default();
+
+            if held.len() < MAX_FUTURE_MESSAGES {
+                held.push(msg);
+            } else {
+                log::warn!(
+                    "Too many messages of later heights from {:?}, drop one",
+                    signer
+                );
+            }
+
+            return Ok(Vec::new());
+        }
+
+        let machine = self.machine()?;
+
+        match &msg.message {
+            Message::Proposal(p) => {
+                let valid = self.executor.execute(&p.payload).await?;
+                self.machine()?.on_proposal(msg, valid)
+            }
+            Message::Vote(_) => machine.on_vote(msg),
+        }
+    }
+
+    async fn apply(&mut self, out: Vec<Output>) -> Result<()> {
+        let mut queue = VecDeque::from(out);
+
+        while let Some(output) = queue.pop_front() {
+            match output {
+                Output::Broadcast(msg) => self.transport.broadcast(msg)?,
+                Output::Schedule(timeout, after) => {
+                    self.timers.push((Instant::now() + after, timeout));
+                }
+                Output::Build { height, round } => match self.executor.build(height).await {
+                    Ok(payload) => queue.extend(self.machine()?.propose(payload)?),
+                    Err(e) => log::warn!(
+                        "Failed to build block of height {} round {}: {}",
+                        height,
+                        round,
+                        e
+                    ),
+                },
+                Output::Commit(commit) => {
+                    log::info!(
+                        "Commit block {}, hash: {:?}, round: {}",
+                        commit.height,
+                        commit.payload.block_hash,
+                        commit.round
+                    );
+
+                    // State machine has moved on, executor can't catch up with it
+                    self.executor
+                        .commit(&commit)
+                        .await
+                        .map_err(|e| Error::CommitFailed(commit.height, e.to_string()))?;
+
+                    for msg in std::mem::take(&mut self.future).into_values().flatten() {
+                        queue.extend(self.on_message(msg).await?);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn on_tick(&mut self) -> Result<()> {
+        let now = Instant::now();
+
+        let (due, timers) = std::mem::take(&mut self.timers)
+            .into_iter()
+            .partition(|(at, _)| *at <= now);
+
+        self.timers = timers;
+
+        for (_, timeout) in due {
+            let out = self.machine()?.on_timeout(timeout)?;
+            self.apply(out).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn _step(&mut self) -> Result<()> {
+        if self.machine.is_none() {
+            return self.load().await;
+        }
+
+        match self.next_event().await? {
+            Event::Request(ApiRequest::GetChainState, resper) => {
+                if let Some(resper) = resper {
+                    resper
+                        .send(ApiResponse::GetChainState(self.executor.chain_state()))
+                        .map_err(|_| CallError::ChannelClosed)?;
+                } else {
+                    log::warn!("Use call method to get chain state")
+                }
+            }
+            Event::Message(msg) => {
+                let out = self.on_message(msg).await?;
+                self.apply(out).await?;
+            }
+            Event::Tick => self.on_tick().await?,
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<E, T> AsyncStepService for BftConsensus<E, T>
+where
+    E: BlockExecutor + Sync,
+    T: MessageTransport + Sync,
+{
+    type Error = Error;
+
+    async fn step(&mut self) -> Result<()> {
+        self._step().await
+    }
+}
+
+pub struct BftConsensusService<SA, T>(AsyncStepServiceWapper1<BftConsensus<EngineExecutor<SA>, T>>);
+
+impl<SA, T> BftConsensusService<SA, T>
+where
+    SA: SequencerApi + Send + Sync + 'static,
+    T: MessageTransport,
+{
+    /// Create service, validator signs votes and receives fees of its blocks
+    pub fn new(config: BftConfig, signer: Option<LocalWallet>, transport: T) -> Self {
+        let fee_recipient = signer.as_ref().map(|s| s.address()).unwrap_or_default();
+
+        Self(AsyncStepServiceWapper1::new(BftConsensus::new(
+            config,
+            signer,
+            EngineExecutor::new(fee_recipient),
+            transport,
+        )))
+    }
+
+    pub fn api(&self) -> BftConsensusApi {
+        self.0.service0().api()
+    }
+
+    /// Address of local validator
+    pub fn validator(&self) -> Option<H160> {
+        self.0.service0().signer.as_ref().map(|s| s.address())
+    }
+}
+
+impl<SA, T> Service for BftConsensusService<SA, T>
+where
+    SA: SequencerApi + Send + Sync + 'static,
+    T: MessageTransport + Sync,
+{
+    type Error = io::Error;
+
+    fn start(&mut self) -> std::result::Result<(), Self::Error> {
+        self.0.start()
+    }
+
+    fn stop(&mut self) -> std::result::Result<(), Self::Error> {
+        self.0.stop()
+    }
+}
+
+impl<SA, T> ConsensusService<SA> for BftConsensusService<SA, T>
+where
+    SA: SequencerApi + Send + Sync + 'static,
+    T: MessageTransport + Sync,
+{
+    fn init(
+        &mut self,
+        genesis: ConsensusGenesis<Transaction>,
+        state: ForkChoiceState,
+    ) -> std::result::Result<(), Self::Error> {
+        let validators = ValidatorSet::new(genesis.validators.clone()).map_err(io::Error::other)?;
+
+        let consensus = self.0.service0_mut();
+        consensus.set_validators(genesis.chain_id, validators);
+
+        consensus.executor.genesis = Some(genesis);
+        consensus.executor.state = state;
+
+        Ok(())
+    }
+
+    fn set_api(&mut self, engine_api: impl EngineApi, web3_api: impl Web3Api, seqencer_api: SA) {
+        let executor = &mut self.0.service0_mut().executor;
+
+        executor.engine_api = Some(Box::new(engine_api));
+        executor.web3_api = Some(Box::new(web3_api));
+        executor.sequencer_api = Some(seqencer_api);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use ethers_core::types::{Bytes, H256};
+    use ethers_signers::{LocalWallet, Signer};
+    use fluct_core::{ExecutionPayload, ForkChoiceState, Transaction};
+    use futures_util::future::join_all;
+
+    use fluct_service::StepError;
+
+    use crate::{
+        BftConfig, BftConsensus, BlockExecutor, Commit, Error, LocalNetwork, LocalTransport,
+        Message, Result, SignedMessage, ValidatorSet, Vote, VoteKind, MAX_ROUNDS_AHEAD,
+    };
+
+    use super::MAX_FUTURE_MESSAGES;
+
+    const CHAIN_ID: u64 = 901;
+
+    /// Executor keeps committed blocks in memory
+    struct MemExecutor {
+        index: u64,
+        commits: Vec<Commit>,
+        /// Engine rejects committed block
+        fail_commit: bool,
+    }
+
+    impl MemExecutor {
+        fn head(&self) -> (u64, H256) {
+            self.commits
+                .last()
+                .map_or((0, H256::zero()), |c| (c.height, c.payload.block_hash))
+        }
+
+        fn hashes(&self) -> Vec<H256> {
+            self.commits.iter().map(|c| c.payload.block_hash).collect()
+        }
+    }
+
+    #[async_trait]
+    impl BlockExecutor for MemExecutor {
+        async fn last_height(&mut self) -> Result<u64> {
+            Ok(self.head().0)
+        }
+
+        async fn build(&mut self, height: u64) -> Result<ExecutionPayload<Transaction>> {
+            let (_, parent) = self.head();
+
+            Ok(ExecutionPayload {
+                parent_hash: parent,
+                fee_recipient: Default::default(),
+                state_root: Default::default(),
+                receipts_root: Default::default(),
+                logs_bloom: Bytes::default(),
+                prev_randao: Default::default(),
+                block_number: height.into(),
+                gas_limit: 30_000_000.into(),
+                gas_used: 0.into(),
+                timestamp: height.into(),
+                extra_data: Bytes::default(),
+                base_fee_per_gas: 7.into(),
+                block_hash: H256::from_low_u64_be(height << 8 | self.index),
+                transactions: Vec::new(),
+            })
+        }
+
+        async fn execute(&mut self, payload: &ExecutionPayload<Transaction>) -> Result<bool> {
+            let (number, parent) = self.head();
+
+            Ok(payload.parent_hash == parent && payload.block_number == (number + 1).into())
+        }
+
+        async fn commit(&mut self, commit: &Commit) -> Result<()> {
+            if self.fail_commit {
+                return Err(Error::InvalidPayload(
+                    commit.payload.block_hash,
+                    "Invalid".into(),
+                ));
+            }
+
+            self.commits.push(commit.clone());
+            Ok(())
+        }
+
+        fn chain_state(&self) -> ForkChoiceState {
+            Default::default()
+        }
+    }
+
+    fn wallet(i: u64) -> LocalWallet {
+        LocalWallet::from_bytes(H256::from_low_u64_be(i).as_bytes()).unwrap()
+    }
+
+    fn config(timeout_propose: Duration) -> BftConfig {
+        BftConfig {
+            timeout_propose,
+            timeout_prevote: Duration::from_millis(100),
+            timeout_precommit: Duration::from_millis(100),
+            timeout_delta: Duration::from_millis(100),
+        }
+    }
+
+    /// Run validators until each commits `heights` blocks
+    fn run(online: usize, heights: usize, config: BftConfig) -> Vec<MemExecutor> {
+        let wallets: Vec<_> = (1..=4).map(wallet).collect();
+        let validators = ValidatorSet::new(wallets.iter().map(|w| w.address()).collect()).unwrap();
+
+        let network = LocalNetwork::new(1024);
+
+        let nodes: Vec<BftConsensus<MemExecutor, LocalTransport>> = wallets
+            .into_iter()
+            .take(online)
+            .enumerate()
+            .map(|(i, w)| {
+                let executor = MemExecutor {
+                    index: i as u64,
+                    commits: Vec::new(),
+                    fail_commit: false,
+                };
+
+                let mut node = BftConsensus::new(config.clone(), Some(w), executor, network.join());
+                node.set_validators(CHAIN_ID, validators.clone());
+                node
+            })
+            .collect();
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+
+        rt.block_on(join_all(nodes.into_iter().map(|mut node| async move {
+            while node.executor().commits.len() < heights {
+                node._step().await.unwrap();
+            }
+            node.executor
+        })))
+    }
+
+    #[test]
+    fn test_commit() {
+        // long enough that round 0 always gets its proposal
+        let executors = run(4, 4, config(Duration::from_secs(5)));
+
+        let hashes = executors[0].hashes();
+
+        for (height, commit) in executors[0].commits.iter().enumerate() {
+            assert_eq!(commit.height, height as u64 + 1);
+            assert_eq!(commit.round, 0);
+            assert!(commit.precommits.len() >= 3);
+        }
+
+        for executor in &executors {
+            assert_eq!(executor.hashes()[..4], hashes[..4]);
+        }
+    }
+
+    #[test]
+    fn test_offline_proposer() {
+        // validator 3 proposes round 0 of height 3, other rounds go on without it
+        let executors = run(3, 4, config(Duration::from_millis(200)));
+
+        let hashes = executors[0].hashes();
+
+        for executor in &executors {
+            assert_eq!(executor.hashes()[..4], hashes[..4]);
+        }
+
+        let commit = &executors[0].commits[2];
+        assert_eq!(commit.height, 3);
+        assert!(commit.round > 0);
+        assert_eq!(commit.payload.parent_hash, hashes[1]);
+    }
+
+    #[test]
+    fn test_commit_failed() {
+        let validators = ValidatorSet::new(vec![wallet(1).address()]).unwrap();
+
+        let executor = MemExecutor {
+            index: 0,
+            commits: Vec::new(),
+            fail_commit: true,
+        };
+
+        let network = LocalNetwork::new(1024);
+        let mut node = BftConsensus::new(
+            config(Duration::from_millis(200)),
+            Some(wallet(1)),
+            executor,
+            network.join(),
+        );
+        node.set_validators(CHAIN_ID, validators);
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+
+        // executor left behind state machine stops the service
+        let err = rt.block_on(async {
+            loop {
+                if let Err(e) = node._step().await {
+                    break e;
+                }
+            }
+        });
+
+        assert!(matches!(err, Error::CommitFailed(1, _)));
+        assert!(err.is_exit());
+    }
+
+    #[test]
+    fn test_future_messages() {
+        let wallets: Vec<_> = (1..=4).map(wallet).collect();
+        let validators = ValidatorSet::new(wallets.iter().map(|w| w.address()).collect()).unwrap();
+
+        let executor = MemExecutor {
+            index: 0,
+            commits: Vec::new(),
+            fail_commit: false,
+        };
+
+        let network = LocalNetwork::new(1024);
+        let mut node = BftConsensus::new(
+            config(Duration::from_secs(5)),
+            Some(wallet(1)),
+            executor,
+            network.join(),
+        );
+        node.set_validators(CHAIN_ID, validators);
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+
+        rt.block_on(node.load()).unwrap();
+
+        let vote = |w: &LocalWallet, height: u64, round: u32, chain_id: u64| {
+            let vote = Vote {
+                kind: VoteKind::Prevote,
+                height,
+                round,
+                block_hash: None,
+            };
+            SignedMessage::sign(Message::Vote(vote), chain_id, w).unwrap()
+        };
+
+        rt.block_on(async {
+            // not a validator, or signed for other chain
+            node.on_message(vote(&wallet(9), 2, 0, CHAIN_ID))
+                .await
+                .unwrap();
+            node.on_message(vote(&wallets[1], 2, 0, CHAIN_ID + 1))
+                .await
+                .unwrap();
+            assert!(node.future.is_empty());
+
+            for round in 0..MAX_FUTURE_MESSAGES as u32 + 8 {
+                node.on_message(vote(&wallets[1], 2, round, CHAIN_ID))
+                    .await
+                    .unwrap();
+            }
+            node.on_message(vote(&wallets[2], 2, 0, CHAIN_ID))
+                .await
+                .unwrap();
+
+            assert_eq!(
+                node.future[&wallets[1].address()].len(),
+                MAX_FUTURE_MESSAGES
+            );
+            assert_eq!(node.future[&wallets[2].address()].len(), 1);
+
+            // f + 1 validators in a later round move node there, unless too far
+            for w in &wallets[1..3] {
+                let out = node
+                    .on_message(vote(w, 1, MAX_ROUNDS_AHEAD + 1, CHAIN_ID))
+                    .await
+                    .unwrap();
+                assert!(out.is_empty());
+            }
+            assert_eq!(node.machine().unwrap().round(), 0);
+
+            for w in &wallets[1..3] {
+                node.on_message(vote(w, 1, MAX_ROUNDS_AHEAD, CHAIN_ID))
+                    .await
+                    .unwrap();
+            }
+            assert_eq!(node.machine().unwrap().round(), MAX_ROUNDS_AHEAD);
+        });
+    }
+}
@@ -0,0 +1,103 @@
+use std::fmt::Debug;
+
+use ethers_core::types::{H160, H256};
+use ethers_signers::WalletError;
+use fluct_core::{EngineError, Web3Error};
+use fluct_service::{CallError, StepError};
+use thiserror::Error;
+
+use crate::Round;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    CallError(#[from] CallError),
+
+    #[error("{0}")]
+    SequencerApiError(String),
+
+    #[error(transparent)]
+    EngineError(#[from] EngineError),
+
+    #[error(transparent)]
+    Web3Error(#[from] Web3Error),
+
+    #[error(transparent)]
+    WalletError(#[from] WalletError),
+
+    #[error("Consensus not inited")]
+    NotInited,
+
+    #[error("No engine api configed")]
+    NoEngineApiConfiged,
+
+    #[error("No web3 api configed")]
+    NoWeb3ApiConfiged,
+
+    #[error("No sequencer api configed")]
+    NoSequencerApiConfiged,
+
+    #[error("No validator configed")]
+    NoValidator,
+
+    #[error("{0:?} is not a validator")]
+    NotValidator(H160),
+
+    #[error("Proposal of height {0} round {1} is not signed by proposer {2:?}")]
+    NotProposer(u64, Round, H160),
+
+    #[error("Invalid signature of message")]
+    InvalidSignature,
+
+    #[error("Block {0:?} not found in engine")]
+    BlockNotFound(H256),
+
+    #[error("Engine didn't start building payload")]
+    PayloadNotBuilt,
+
+    #[error("Payload {0:?} rejected by engine: {1}")]
+    InvalidPayload(H256, String),
+
+    #[error("Message transport closed")]
+    TransportClosed,
+
+    #[error("Failed to commit block {0}: {1}")]
+    CommitFailed(u64, String),
+}
+
+impl StepError for Error {
+    fn is_exit(&self) -> bool {
+        match self {
+            Self::CallError(CallError::ChannelClosed) => true,
+            Self::CallError(CallError::SenderReject) => false,
+            Self::SequencerApiError(_) => false,
+            Self::EngineError(_) => true,
+            Self::Web3Error(_) => true,
+            Self::WalletError(_) => false,
+            Self::NotInited => true,
+            Self::NoEngineApiConfiged => true,
+            Self::NoWeb3ApiConfiged => true,
+            Self::NoSequencerApiConfiged => true,
+            Self::NoValidator => true,
+            Self::NotValidator(_) => false,
+            Self::NotProposer(_, _, _) => false,
+            Self::InvalidSignature => false,
+            Self::BlockNotFound(_) => false,
+            Self::PayloadNotBuilt => false,
+            Self::InvalidPayload(_, _) => false,
+            Self::TransportClosed => true,
+            Self::CommitFailed(_, _) => true,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("Block can't be added without commit of validators")]
+    BlockWithoutCommit,
+
+    #[error(transparent)]
+    CallError(#[from] CallError),
+}
@@ -0,0 +1,221 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use ethers_core::types::{BlockId, Bytes, H160, H256, U256};
+use fluct_core::{
+    ConsensusGenesis, EngineApi, ExecutionPayload, ForkChoiceState, PayloadAttributes,
+    SequencerApi, Status, Transaction, Web3Api,
+};
+
+use crate::{Commit, Error, Result};
+
+/// Builds and executes blocks decided by consensus
+#[async_trait]
+pub trait BlockExecutor: Send + 'static {
+    /// Number of last committed block, consensus starts from the next one
+    async fn last_height(&mut self) -> Result<u64>;
+
+    /// Build block of height on last committed block
+    async fn build(&mut self, height: u64) -> Result<ExecutionPayload<Transaction>>;
+
+    /// Execute proposed block, return false if it is invalid
+    async fn execute(&mut self, payload: &ExecutionPayload<Transaction>) -> Result<bool>;
+
+    /// Block is committed, it is final
+    async fn commit(&mut self, commit: &Commit) -> Result<()>;
+
+    /// State of chain after last commit
+    fn chain_state(&self) -> ForkChoiceState;
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Executor over Engine API, committed block is head, safe and finalized block
+pub struct EngineExecutor<SA> {
+    pub(crate) genesis: Option<ConsensusGenesis<Transaction>>,
+    pub(crate) state: ForkChoiceState,
+    pub(crate) fee_recipient: H160,
+    pub(crate) engine_api: Option<Box<dyn EngineApi>>,
+    pub(crate) web3_api: Option<Box<dyn Web3Api>>,
+    pub(crate) sequencer_api: Option<SA>,
+    head_number: u64,
+    head_timestamp: u64,
+    head_base_fee: U256,
+}
+
+impl<SA> EngineExecutor<SA>
+where
+    SA: SequencerApi + Send + Sync + 'static,
+{
+    pub fn new(fee_recipient: H160) -> Self {
+        Self {
+            genesis: None,
+            state: Default::default(),
+            fee_recipient,
+            engine_api: None,
+            web3_api: None,
+            sequencer_api: None,
+            head_number: 0,
+            head_timestamp: 0,
+            head_base_fee: U256::zero(),
+        }
+    }
+
+    fn engine_api(&mut self) -> Result<&mut Box<dyn EngineApi>> {
+        self.engine_api.as_mut().ok_or(Error::NoEngineApiConfiged)
+    }
+
+    fn sequencer_api(&self) -> Result<&SA> {
+        self.sequencer_api
+            .as_ref()
+            .ok_or(Error::NoSequencerApiConfiged)
+    }
+
+    /// Move engine to committed head, and build payload on it
+    async fn fork_choice(&mut self, txs: Vec<Transaction>) -> Result<Option<Bytes>> {
+        let genesis = self.genesis.as_ref().ok_or(Error::NotInited)?;
+
+        let attr = PayloadAttributes {
+            timestamp: now().max(self.head_timestamp + 1).into(),
+            prev_randao: H256::zero(),
+            suggested_fee_recipient: self.fee_recipient,
+            transactions: txs,
+            gas_limit: Some(genesis.block_gas_limit),
+        };
+
+        let state = self.state.clone();
+        let head = state.head_block_hash;
+
+        let res = self.engine_api()?.engine_fork_choice(state, attr).await?;
+
+        let status = res.payload_status();
+
+        if matches!(status.status(), Status::Invalid | Status::InvalidBlockHash) {
+            let reason = status.validation_error().unwrap_or_default().to_string();
+            return Err(Error::InvalidPayload(head, reason));
+        }
+
+        Ok(res.payload_id().cloned())
+    }
+}
+
+#[async_trait]
+impl<SA> BlockExecutor for EngineExecutor<SA>
+where
+    SA: SequencerApi + Send + Sync + 'static,
+{
+    async fn last_height(&mut self) -> Result<u64> {
+        let genesis = self.genesis.as_ref().ok_or(Error::NotInited)?;
+
+        let head = if self.state.head_block_hash.is_zero() {
+            genesis.earliest_block_hash
+        } else {
+            self.state.head_block_hash
+        };
+
+        let block = self
+            .web3_api
+            .as_mut()
+            .ok_or(Error::NoWeb3ApiConfiged)?
+            .get_block(BlockId::Hash(head))
+            .await?
+            .ok_or(Error::BlockNotFound(head))?;
+
+        self.head_number = block.number.unwrap_or_default().as_u64();
+        self.head_timestamp = block.timestamp.as_u64();
+        self.head_base_fee = block.base_fee_per_gas.unwrap_or_default();
+
+        self.state = ForkChoiceState {
+            head_block_hash: head,
+            safe_block_hash: head,
+            finalized_block_hash: head,
+        };
+
+        Ok(self.head_number)
+    }
+
+    async fn build(&mut self, height: u64) -> Result<ExecutionPayload<Transaction>> {
+        let genesis = self.genesis.as_ref().ok_or(Error::NotInited)?;
+
+        let mut txs = if height == genesis.earliest_block_height + 1 {
+            genesis.transactions.clone()
+        } else {
+            Vec::new()
+        };
+
+        let (gas_limit, byte_limit) = (genesis.block_gas_limit, genesis.block_block_size);
+
+        txs.extend(
+            self.sequencer_api()?
+                .txs_for_block(gas_limit, byte_limit, self.head_base_fee)
+                .await
+                .map_err(|e| Error::SequencerApiError(e.to_string()))?,
+        );
+
+        let payload_id = self.fork_choice(txs).await?.ok_or(Error::PayloadNotBuilt)?;
+
+        Ok(self.engine_api()?.engine_get_payload(payload_id).await?)
+    }
+
+    async fn execute(&mut self, payload: &ExecutionPayload<Transaction>) -> Result<bool> {
+        if payload.parent_hash != self.state.head_block_hash
+            || payload.block_number != (self.head_number + 1).into()
+        {
+            return Ok(false);
+        }
+
+        let status = self
+            .engine_api()?
+            .engine_new_payload(payload.clone())
+            .await?;
+
+        Ok(matches!(status, Status::Valid | Status::Accepted))
+    }
+
+    async fn commit(&mut self, commit: &Commit) -> Result<()> {
+        let payload = &commit.payload;
+
+        let status = self
+            .engine_api()?
+            .engine_new_payload(payload.clone())
+            .await?;
+
+        if !matches!(status, Status::Valid | Status::Accepted) {
+            return Err(Error::InvalidPayload(
+                payload.block_hash,
+                format!("{:?}", status),
+            ));
+        }
+
+        let hash = payload.block_hash;
+
+        self.state = ForkChoiceState {
+            head_block_hash: hash,
+            safe_block_hash: hash,
+            finalized_block_hash: hash,
+        };
+        self.head_number = payload.block_number.as_u64();
+        self.head_timestamp = payload.timestamp.as_u64();
+        self.head_base_fee = payload.base_fee_per_gas;
+
+        self.fork_choice(Vec::new()).await?;
+
+        let api = self.sequencer_api()?;
+
+        for tx in &payload.transactions {
+            api.comfirm_tx(tx.hash)
+                .map_err(|e| Error::SequencerApiError(e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    fn chain_state(&self) -> ForkChoiceState {
+        self.state.clone()
+    }
+}
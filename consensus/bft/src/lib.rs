@@ -0,0 +1,36 @@
+//! Consensus with Tendermint-style BFT over a fixed validator set
+//!
+//! A height is decided in rounds of propose, prevote and precommit. Proposer of round
+//! builds block, validators prevote and precommit it, and block with precommits from
+//! more than two thirds of validators is committed and final at once. Round times out
+//! and moves on when proposer is offline or votes split.
+
+mod message;
+pub use message::*;
+
+mod validator_set;
+pub use validator_set::*;
+
+mod tendermint;
+pub use tendermint::*;
+
+mod transport;
+pub use transport::*;
+
+mod executor;
+pub use executor::*;
+
+mod config;
+pub use config::*;
+
+mod consensus;
+pub use consensus::*;
+
+mod api;
+pub use api::*;
+
+mod error;
+pub use error::*;
+
+mod types;
+pub use types::*;
@@ -0,0 +1,118 @@
+use ethers_core::{
+    types::{Signature, H160, H256},
+    utils::{keccak256, rlp::RlpStream},
+};
+use ethers_signers::LocalWallet;
+use fluct_core::{ExecutionPayload, Transaction};
+
+use crate::Result;
+
+pub type Round = u32;
+
+/// Domain of consensus message digest, a signature can't be replayed as another message
+pub const MESSAGE_DOMAIN: &[u8] = b"fluct-bft-message-v1";
+
+const KIND_PROPOSAL: u8 = 0;
+const KIND_PREVOTE: u8 = 1;
+const KIND_PRECOMMIT: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VoteKind {
+    Prevote,
+    Precommit,
+}
+
+/// Vote for block of round, `None` is vote for nil
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vote {
+    pub kind: VoteKind,
+    pub height: u64,
+    pub round: Round,
+    pub block_hash: Option<H256>,
+}
+
+/// Block proposed in round
+///
+/// `valid_round` is set when proposer re-proposes block seen prevoted in that round.
+#[derive(Debug, Clone)]
+pub struct Proposal {
+    pub height: u64,
+    pub round: Round,
+    pub valid_round: Option<Round>,
+    pub payload: ExecutionPayload<Transaction>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Proposal(Box<Proposal>),
+    Vote(Vote),
+}
+
+impl Message {
+    pub fn height(&self) -> u64 {
+        match self {
+            Self::Proposal(p) => p.height,
+            Self::Vote(v) => v.height,
+        }
+    }
+
+    pub fn round(&self) -> Round {
+        match self {
+            Self::Proposal(p) => p.round,
+            Self::Vote(v) => v.round,
+        }
+    }
+
+    /// Hash to sign,
+    /// `keccak256(rlp([MESSAGE_DOMAIN, chain_id, kind, height, round, valid_round, block_hash]))`
+    ///
+    /// `valid_round` is `round + 1` or 0 if not set, and is 0 for votes. Nil vote signs zero
+    /// hash. Proposal only signs block hash, content of block is checked by executing it.
+    pub fn digest(&self, chain_id: u64) -> H256 {
+        let (kind, valid_round, block_hash) = match self {
+            Self::Proposal(p) => (
+                KIND_PROPOSAL,
+                p.valid_round.map_or(0, |r| r + 1),
+                p.payload.block_hash,
+            ),
+            Self::Vote(v) => {
+                let kind = match v.kind {
+                    VoteKind::Prevote => KIND_PREVOTE,
+                    VoteKind::Precommit => KIND_PRECOMMIT,
+                };
+                (kind, 0, v.block_hash.unwrap_or_default())
+            }
+        };
+
+        let mut s = RlpStream::new_list(7);
+        s.append(&MESSAGE_DOMAIN);
+        s.append(&chain_id);
+        s.append(&kind);
+        s.append(&self.height());
+        s.append(&self.round());
+        s.append(&valid_round);
+        s.append(&block_hash);
+
+        keccak256(s.out()).into()
+    }
+}
+
+/// Message signed by validator
+#[derive(Debug, Clone)]
+pub struct SignedMessage {
+    pub message: Message,
+    pub signature: Signature,
+}
+
+impl SignedMessage {
+    pub fn sign(message: Message, chain_id: u64, signer: &LocalWallet) -> Result<Self> {
+        let signature = signer.sign_hash(message.digest(chain_id))?;
+
+        Ok(Self { message, signature })
+    }
+
+    /// Address signed the message on chain
+    pub fn signer(&self, chain_id: u64) -> Option<H160> {
+        self.signature.recover(self.message.digest(chain_id)).ok()
+    }
+}
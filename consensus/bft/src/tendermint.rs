@@ -0,0 +1,598 @@
+use std::{collections::HashMap, time::Duration};
+
+use ethers_core::types::{H160, H256};
+use ethers_signers::{LocalWallet, Signer};
+use fluct_core::{ExecutionPayload, Transaction};
+
+use crate::{
+    BftConfig, Error, Message, Proposal, Result, Round, SignedMessage, ValidatorSet, Vote, VoteKind,
+};
+
+/// Rounds after current one messages are taken for, later ones are dropped
+///
+/// Votes of a round are kept until height is committed, so the bound keeps a
+/// validator from filling memory with votes of far rounds.
+pub const MAX_ROUNDS_AHEAD: Round = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Step {
+    Propose,
+    Prevote,
+    Precommit,
+}
+
+/// Timeout of step, scheduled by `Output::Schedule`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeout {
+    pub height: u64,
+    pub round: Round,
+    pub step: Step,
+}
+
+/// Block decided at height, with precommits of `2f + 1` validators
+#[derive(Debug, Clone)]
+pub struct Commit {
+    pub height: u64,
+    pub round: Round,
+    pub payload: ExecutionPayload<Transaction>,
+    pub precommits: Vec<SignedMessage>,
+}
+
+/// Action asked by state machine
+#[derive(Debug)]
+pub enum Output {
+    /// Send message to other validators
+    Broadcast(SignedMessage),
+    /// Call `on_timeout` after duration
+    Schedule(Timeout, Duration),
+    /// Local node is proposer of round, call `propose` with block built
+    Build { height: u64, round: Round },
+    /// Block is final, state machine moves to next height
+    Commit(Box<Commit>),
+}
+
+struct RoundProposal {
+    payload: ExecutionPayload<Transaction>,
+    valid_round: Option<Round>,
+    /// Block is executed successfully
+    valid: bool,
+}
+
+/// State machine of Tendermint consensus
+///
+/// It does no IO, inputs are messages, timeouts and built blocks, and actions are
+/// returned as `Output`. Only messages of current height are taken, messages of later
+/// heights should be held and passed again after commit.
+///
+/// Locked and valid blocks are only kept in memory, there is no write-ahead log. A
+/// validator restarted during a height starts it over unlocked, and may prevote a
+/// block other than the one it precommitted, so the restart counts as a byzantine
+/// fault until the height is committed.
+///
+/// See [The latest gossip on BFT consensus](https://arxiv.org/abs/1807.04938).
+pub struct Tendermint {
+    chain_id: u64,
+    validators: ValidatorSet,
+    signer: Option<LocalWallet>,
+    config: BftConfig,
+    height: u64,
+    round: Round,
+    step: Step,
+    /// Not persisted, lost on restart
+    locked: Option<(Round, ExecutionPayload<Transaction>)>,
+    /// Not persisted, lost on restart
+    valid: Option<(Round, ExecutionPayload<Transaction>)>,
+    proposals: HashMap<Round, RoundProposal>,
+    votes: HashMap<(Round, VoteKind), HashMap<H160, SignedMessage>>,
+    /// Rules only fire once in a round
+    prevote_waited: bool,
+    precommit_waited: bool,
+    polka_seen: bool,
+}
+
+impl Tendermint {
+    /// Create state machine, node without signer only follows votes of validators
+    ///
+    /// Messages are signed for `chain_id`, ones of other chains don't verify.
+    pub fn new(
+        chain_id: u64,
+        validators: ValidatorSet,
+        signer: Option<LocalWallet>,
+        config: BftConfig,
+    ) -> Result<Self> {
+        if let Some(signer) = &signer {
+            if !validators.contains(&signer.address()) {
+                return Err(Error::NotValidator(signer.address()));
+            }
+        }
+
+        Ok(Self {
+            chain_id,
+            validators,
+            signer,
+            config,
+            height: 0,
+            round: 0,
+            step: Step::Propose,
+            locked: None,
+            valid: None,
+            proposals: HashMap::new(),
+            votes: HashMap::new(),
+            prevote_waited: false,
+            precommit_waited: false,
+            polka_seen: false,
+        })
+    }
+
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    pub fn round(&self) -> Round {
+        self.round
+    }
+
+    pub fn step(&self) -> Step {
+        self.step
+    }
+
+    pub fn address(&self) -> Option<H160> {
+        self.signer.as_ref().map(|s| s.address())
+    }
+
+    /// Start deciding block of height
+    pub fn start(&mut self, height: u64) -> Result<Vec<Output>> {
+        let mut out = Vec::new();
+
+        self.start_height(height, &mut out)?;
+        self.process(&mut out)?;
+
+        Ok(out)
+    }
+
+    /// Check message is signed by validator, and proposal by proposer of its round
+    pub fn verify(&self, msg: &SignedMessage) -> Result<H160> {
+        let signer = msg.signer(self.chain_id).ok_or(Error::InvalidSignature)?;
+
+        if !self.validators.contains(&signer) {
+            return Err(Error::NotValidator(signer));
+        }
+
+        if let Message::Proposal(p) = &msg.message {
+            let proposer = self.validators.proposer(p.height, p.round);
+
+            if signer != proposer {
+                return Err(Error::NotProposer(p.height, p.round, proposer));
+            }
+        }
+
+        Ok(signer)
+    }
+
+    /// Block built for `Output::Build`, ignored if round is over
+    pub fn propose(&mut self, payload: ExecutionPayload<Transaction>) -> Result<Vec<Output>> {
+        let mut out = Vec::new();
+
+        let is_proposer = self.address() == Some(self.validators.proposer(self.height, self.round));
+
+        if self.step == Step::Propose
+            && is_proposer
+            && !self.proposals.contains_key(&self.round)
+            && payload.block_number == self.height.into()
+        {
+            self.broadcast_proposal(payload, None, &mut out)?;
+            self.process(&mut out)?;
+        }
+
+        Ok(out)
+    }
+
+    /// Message is of current height, and not too many rounds ahead
+    pub fn is_current(&self, msg: &SignedMessage) -> bool {
+        msg.message.height() == self.height
+            && msg.message.round() <= self.round.saturating_add(MAX_ROUNDS_AHEAD)
+    }
+
+    /// Proposal of current height, `valid` is result of executing its block
+    ///
+    /// Proposal more than `MAX_ROUNDS_AHEAD` rounds ahead is ignored.
+    pub fn on_proposal(&mut self, msg: SignedMessage, valid: bool) -> Result<Vec<Output>> {
+        let mut out = Vec::new();
+
+        if !self.is_current(&msg) {
+            return Ok(out);
+        }
+
+        self.verify(&msg)?;
+
+        if let Message::Proposal(p) = msg.message {
+            let p = *p;
+
+            self.proposals.entry(p.round).or_insert(RoundProposal {
+                payload: p.payload,
+                valid_round: p.valid_round,
+                valid,
+            });
+
+            self.process(&mut out)?;
+        }
+
+        Ok(out)
+    }
+
+    /// Vote of current height, only first vote of validator in a step is counted
+    ///
+    /// Vote more than `MAX_ROUNDS_AHEAD` rounds ahead is ignored.
+    pub fn on_vote(&mut self, msg: SignedMessage) -> Result<Vec<Output>> {
+        let mut out = Vec::new();
+
+        if !self.is_current(&msg) {
+            return Ok(out);
+        }
+
+        let signer = self.verify(&msg)?;
+
+        if let Message::Vote(v) = &msg.message {
+            self.votes
+                .entry((v.round, v.kind))
+                .or_default()
+                .entry(signer)
+                .or_insert(msg);
+
+            self.process(&mut out)?;
+        }
+
+        Ok(out)
+    }
+
+    pub fn on_timeout(&mut self, timeout: Timeout) -> Result<Vec<Output>> {
+        let mut out = Vec::new();
+
+        if timeout.height != self.height || timeout.round != self.round {
+            return Ok(out);
+        }
+
+        match timeout.step {
+            Step::Propose if self.step == Step::Propose => {
+                self.vote(VoteKind::Prevote, None, &mut out)?;
+                self.step = Step::Prevote;
+            }
+            Step::Prevote if self.step == Step::Prevote => {
+                self.vote(VoteKind::Precommit, None, &mut out)?;
+                self.step = Step::Precommit;
+            }
+            Step::Precommit => self.start_round(self.round + 1, &mut out)?,
+            _ => {}
+        }
+
+        self.process(&mut out)?;
+
+        Ok(out)
+    }
+
+    fn start_height(&mut self, height: u64, out: &mut Vec<Output>) -> Result<()> {
+        self.height = height;
+        self.locked = None;
+        self.valid = None;
+        self.proposals.clear();
+        self.votes.clear();
+
+        self.start_round(0, out)
+    }
+
+    fn start_round(&mut self, round: Round, out: &mut Vec<Output>) -> Result<()> {
+        self.round = round;
+        self.step = Step::Propose;
+        self.prevote_waited = false;
+        self.precommit_waited = false;
+        self.polka_seen = false;
+
+        self.schedule(Step::Propose, out);
+
+        if self.address() != Some(self.validators.proposer(self.height, round)) {
+            return Ok(());
+        }
+
+        match self.valid.clone() {
+            Some((valid_round, payload)) => {
+                self.broadcast_proposal(payload, Some(valid_round), out)?
+            }
+            None => out.push(Output::Build {
+                height: self.height,
+                round,
+            }),
+        }
+
+        Ok(())
+    }
+
+    fn schedule(&self, step: Step, out: &mut Vec<Output>) {
+        let timeout = Timeout {
+            height: self.height,
+            round: self.round,
+            step,
+        };
+
+        out.push(Output::Schedule(
+            timeout,
+            self.config.timeout(step, self.round),
+        ));
+    }
+
+    fn broadcast_proposal(
+        &mut self,
+        payload: ExecutionPayload<Transaction>,
+        valid_round: Option<Round>,
+        out: &mut Vec<Output>,
+    ) -> Result<()> {
+        let Some(signer) = &self.signer else {
+            return Ok(());
+        };
+
+        let proposal = Proposal {
+            height: self.height,
+            round: self.round,
+            valid_round,
+            payload: payload.clone(),
+        };
+
+        let msg =
+            SignedMessage::sign(Message::Proposal(Box::new(proposal)), self.chain_id, signer)?;
+
+        self.proposals.insert(
+            self.round,
+            RoundProposal {
+                payload,
+                valid_round,
+                valid: true,
+            },
+        );
+
+        out.push(Output::Broadcast(msg));
+
+        Ok(())
+    }
+
+    fn vote(
+        &mut self,
+        kind: VoteKind,
+        block_hash: Option<H256>,
+        out: &mut Vec<Output>,
+    ) -> Result<()> {
+        let Some(signer) = &self.signer else {
+            return Ok(());
+        };
+
+        let vote = Vote {
+            kind,
+            height: self.height,
+            round: self.round,
+            block_hash,
+        };
+
+        let msg = SignedMessage::sign(Message::Vote(vote), self.chain_id, signer)?;
+
+        self.votes
+            .entry((self.round, kind))
+            .or_default()
+            .insert(signer.address(), msg.clone());
+
+        out.push(Output::Broadcast(msg));
+
+        Ok(())
+    }
+
+    fn vote_hash(msg: &SignedMessage) -> Option<H256> {
+        match &msg.message {
+            Message::Vote(v) => v.block_hash,
+            Message::Proposal(_) => None,
+        }
+    }
+
+    /// Count of votes for block, or for nil if `block_hash` is `None`
+    fn count(&self, round: Round, kind: VoteKind, block_hash: Option<H256>) -> usize {
+        self.votes.get(&(round, kind)).map_or(0, |votes| {
+            votes
+                .values()
+                .filter(|msg| Self::vote_hash(msg) == block_hash)
+                .count()
+        })
+    }
+
+    fn total(&self, round: Round, kind: VoteKind) -> usize {
+        self.votes
+            .get(&(round, kind))
+            .map_or(0, |votes| votes.len())
+    }
+
+    /// Apply rules until nothing changes
+    fn process(&mut self, out: &mut Vec<Output>) -> Result<()> {
+        loop {
+            let fired = self.try_commit(out)?
+                || self.try_skip_round(out)?
+                || self.try_prevote(out)?
+                || self.try_wait_prevote(out)
+                || self.try_polka(out)?
+                || self.try_precommit_nil(out)?
+                || self.try_wait_precommit(out);
+
+            if !fired {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Proposal of any round with `2f + 1` precommits is decided
+    fn try_commit(&mut self, out: &mut Vec<Output>) -> Result<bool> {
+        let quorum = self.validators.quorum();
+
+        let decided = self.proposals.iter().find(|(round, p)| {
+            p.valid
+                && self.count(**round, VoteKind::Precommit, Some(p.payload.block_hash)) >= quorum
+        });
+
+        let Some((&round, proposal)) = decided else {
+            return Ok(false);
+        };
+
+        let hash = proposal.payload.block_hash;
+
+        let precommits = self.votes[&(round, VoteKind::Precommit)]
+            .values()
+            .filter(|msg| Self::vote_hash(msg) == Some(hash))
+            .cloned()
+            .collect();
+
+        out.push(Output::Commit(Box::new(Commit {
+            height: self.height,
+            round,
+            payload: proposal.payload.clone(),
+            precommits,
+        })));
+
+        self.start_height(self.height + 1, out)?;
+
+        Ok(true)
+    }
+
+    /// Move to later round when `f + 1` validators are there
+    fn try_skip_round(&mut self, out: &mut Vec<Output>) -> Result<bool> {
+        let mut senders: HashMap<Round, Vec<H160>> = HashMap::new();
+
+        for ((round, _), votes) in &self.votes {
+            if *round > self.round {
+                senders.entry(*round).or_default().extend(votes.keys());
+            }
+        }
+
+        for round in self.proposals.keys().filter(|r| **r > self.round) {
+            let proposer = self.validators.proposer(self.height, *round);
+            senders.entry(*round).or_default().push(proposer);
+        }
+
+        let honest = self.validators.honest();
+
+        let round = senders
+            .into_iter()
+            .filter_map(|(round, mut s)| {
+                s.sort();
+                s.dedup();
+                (s.len() >= honest).then_some(round)
+            })
+            .max();
+
+        let Some(round) = round else {
+            return Ok(false);
+        };
+
+        self.start_round(round, out)?;
+
+        Ok(true)
+    }
+
+    /// Prevote proposal of round, or nil if it is invalid or conflicts with lock
+    fn try_prevote(&mut self, out: &mut Vec<Output>) -> Result<bool> {
+        if self.step != Step::Propose {
+            return Ok(false);
+        }
+
+        let Some(p) = self.proposals.get(&self.round) else {
+            return Ok(false);
+        };
+
+        let hash = p.payload.block_hash;
+        let locked = self.locked.as_ref().map(|(r, v)| (*r, v.block_hash));
+
+        let accept = match p.valid_round {
+            None => locked.is_none_or(|(_, h)| h == hash),
+            Some(vr)
+                if vr < self.round
+                    && self.count(vr, VoteKind::Prevote, Some(hash))
+                        >= self.validators.quorum() =>
+            {
+                locked.is_none_or(|(r, h)| r <= vr || h == hash)
+            }
+            Some(_) => return Ok(false),
+        };
+
+        let block_hash = (p.valid && accept).then_some(hash);
+
+        self.vote(VoteKind::Prevote, block_hash, out)?;
+        self.step = Step::Prevote;
+
+        Ok(true)
+    }
+
+    fn try_wait_prevote(&mut self, out: &mut Vec<Output>) -> bool {
+        if self.step != Step::Prevote
+            || self.prevote_waited
+            || self.total(self.round, VoteKind::Prevote) < self.validators.quorum()
+        {
+            return false;
+        }
+
+        self.prevote_waited = true;
+        self.schedule(Step::Prevote, out);
+
+        true
+    }
+
+    /// `2f + 1` prevotes for proposal of round, lock it and precommit
+    fn try_polka(&mut self, out: &mut Vec<Output>) -> Result<bool> {
+        if self.polka_seen || self.step < Step::Prevote {
+            return Ok(false);
+        }
+
+        let Some(p) = self.proposals.get(&self.round) else {
+            return Ok(false);
+        };
+
+        let hash = p.payload.block_hash;
+
+        if !p.valid
+            || self.count(self.round, VoteKind::Prevote, Some(hash)) < self.validators.quorum()
+        {
+            return Ok(false);
+        }
+
+        let payload = p.payload.clone();
+
+        self.polka_seen = true;
+
+        if self.step == Step::Prevote {
+            self.locked = Some((self.round, payload.clone()));
+            self.vote(VoteKind::Precommit, Some(hash), out)?;
+            self.step = Step::Precommit;
+        }
+
+        self.valid = Some((self.round, payload));
+
+        Ok(true)
+    }
+
+    fn try_precommit_nil(&mut self, out: &mut Vec<Output>) -> Result<bool> {
+        if self.step != Step::Prevote
+            || self.count(self.round, VoteKind::Prevote, None) < self.validators.quorum()
+        {
+            return Ok(false);
+        }
+
+        self.vote(VoteKind::Precommit, None, out)?;
+        self.step = Step::Precommit;
+
+        Ok(true)
+    }
+
+    fn try_wait_precommit(&mut self, out: &mut Vec<Output>) -> bool {
+        if self.precommit_waited
+            || self.total(self.round, VoteKind::Precommit) < self.validators.quorum()
+        {
+            return false;
+        }
+
+        self.precommit_waited = true;
+        self.schedule(Step::Precommit, out);
+
+        true
+    }
+}
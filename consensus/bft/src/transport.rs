@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+use tokio::sync::broadcast;
+
+use crate::{Error, Result, SignedMessage};
+
+/// Transport of consensus messages between validators
+#[async_trait]
+pub trait MessageTransport: Send + 'static {
+    /// Send message to all validators
+    fn broadcast(&mut self, message: SignedMessage) -> Result<()>;
+
+    /// Receive next message from any validator
+    async fn recv(&mut self) -> Result<SignedMessage>;
+}
+
+/// In-process network of validators
+#[derive(Clone)]
+pub struct LocalNetwork {
+    sender: broadcast::Sender<SignedMessage>,
+}
+
+impl LocalNetwork {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+
+        Self { sender }
+    }
+
+    /// Transport of a node joining network
+    pub fn join(&self) -> LocalTransport {
+        LocalTransport {
+            sender: self.sender.clone(),
+            receiver: self.sender.subscribe(),
+        }
+    }
+}
+
+/// Transport over `LocalNetwork`, node receives messages sent by itself too
+pub struct LocalTransport {
+    sender: broadcast::Sender<SignedMessage>,
+    receiver: broadcast::Receiver<SignedMessage>,
+}
+
+#[async_trait]
+impl MessageTransport for LocalTransport {
+    fn broadcast(&mut self, message: SignedMessage) -> Result<()> {
+        self.sender
+            .send(message)
+            .map_err(|_| Error::TransportClosed)?;
+
+        Ok(())
+    }
+
+    async fn recv(&mut self) -> Result<SignedMessage> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(message) => return Ok(message),
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    log::warn!("Transport lagged, {} messages lost", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => return Err(Error::TransportClosed),
+            }
+        }
+    }
+}
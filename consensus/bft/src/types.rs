@@ -0,0 +1,9 @@
+use fluct_core::ForkChoiceState;
+
+pub enum ApiRequest {
+    GetChainState,
+}
+
+pub enum ApiResponse {
+    GetChainState(ForkChoiceState),
+}
@@ -0,0 +1,48 @@
+use ethers_core::types::H160;
+
+use crate::{Error, Result, Round};
+
+/// Validators with equal voting power, up to `f` of `3f + 1` can be faulty
+#[derive(Debug, Clone)]
+pub struct ValidatorSet {
+    validators: Vec<H160>,
+}
+
+impl ValidatorSet {
+    pub fn new(validators: Vec<H160>) -> Result<Self> {
+        if validators.is_empty() {
+            return Err(Error::NoValidator);
+        }
+
+        Ok(Self { validators })
+    }
+
+    pub fn len(&self) -> usize {
+        self.validators.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.validators.is_empty()
+    }
+
+    pub fn contains(&self, address: &H160) -> bool {
+        self.validators.contains(address)
+    }
+
+    /// Proposer of round, rotates by height and round
+    pub fn proposer(&self, height: u64, round: Round) -> H160 {
+        let index = (height + round as u64) % self.validators.len() as u64;
+
+        self.validators[index as usize]
+    }
+
+    /// Votes more than two thirds, `2f + 1`
+    pub fn quorum(&self) -> usize {
+        self.validators.len() * 2 / 3 + 1
+    }
+
+    /// Votes ensure one honest validator, `f + 1`
+    pub fn honest(&self) -> usize {
+        self.validators.len() - self.quorum() + 1
+    }
+}
@@ -0,0 +1,48 @@
+use async_trait::async_trait;
+use fluct_core::{Block, BlockHeader, ConsensusApi, ForkChoiceState};
+use fluct_service::Caller;
+
+use crate::{execution_hash, verify_seal, ApiError, ApiRequest, ApiResponse};
+
+#[derive(Clone)]
+pub struct PowConsensusApi {
+    pub(crate) caller: Caller<ApiRequest, ApiResponse>,
+}
+
+#[async_trait]
+impl ConsensusApi for PowConsensusApi {
+    type Error = ApiError;
+
+    /// Add sealed block, hash of block is the one known by execution engine
+    async fn add_block(&mut self, block: Block) -> Result<(), Self::Error> {
+        let mut block = block;
+
+        let BlockHeader::Ethereum(header) = BlockHeader::from(block.clone());
+
+        if !verify_seal(&header) {
+            return Err(ApiError::InvalidSeal(header.hash()));
+        }
+
+        let ohash = execution_hash(&header);
+
+        if let Some(hash) = block.hash() {
+            if hash != ohash {
+                return Err(ApiError::FailedVerifyBlockHash(ohash, hash));
+            }
+        }
+
+        block.fill_hash(ohash);
+
+        self.caller.send(ApiRequest::AddBlock(Box::new(block)))?;
+
+        Ok(())
+    }
+
+    async fn chain_state(&self) -> Result<ForkChoiceState, Self::Error> {
+        let resp = self.caller.call(ApiRequest::GetChainState).await?;
+
+        match resp {
+            ApiResponse::GetChainState(v) => Ok(v),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use ethers_core::types::H160;
+
+use crate::DifficultyConfig;
+
+/// Default count of nonces tried in one step, requests are handled between steps
+pub const DEFAULT_NONCES_PER_STEP: u64 = 10_000;
+
+/// Default count of blocks kept in fork tree before head
+pub const DEFAULT_PRUNE_DEPTH: u64 = 256;
+
+/// Config of PoW consensus
+#[derive(Debug, Clone)]
+pub struct PowConfig {
+    /// Fee recipient of mined block, node only follows heaviest fork if not set
+    pub miner: Option<H160>,
+
+    pub difficulty: DifficultyConfig,
+
+    /// Count of nonces tried in one step
+    pub nonces_per_step: u64,
+
+    /// Path of chain store, seals and total difficulty are kept across restarts if set
+    ///
+    /// Without it, fork tree is rooted at head after restart and total difficulty
+    /// counts from there.
+    pub store: Option<PathBuf>,
+
+    /// Count of blocks kept in fork tree before head, reorgs deeper than it are not
+    /// followed
+    pub prune_depth: u64,
+}
+
+impl Default for PowConfig {
+    fn default() -> Self {
+        Self {
+            miner: None,
+            difficulty: Default::default(),
+            nonces_per_step: DEFAULT_NONCES_PER_STEP,
+            store: None,
+            prune_depth: DEFAULT_PRUNE_DEPTH,
+        }
+    }
+}
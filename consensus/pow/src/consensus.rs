@@ -0,0 +1,997 @@
+use std::{
+    collections::HashSet,
+    io,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use ethers_core::types::{Block as EthBlock, BlockId, Bytes, H256};
+use fluct_core::{
+    Block, BlockHeader, ConsensusGenesis, ConsensusService, EngineApi, EthereumHeader,
+    ExecutionPayload, ForkChoiceState, PayloadAttributes, SequencerApi, Service, Status,
+    Transaction, Web3Api,
+};
+use fluct_service::{
+    local_rpc, AsyncStepService, AsyncStepServiceWapper1, CallError, Caller, Hander,
+};
+use futures_util::FutureExt;
+use tokio::sync::oneshot;
+
+use crate::{
+    execution_hash, search_nonce, store::ChainStore, ApiRequest, ApiResponse, Error, ForkTree,
+    PowConfig, PowConsensusApi, Result,
+};
+
+/// Block built by engine, waiting for nonce
+struct Work {
+    header: EthereumHeader,
+    block: EthBlock<Transaction>,
+    next_nonce: u64,
+}
+
+/// Payload to execute sealed block in engine
+fn execution_payload(
+    header: &EthereumHeader,
+    transactions: Vec<Transaction>,
+) -> ExecutionPayload<Transaction> {
+    let h = &header.header;
+
+    ExecutionPayload {
+        parent_hash: h.parent_hash,
+        fee_recipient: h.beneficiary,
+        state_root: h.state_root,
+        receipts_root: h.receipts_root,
+        logs_bloom: Bytes::from(h.logs_bloom.as_bytes().to_vec()),
+        prev_randao: h.mix_hash,
+        block_number: h.number,
+        gas_limit: h.gas_limit,
+        gas_used: h.gas_used,
+        timestamp: h.timestamp.into(),
+        extra_data: h.extra_data.clone().into(),
+        base_fee_per_gas: header.base_fee_per_gas.unwrap_or_default(),
+        block_hash: execution_hash(header),
+        transactions,
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+pub struct PowConsensus<SA> {
+    handler: Hander<ApiRequest, ApiResponse>,
+    caller: Caller<ApiRequest, ApiResponse>,
+    config: PowConfig,
+    genesis: Option<ConsensusGenesis<Transaction>>,
+    state: ForkChoiceState,
+    tree: Option<ForkTree>,
+    store: Option<ChainStore>,
+    work: Option<Work>,
+    engine_api: Option<Box<dyn EngineApi>>,
+    web3_api: Option<Box<dyn Web3Api>>,
+    sequencer_api: Option<SA>,
+}
+
+impl<SA> PowConsensus<SA>
+where
+    SA: SequencerApi + Send + Sync,
+{
+    pub fn new(config: PowConfig) -> Self {
+        let (handler, caller) = local_rpc();
+
+        let store = config
+            .store
+            .as_ref()
+            .and_then(|path| match ChainStore::open(path) {
+                Ok(store) => Some(store),
+                Err(e) => {
+                    log::error!("Failed to open chain store {}: {}", path.display(), e);
+                    None
+                }
+            });
+
+        Self {
+            handler,
+            caller,
+            config,
+            genesis: None,
+            state: Default::default(),
+            tree: None,
+            store,
+            work: None,
+            engine_api: None,
+            web3_api: None,
+            sequencer_api: None,
+        }
+    }
+
+    fn engine_api(&mut self) -> Result<&mut Box<dyn EngineApi>> {
+        self.engine_api.as_mut().ok_or(Error::NoEngineApiConfiged)
+    }
+
+    fn web3_api(&mut self) -> Result<&mut Box<dyn Web3Api>> {
+        self.web3_api.as_mut().ok_or(Error::NoWeb3ApiConfiged)
+    }
+
+    fn sequencer_api(&self) -> Result<&SA> {
+        self.sequencer_api
+            .as_ref()
+            .ok_or(Error::NoSequencerApiConfiged)
+    }
+
+    fn tree(&self) -> Result<&ForkTree> {
+        self.tree.as_ref().ok_or(Error::NotInited)
+    }
+
+    async fn get_block(&mut self, hash: H256) -> Result<EthBlock<Transaction>> {
+        self.web3_api()?
+            .get_block(BlockId::Hash(hash))
+            .await?
+            .ok_or(Error::BlockNotFound(hash))
+    }
+
+    /// Write chain store, failure is logged and consensus keeps working
+    fn store(&mut self, f: impl FnOnce(&mut ChainStore) -> Result<()>) {
+        if let Some(store) = &mut self.store {
+            if let Err(e) = f(store) {
+                log::error!("Failed to write chain store: {}", e);
+            }
+        }
+    }
+
+    /// Rebuild fork tree from chain store, none if it doesn't know chain head
+    fn restore_tree(&self) -> Result<Option<ForkTree>> {
+        let Some(store) = &self.store else {
+            return Ok(None);
+        };
+
+        let head = self.state.head_block_hash;
+
+        let tree = ForkTree::restore(self.config.difficulty.clone(), store.load()?, &head);
+
+        match tree {
+            Some(tree) if tree.contains(&head) => Ok(Some(tree)),
+            _ => {
+                log::warn!("Chain store doesn't know head {:?}, root tree at it", head);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Restore fork tree from chain store, or root it at chain head, or earliest block
+    /// of genesis on new chain
+    ///
+    /// Total difficulty is counted from the root, blocks before it are not compared.
+    async fn load_tree(&mut self) -> Result<()> {
+        if !self.state.head_block_hash.is_zero() {
+            if let Some(tree) = self.restore_tree()? {
+                let head = tree.head();
+
+                log::info!(
+                    "Restore fork tree, head: {}, total difficulty: {}",
+                    head.number,
+                    head.total_difficulty
+                );
+
+                self.tree = Some(tree);
+
+                return Ok(());
+            }
+        }
+
+        let genesis = self.genesis.as_ref().ok_or(Error::NotInited)?;
+
+        let root = if self.state.head_block_hash.is_zero() {
+            genesis.earliest_block_hash
+        } else {
+            self.state.head_block_hash
+        };
+
+        let block = self.get_block(root).await?;
+
+        let number = block.number.unwrap_or_default().as_u64();
+
+        let tree = ForkTree::new(
+            self.config.difficulty.clone(),
+            root,
+            number,
+            block.timestamp.as_u64(),
+        );
+
+        let nodes = tree.nodes();
+        self.store(|store| store.compact(&nodes));
+
+        self.tree = Some(tree);
+
+        if self.state.head_block_hash.is_zero() {
+            self.state = ForkChoiceState {
+                head_block_hash: root,
+                safe_block_hash: root,
+                finalized_block_hash: root,
+            };
+        }
+
+        Ok(())
+    }
+
+    fn next_timestamp(&self) -> Result<u64> {
+        Ok(now().max(self.tree()?.head().timestamp + 1))
+    }
+
+    /// Move engine head to head of fork tree, and build payload on it
+    ///
+    /// PoW never finalizes, only head and safe block follow the heaviest fork.
+    async fn fork_choice(&mut self, txs: Vec<Transaction>) -> Result<Option<Bytes>> {
+        let head = self.tree()?.head().hash;
+        let timestamp = self.next_timestamp()?;
+
+        let genesis = self.genesis.as_ref().ok_or(Error::NotInited)?;
+
+        let attr = PayloadAttributes {
+            timestamp: timestamp.into(),
+            prev_randao: H256::zero(),
+            suggested_fee_recipient: self.config.miner.unwrap_or_default(),
+            transactions: txs,
+            gas_limit: Some(genesis.block_gas_limit),
+        };
+
+        self.state.head_block_hash = head;
+        self.state.safe_block_hash = head;
+
+        let state = self.state.clone();
+
+        let res = self.engine_api()?.engine_fork_choice(state, attr).await?;
+
+        let status = res.payload_status();
+
+        if matches!(status.status(), Status::Invalid | Status::InvalidBlockHash) {
+            let reason = status.validation_error().unwrap_or_default().to_string();
+            return Err(Error::InvalidPayload(head, reason));
+        }
+
+        Ok(res.payload_id().cloned())
+    }
+
+    async fn new_payload(&mut self, payload: ExecutionPayload<Transaction>) -> Result<()> {
+        let hash = payload.block_hash;
+
+        let status = self.engine_api()?.engine_new_payload(payload).await?;
+
+        match status {
+            Status::Valid | Status::Accepted => Ok(()),
+            s => Err(Error::InvalidPayload(hash, format!("{:?}", s))),
+        }
+    }
+
+    /// Insert sealed and executed block into fork tree, switch to it if it is heaviest
+    ///
+    /// On reorg, transactions of every joining block are confirmed, and transactions
+    /// only in leaving blocks are given back to sequencer. New block is recorded in
+    /// chain store before engine moves to it.
+    async fn accept(&mut self, header: &EthereumHeader, txs: &[Transaction]) -> Result<()> {
+        let tree = self.tree.as_mut().ok_or(Error::NotInited)?;
+        let old_head = tree.head().hash;
+
+        let hash = execution_hash(header);
+        let known = tree.contains(&hash);
+
+        let is_head = tree.insert(header, now())?;
+
+        if !known {
+            if let Some(node) = tree.get(&hash).cloned() {
+                self.store(|store| store.append(&node));
+            }
+        }
+
+        if !is_head {
+            return Ok(());
+        }
+
+        let tree = self.tree()?;
+        let (retracted, mut enacted) = tree.route(&old_head, &hash);
+        enacted.pop();
+
+        self.fork_choice(Vec::new()).await?;
+
+        let mut confirmed = Vec::new();
+
+        for hash in enacted {
+            confirmed.extend(self.get_block(hash).await?.transactions);
+        }
+
+        confirmed.extend_from_slice(txs);
+
+        let mut orphaned = Vec::new();
+
+        for hash in retracted {
+            orphaned.extend(self.get_block(hash).await?.transactions);
+        }
+
+        let confirmed_hashes: HashSet<H256> = confirmed.iter().map(|tx| tx.hash).collect();
+
+        let api = self.sequencer_api()?;
+
+        for tx in confirmed {
+            api.comfirm_tx(tx.hash)
+                .map_err(|e| Error::SequencerApiError(e.to_string()))?;
+        }
+
+        for tx in orphaned {
+            if confirmed_hashes.contains(&tx.hash) {
+                continue;
+            }
+
+            let txhash = tx.hash;
+
+            if let Err(e) = api.broadcast_tx(tx).await {
+                log::debug!("Orphaned transaction {:?} dropped: {}", txhash, e);
+            }
+        }
+
+        self.prune();
+
+        Ok(())
+    }
+
+    /// Move root of fork tree up when it's twice prune depth before head
+    ///
+    /// Tree is pruned in batches, chain store is rewritten each time.
+    fn prune(&mut self) {
+        let depth = self.config.prune_depth;
+
+        let Some(tree) = self.tree.as_mut() else {
+            return;
+        };
+
+        if tree.head().number - tree.root().number <= depth.saturating_mul(2) {
+            return;
+        }
+
+        if tree.prune(depth) {
+            let nodes = tree.nodes();
+            self.store(|store| store.compact(&nodes));
+        }
+    }
+
+    /// Build block on head, start searching nonce
+    async fn build(&mut self) -> Result<()> {
+        let tree = self.tree()?;
+        let head = tree.head().clone();
+
+        let genesis = self.genesis.as_ref().ok_or(Error::NotInited)?;
+
+        let mut txs = if head.number == genesis.earliest_block_height {
+            genesis.transactions.clone()
+        } else {
+            Vec::new()
+        };
+
+        let (gas_limit, byte_limit) = (genesis.block_gas_limit, genesis.block_block_size);
+
+        let base_fee = self
+            .get_block(head.hash)
+            .await?
+            .base_fee_per_gas
+            .unwrap_or_default();
+
+        txs.extend(
+            self.sequencer_api()?
+                .txs_for_block(gas_limit, byte_limit, base_fee)
+                .await
+                .map_err(|e| Error::SequencerApiError(e.to_string()))?,
+        );
+
+        let payload_id = self.fork_choice(txs).await?.ok_or(Error::PayloadNotBuilt)?;
+
+        let payload = self.engine_api()?.engine_get_payload(payload_id).await?;
+        let hash = payload.block_hash;
+
+        self.new_payload(payload).await?;
+
+        let block = self.get_block(hash).await?;
+
+        let BlockHeader::Ethereum(mut header) = BlockHeader::from(block.clone());
+
+        if execution_hash(&header) != hash {
+            return Err(Error::InvalidPayload(hash, "header hash mismatch".into()));
+        }
+
+        let timestamp = header.header.timestamp;
+        header.header.difficulty = self.tree()?.next_difficulty(&head, timestamp);
+
+        self.work = Some(Work {
+            header,
+            block,
+            next_nonce: 0,
+        });
+
+        Ok(())
+    }
+
+    /// Try a round of nonces, work on stale head is dropped
+    async fn mine(&mut self) -> Result<()> {
+        let head = self.tree()?.head().hash;
+
+        if let Some(work) = &self.work {
+            if work.header.header.parent_hash != head {
+                self.work = None;
+            }
+        }
+
+        let Some(work) = self.work.as_mut() else {
+            return self.build().await;
+        };
+
+        let count = self.config.nonces_per_step;
+
+        if search_nonce(&mut work.header, work.next_nonce, count).is_none() {
+            work.next_nonce = work.next_nonce.saturating_add(count);
+            return Ok(());
+        }
+
+        let Some(Work { header, block, .. }) = self.work.take() else {
+            return Ok(());
+        };
+
+        log::info!(
+            "Mined block {}, hash: {:?}, difficulty: {}",
+            header.header.number,
+            block.hash.unwrap_or_default(),
+            header.header.difficulty
+        );
+
+        self.accept(&header, &block.transactions).await
+    }
+
+    /// Execute block from other node, and choose fork
+    async fn add_block(&mut self, block: Block) -> Result<()> {
+        let Block::Ethereum(block) = block;
+
+        let BlockHeader::Ethereum(header) = BlockHeader::from(block.clone());
+
+        let tree = self.tree()?;
+
+        if tree.contains(&execution_hash(&header)) {
+            return Ok(());
+        }
+
+        tree.validate(&header, now())?;
+
+        self.new_payload(execution_payload(&header, block.transactions.clone()))
+            .await?;
+
+        self.accept(&header, &block.transactions).await
+    }
+
+    async fn handle(
+        &mut self,
+        req: ApiRequest,
+        resper: Option<oneshot::Sender<ApiResponse>>,
+    ) -> Result<()> {
+        match req {
+            ApiRequest::AddBlock(block) => self.add_block(*block).await?,
+            ApiRequest::GetChainState => {
+                if let Some(resper) = resper {
+                    resper
+                        .send(ApiResponse::GetChainState(self.state.clone()))
+                        .map_err(|_| CallError::ChannelClosed)?;
+                } else {
+                    log::warn!("Use call method to get chain state")
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn _step(&mut self) -> Result<()> {
+        if self.tree.is_none() {
+            self.load_tree().await?;
+        }
+
+        if self.config.miner.is_none() {
+            let (req, resper) = self.handler.recv().await?;
+            return self.handle(req, resper).await;
+        }
+
+        while let Some(r) = self.handler.recv().now_or_never() {
+            let (req, resper) = r?;
+            self.handle(req, resper).await?;
+        }
+
+        self.mine().await
+    }
+}
+
+#[async_trait]
+impl<SA> AsyncStepService for PowConsensus<SA>
+where
+    SA: SequencerApi + Send + Sync,
+{
+    type Error = Error;
+
+    async fn step(&mut self) -> Result<()> {
+        self._step().await
+    }
+}
+
+pub struct PowConsensusService<SA>(AsyncStepServiceWapper1<PowConsensus<SA>>);
+
+impl<SA> PowConsensusService<SA>
+where
+    SA: SequencerApi + Send + Sync,
+{
+    pub fn new(config: PowConfig) -> Self {
+        Self(AsyncStepServiceWapper1::new(PowConsensus::new(config)))
+    }
+
+    pub fn api(&self) -> PowConsensusApi {
+        PowConsensusApi {
+            caller: self.0.service0().caller.clone(),
+        }
+    }
+}
+
+impl<SA> Service for PowConsensusService<SA>
+where
+    SA: SequencerApi + Send + Sync,
+{
+    type Error = io::Error;
+
+    fn start(&mut self) -> std::result::Result<(), Self::Error> {
+        self.0.start()
+    }
+
+    fn stop(&mut self) -> std::result::Result<(), Self::Error> {
+        self.0.stop()
+    }
+}
+
+impl<SA> ConsensusService<SA> for PowConsensusService<SA>
+where
+    SA: SequencerApi + Send + Sync,
+{
+    fn init(
+        &mut self,
+        genesis: ConsensusGenesis<Transaction>,
+        state: ForkChoiceState,
+    ) -> std::result::Result<(), Self::Error> {
+        let consensus = self.0.service0_mut();
+
+        consensus.genesis = Some(genesis);
+        consensus.state = state;
+
+        Ok(())
+    }
+
+    fn set_api(&mut self, engine_api: impl EngineApi, web3_api: impl Web3Api, seqencer_api: SA) {
+        let consensus = self.0.service0_mut();
+
+        consensus.engine_api = Some(Box::new(engine_api));
+        consensus.web3_api = Some(Box::new(web3_api));
+        consensus.sequencer_api = Some(seqencer_api);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        io,
+        path::PathBuf,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use ethers_core::types::{
+        Block as EthBlock, BlockId, Bytes, SyncingStatus, TransactionReceipt, H160, H256, U256,
+    };
+    use fluct_core::{
+        Block, ConsensusGenesis, EngineApi, EngineError, EthereumHeader, ExecutionPayload,
+        ForkChoiceResult, ForkChoiceState, Header, PayloadAttributes, PreConfirmation,
+        SequencerApi, Status, Transaction, TxAdmission, Web3Api, Web3Error,
+    };
+
+    use crate::{
+        execution_hash, search_nonce, store::ChainStore, DifficultyConfig, Error, PowConfig,
+    };
+
+    use super::PowConsensus;
+
+    type Blocks = Arc<Mutex<HashMap<H256, EthBlock<Transaction>>>>;
+
+    /// Engine accepting every block, never builds payload
+    struct MockEngineApi;
+
+    #[async_trait]
+    impl EngineApi for MockEngineApi {
+        async fn engine_fork_choice(
+            &mut self,
+            _state: ForkChoiceState,
+            _attr: PayloadAttributes<Transaction>,
+        ) -> Result<ForkChoiceResult, EngineError> {
+            let res = r#"{"payloadStatus":{"status":"VALID"},"payloadId":null}"#;
+            Ok(serde_json::from_str(res).unwrap())
+        }
+
+        async fn engine_new_payload(
+            &mut self,
+            _payload: ExecutionPayload<Transaction>,
+        ) -> Result<Status, EngineError> {
+            Ok(Status::Valid)
+        }
+
+        async fn engine_get_payload(
+            &mut self,
+            _payload_id: Bytes,
+        ) -> Result<ExecutionPayload<Transaction>, EngineError> {
+            Err(EngineError::EmptyResponse)
+        }
+    }
+
+    /// Web3 api serving blocks known by engine
+    struct MockWeb3Api(Blocks);
+
+    #[async_trait]
+    impl Web3Api for MockWeb3Api {
+        async fn block_number(&mut self) -> Result<u64, Web3Error> {
+            Ok(0)
+        }
+
+        async fn chain_id(&mut self) -> Result<u64, Web3Error> {
+            Ok(901)
+        }
+
+        async fn get_block(
+            &mut self,
+            block: BlockId,
+        ) -> Result<Option<EthBlock<Transaction>>, Web3Error> {
+            let BlockId::Hash(hash) = block else {
+                return Ok(None);
+            };
+
+            Ok(self.0.lock().unwrap().get(&hash).cloned())
+        }
+
+        async fn get_transaction(&mut self, _hash: H256) -> Result<Option<Transaction>, Web3Error> {
+            Ok(None)
+        }
+
+        async fn get_transaction_receipt(
+            &mut self,
+            _hash: H256,
+        ) -> Result<Option<TransactionReceipt>, Web3Error> {
+            Ok(None)
+        }
+
+        async fn syncing(&mut self) -> Result<SyncingStatus, Web3Error> {
+            Ok(SyncingStatus::IsFalse)
+        }
+
+        async fn balance(
+            &mut self,
+            _address: H160,
+            _block: Option<BlockId>,
+        ) -> Result<U256, Web3Error> {
+            Ok(U256::zero())
+        }
+
+        async fn transaction_count(
+            &mut self,
+            _address: H160,
+            _block: Option<BlockId>,
+        ) -> Result<U256, Web3Error> {
+            Ok(U256::zero())
+        }
+
+        async fn code(
+            &mut self,
+            _address: H160,
+            _block: Option<BlockId>,
+        ) -> Result<Bytes, Web3Error> {
+            Ok(Bytes::new())
+        }
+
+        async fn storage_at(
+            &mut self,
+            _address: H160,
+            _index: H256,
+            _block: Option<BlockId>,
+        ) -> Result<H256, Web3Error> {
+            Ok(H256::zero())
+        }
+    }
+
+    /// Sequencer recording confirmed and broadcast transactions
+    #[derive(Clone, Default)]
+    struct MockSequencerApi {
+        confirmed: Arc<Mutex<Vec<H256>>>,
+        broadcast: Arc<Mutex<Vec<H256>>>,
+        base_fee: Arc<Mutex<Option<U256>>>,
+    }
+
+    #[async_trait]
+    impl SequencerApi for MockSequencerApi {
+        type Error = io::Error;
+
+        async fn broadcast_tx(&self, tx: Transaction) -> io::Result<Option<PreConfirmation>> {
+            self.broadcast.lock().unwrap().push(tx.hash);
+            Ok(None)
+        }
+
+        async fn submit_tx(&self, _tx: Transaction) -> io::Result<TxAdmission> {
+            Ok(TxAdmission::Added)
+        }
+
+        fn comfirm_tx(&self, txhash: H256) -> io::Result<()> {
+            self.confirmed.lock().unwrap().push(txhash);
+            Ok(())
+        }
+
+        async fn txs(&self) -> io::Result<Vec<Transaction>> {
+            Ok(Vec::new())
+        }
+
+        async fn txs_for_block(
+            &self,
+            _gas_limit: U256,
+            _byte_limit: u64,
+            base_fee: U256,
+        ) -> io::Result<Vec<Transaction>> {
+            *self.base_fee.lock().unwrap() = Some(base_fee);
+            Ok(Vec::new())
+        }
+    }
+
+    fn tx(n: u64) -> Transaction {
+        Transaction {
+            hash: H256::from_low_u64_be(n),
+            ..Default::default()
+        }
+    }
+
+    /// Seal block on `parent` of fork tree, and make it known by engine
+    fn mine(
+        consensus: &PowConsensus<MockSequencerApi>,
+        blocks: &Blocks,
+        parent: H256,
+        timestamp: u64,
+        transactions: Vec<Transaction>,
+    ) -> Block {
+        let tree = consensus.tree().unwrap();
+        let p = tree.get(&parent).unwrap();
+
+        let mut header = EthereumHeader::from(Header {
+            parent_hash: parent,
+            ommers_hash: Default::default(),
+            beneficiary: Default::default(),
+            state_root: Default::default(),
+            transactions_root: Default::default(),
+            receipts_root: Default::default(),
+            logs_bloom: Default::default(),
+            difficulty: tree.next_difficulty(p, timestamp),
+            number: (p.number + 1).into(),
+            gas_limit: 30_000_000.into(),
+            gas_used: 0.into(),
+            timestamp,
+            extra_data: Vec::new(),
+            mix_hash: Default::default(),
+            nonce: Default::default(),
+        });
+        header.base_fee_per_gas = Some(7.into());
+
+        search_nonce(&mut header, 0, u64::MAX).unwrap();
+
+        let h = &header.header;
+        let hash = execution_hash(&header);
+
+        let block = EthBlock {
+            hash: Some(hash),
+            parent_hash: parent,
+            uncles_hash: h.ommers_hash,
+            author: Some(h.beneficiary),
+            logs_bloom: Some(h.logs_bloom),
+            difficulty: h.difficulty,
+            number: Some(h.number.as_u64().into()),
+            gas_limit: h.gas_limit,
+            timestamp: h.timestamp.into(),
+            mix_hash: Some(h.mix_hash),
+            nonce: Some(h.nonce),
+            base_fee_per_gas: header.base_fee_per_gas,
+            transactions,
+            ..Default::default()
+        };
+
+        blocks.lock().unwrap().insert(hash, block.clone());
+
+        Block::Ethereum(block)
+    }
+
+    fn new_consensus(
+        blocks: &Blocks,
+        sequencer: &MockSequencerApi,
+        store: Option<PathBuf>,
+    ) -> PowConsensus<MockSequencerApi> {
+        let root = H256::from_low_u64_be(1);
+
+        blocks.lock().unwrap().insert(
+            root,
+            EthBlock {
+                hash: Some(root),
+                number: Some(0.into()),
+                timestamp: 100.into(),
+                ..Default::default()
+            },
+        );
+
+        let mut consensus = PowConsensus::new(PowConfig {
+            difficulty: DifficultyConfig {
+                target_block_time: Duration::from_secs(10),
+                initial_difficulty: 4096.into(),
+                min_difficulty: 1024.into(),
+            },
+            store,
+            ..Default::default()
+        });
+
+        consensus.genesis = Some(ConsensusGenesis {
+            chain_id: 901,
+            earliest_block_height: 0,
+            earliest_block_hash: root,
+            extra_data: Default::default(),
+            block_gas_limit: 30_000_000.into(),
+            block_block_size: 0,
+            transactions: Vec::new(),
+            timestamp: 100,
+            validators: Vec::new(),
+        });
+        consensus.engine_api = Some(Box::new(MockEngineApi));
+        consensus.web3_api = Some(Box::new(MockWeb3Api(blocks.clone())));
+        consensus.sequencer_api = Some(sequencer.clone());
+
+        consensus
+    }
+
+    #[test]
+    fn test_reorg() {
+        let root = H256::from_low_u64_be(1);
+
+        let blocks = Blocks::default();
+        let sequencer = MockSequencerApi::default();
+
+        let mut consensus = new_consensus(&blocks, &sequencer, None);
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        rt.block_on(consensus.load_tree()).unwrap();
+
+        let a1 = mine(&consensus, &blocks, root, 110, vec![tx(1), tx(2)]);
+        rt.block_on(consensus.add_block(a1.clone())).unwrap();
+        assert_eq!(consensus.state.head_block_hash, a1.hash().unwrap());
+        assert_eq!(
+            *sequencer.confirmed.lock().unwrap(),
+            vec![tx(1).hash, tx(2).hash]
+        );
+
+        // same work, stays on first seen fork
+        let b1 = mine(&consensus, &blocks, root, 120, vec![tx(1)]);
+        rt.block_on(consensus.add_block(b1.clone())).unwrap();
+        assert_eq!(consensus.state.head_block_hash, a1.hash().unwrap());
+        assert_eq!(sequencer.confirmed.lock().unwrap().len(), 2);
+
+        let b2 = mine(&consensus, &blocks, b1.hash().unwrap(), 125, vec![tx(3)]);
+        rt.block_on(consensus.add_block(b2.clone())).unwrap();
+        assert_eq!(consensus.state.head_block_hash, b2.hash().unwrap());
+        assert_eq!(
+            sequencer.confirmed.lock().unwrap()[2..],
+            [tx(1).hash, tx(3).hash]
+        );
+        // transaction only in orphaned block goes back to mempool
+        assert_eq!(*sequencer.broadcast.lock().unwrap(), vec![tx(2).hash]);
+
+        // next block pays base fee of head
+        assert!(matches!(
+            rt.block_on(consensus.build()),
+            Err(Error::PayloadNotBuilt)
+        ));
+        assert_eq!(*sequencer.base_fee.lock().unwrap(), Some(7.into()));
+    }
+
+    #[test]
+    fn test_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chain.rlp");
+
+        let root = H256::from_low_u64_be(1);
+
+        let blocks = Blocks::default();
+        let sequencer = MockSequencerApi::default();
+
+        let mut consensus = new_consensus(&blocks, &sequencer, Some(path.clone()));
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        rt.block_on(consensus.load_tree()).unwrap();
+
+        let a1 = mine(&consensus, &blocks, root, 110, Vec::new());
+        rt.block_on(consensus.add_block(a1.clone())).unwrap();
+        let a2 = mine(&consensus, &blocks, a1.hash().unwrap(), 115, Vec::new());
+        rt.block_on(consensus.add_block(a2.clone())).unwrap();
+        let b1 = mine(&consensus, &blocks, root, 120, Vec::new());
+        rt.block_on(consensus.add_block(b1.clone())).unwrap();
+
+        let head = consensus.tree().unwrap().head().clone();
+        assert_eq!(head.hash, a2.hash().unwrap());
+        let state = consensus.state.clone();
+        drop(consensus);
+
+        // seals and total difficulty are restored, forks are kept
+        let mut consensus = new_consensus(&blocks, &sequencer, Some(path.clone()));
+        consensus.state = state.clone();
+        rt.block_on(consensus.load_tree()).unwrap();
+
+        let tree = consensus.tree().unwrap();
+        assert_eq!(tree.head(), &head);
+        assert_eq!(tree.root().hash, root);
+        assert!(tree.contains(&b1.hash().unwrap()));
+        assert_eq!(tree.next_difficulty(tree.head(), 125), 4098.into());
+
+        // fork with less work doesn't take over after restart
+        let b2 = mine(&consensus, &blocks, b1.hash().unwrap(), 125, Vec::new());
+        rt.block_on(consensus.add_block(b2.clone())).unwrap();
+        assert_eq!(consensus.state.head_block_hash, a2.hash().unwrap());
+
+        // without store, tree is rooted at head
+        let mut consensus = new_consensus(&blocks, &sequencer, None);
+        consensus.state = state;
+        rt.block_on(consensus.load_tree()).unwrap();
+
+        let tree = consensus.tree().unwrap();
+        assert_eq!(tree.root().hash, a2.hash().unwrap());
+        assert!(tree.head().total_difficulty.is_zero());
+    }
+
+    #[test]
+    fn test_prune() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chain.rlp");
+
+        let root = H256::from_low_u64_be(1);
+
+        let blocks = Blocks::default();
+        let sequencer = MockSequencerApi::default();
+
+        let mut consensus = new_consensus(&blocks, &sequencer, Some(path.clone()));
+        consensus.config.prune_depth = 2;
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        rt.block_on(consensus.load_tree()).unwrap();
+
+        let mut parent = root;
+
+        for n in 1..=5 {
+            let block = mine(&consensus, &blocks, parent, 100 + n * 10, Vec::new());
+            rt.block_on(consensus.add_block(block.clone())).unwrap();
+            parent = block.hash().unwrap();
+        }
+
+        // pruned once head is more than twice depth after root
+        let tree = consensus.tree().unwrap();
+        assert_eq!(tree.root().number, 3);
+        assert_eq!(tree.nodes().len(), 3);
+
+        let store = ChainStore::open(&path).unwrap();
+        assert_eq!(store.load().unwrap(), tree.nodes());
+    }
+}
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use ethers_core::types::U256;
+
+/// Difficulty moves at most `parent / DIFFICULTY_BOUND_DIVISOR` per unit of adjustment
+pub const DIFFICULTY_BOUND_DIVISOR: u64 = 2048;
+
+/// Max units difficulty can drop in one block
+pub const MAX_DIFFICULTY_DROP: u64 = 99;
+
+/// Default target time between blocks
+pub const DEFAULT_TARGET_BLOCK_TIME: Duration = Duration::from_secs(2);
+
+/// Default difficulty of first block
+pub const DEFAULT_INITIAL_DIFFICULTY: u64 = 1 << 20;
+
+/// Default lowest difficulty
+pub const DEFAULT_MIN_DIFFICULTY: u64 = 1 << 10;
+
+/// Config of difficulty adjustment
+#[derive(Debug, Clone)]
+pub struct DifficultyConfig {
+    /// Difficulty rises when block comes faster than it, and drops when slower
+    pub target_block_time: Duration,
+
+    /// Difficulty of block after the block node starts with
+    pub initial_difficulty: U256,
+
+    /// Difficulty never drops below it
+    pub min_difficulty: U256,
+}
+
+impl Default for DifficultyConfig {
+    fn default() -> Self {
+        Self {
+            target_block_time: DEFAULT_TARGET_BLOCK_TIME,
+            initial_difficulty: DEFAULT_INITIAL_DIFFICULTY.into(),
+            min_difficulty: DEFAULT_MIN_DIFFICULTY.into(),
+        }
+    }
+}
+
+impl DifficultyConfig {
+    /// Difficulty of block, computed from its parent like Homestead
+    ///
+    /// `parent + parent / 2048 * max(1 - elapsed / target, -99)`, where elapsed is seconds
+    /// between parent and block.
+    pub fn next_difficulty(
+        &self,
+        parent_difficulty: U256,
+        parent_timestamp: u64,
+        timestamp: u64,
+    ) -> U256 {
+        let target = self.target_block_time.as_secs().max(1);
+        let elapsed = timestamp.saturating_sub(parent_timestamp);
+
+        let step = parent_difficulty / DIFFICULTY_BOUND_DIVISOR;
+        let slots = elapsed / target;
+
+        let difficulty = if slots == 0 {
+            parent_difficulty.saturating_add(step)
+        } else {
+            let drop = (slots - 1).min(MAX_DIFFICULTY_DROP);
+            parent_difficulty.saturating_sub(step * drop)
+        };
+
+        difficulty.max(self.min_difficulty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use ethers_core::types::U256;
+
+    use super::DifficultyConfig;
+
+    #[test]
+    fn test_next_difficulty() {
+        let config = DifficultyConfig {
+            target_block_time: Duration::from_secs(10),
+            initial_difficulty: 1_000_000.into(),
+            min_difficulty: 2_000_000.into(),
+        };
+
+        let parent = U256::from(2048 * 1000);
+
+        // faster than target
+        assert_eq!(config.next_difficulty(parent, 100, 105), parent + 1000);
+        // on target
+        assert_eq!(config.next_difficulty(parent, 100, 110), parent);
+        assert_eq!(config.next_difficulty(parent, 100, 119), parent);
+        // slower than target
+        assert_eq!(config.next_difficulty(parent, 100, 130), parent - 2000);
+        // drop is bounded, and stops at min difficulty
+        assert_eq!(
+            config.next_difficulty(parent, 100, 100_000),
+            config.min_difficulty
+        );
+        assert_eq!(
+            config.next_difficulty(config.min_difficulty, 100, 200),
+            config.min_difficulty
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use std::{fmt::Debug, io};
+
+use ethers_core::types::{H256, U256};
+use fluct_core::{EngineError, Web3Error};
+use fluct_service::{CallError, StepError};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    CallError(#[from] CallError),
+
+    #[error("{0}")]
+    SequencerApiError(String),
+
+    #[error(transparent)]
+    EngineError(#[from] EngineError),
+
+    #[error(transparent)]
+    Web3Error(#[from] Web3Error),
+
+    #[error(transparent)]
+    IoError(#[from] io::Error),
+
+    #[error("Consensus not inited")]
+    NotInited,
+
+    #[error("No engine api configed")]
+    NoEngineApiConfiged,
+
+    #[error("No web3 api configed")]
+    NoWeb3ApiConfiged,
+
+    #[error("No sequencer api configed")]
+    NoSequencerApiConfiged,
+
+    #[error("Block {0:?} not found in engine")]
+    BlockNotFound(H256),
+
+    #[error("Engine didn't start building payload")]
+    PayloadNotBuilt,
+
+    #[error("Payload {0:?} rejected by engine: {1}")]
+    InvalidPayload(H256, String),
+
+    #[error("Parent {0:?} of block is unknown")]
+    UnknownParent(H256),
+
+    #[error("Invalid block number, expect: {0}, got: {1}")]
+    InvalidNumber(u64, U256),
+
+    #[error("Block timestamp must be after parent: {0}, got: {1}")]
+    InvalidTimestamp(u64, u64),
+
+    #[error("Block timestamp {0} is too far in future, now: {1}")]
+    FutureTimestamp(u64, u64),
+
+    #[error("Difficulty mismatch, expect: {0}, got: {1}")]
+    DifficultyMismatch(U256, U256),
+
+    #[error("Hash {0:?} doesn't meet difficulty")]
+    InvalidSeal(H256),
+}
+
+impl StepError for Error {
+    fn is_exit(&self) -> bool {
+        match self {
+            Self::CallError(CallError::ChannelClosed) => true,
+            Self::CallError(CallError::SenderReject) => false,
+            Self::SequencerApiError(_) => false,
+            Self::EngineError(_) => true,
+            Self::Web3Error(_) => true,
+            Self::IoError(_) => false,
+            Self::NotInited => true,
+            Self::NoEngineApiConfiged => true,
+            Self::NoWeb3ApiConfiged => true,
+            Self::NoSequencerApiConfiged => true,
+            Self::BlockNotFound(_) => false,
+            Self::PayloadNotBuilt => false,
+            Self::InvalidPayload(_, _) => false,
+            Self::UnknownParent(_) => false,
+            Self::InvalidNumber(_, _) => false,
+            Self::InvalidTimestamp(_, _) => false,
+            Self::FutureTimestamp(_, _) => false,
+            Self::DifficultyMismatch(_, _) => false,
+            Self::InvalidSeal(_) => false,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("Verify Block Hash Failed, expect: {0}, got: {1}")]
+    FailedVerifyBlockHash(H256, H256),
+
+    #[error("Hash {0:?} doesn't meet difficulty")]
+    InvalidSeal(H256),
+
+    #[error(transparent)]
+    CallError(#[from] CallError),
+}
@@ -0,0 +1,461 @@
+use std::collections::HashMap;
+
+use ethers_core::types::{H256, H64, U256};
+use fluct_core::EthereumHeader;
+
+use crate::{execution_hash, verify_seal, DifficultyConfig, Error, Result};
+
+/// Seconds a block timestamp may be ahead of local clock
+pub const MAX_FUTURE_BLOCK_TIME: u64 = 15;
+
+/// Block in fork tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainNode {
+    /// Execution hash of block
+    pub hash: H256,
+    pub parent: H256,
+    pub number: u64,
+    pub timestamp: u64,
+    pub difficulty: U256,
+    /// Nonce sealing block, with difficulty
+    pub nonce: H64,
+    /// Sum of difficulty from root of tree
+    pub total_difficulty: U256,
+}
+
+/// Known forks start from a root block, head is the fork with most total difficulty
+///
+/// When total difficulty ties, the block seen first stays head. Total difficulty
+/// is kept when root moves up by pruning, so it's comparable across restarts.
+pub struct ForkTree {
+    config: DifficultyConfig,
+    nodes: HashMap<H256, ChainNode>,
+    root: H256,
+    head: H256,
+}
+
+impl ForkTree {
+    /// Create tree with root block, difficulty of children is `initial_difficulty`
+    pub fn new(config: DifficultyConfig, root: H256, number: u64, timestamp: u64) -> Self {
+        let node = ChainNode {
+            hash: root,
+            parent: H256::zero(),
+            number,
+            timestamp,
+            difficulty: U256::zero(),
+            nonce: H64::zero(),
+            total_difficulty: U256::zero(),
+        };
+
+        let mut nodes = HashMap::new();
+        nodes.insert(root, node);
+
+        Self {
+            config,
+            nodes,
+            root,
+            head: root,
+        }
+    }
+
+    /// Rebuild tree from nodes, the first one is root
+    ///
+    /// Nodes must come after their parent, ones with unknown parent are skipped.
+    /// Nodes are trusted, seals are not checked again. `head` stays head if total
+    /// difficulty ties.
+    pub fn restore(config: DifficultyConfig, nodes: Vec<ChainNode>, head: &H256) -> Option<Self> {
+        let mut iter = nodes.into_iter();
+        let root = iter.next()?;
+
+        let mut tree = Self {
+            config,
+            nodes: HashMap::new(),
+            root: root.hash,
+            head: root.hash,
+        };
+
+        tree.nodes.insert(root.hash, root);
+
+        for node in iter {
+            if tree.nodes.contains_key(&node.hash) || !tree.nodes.contains_key(&node.parent) {
+                log::warn!("Skip block {:?} restoring fork tree", node.hash);
+                continue;
+            }
+
+            if node.total_difficulty > tree.head().total_difficulty {
+                tree.head = node.hash;
+            }
+
+            tree.nodes.insert(node.hash, node);
+        }
+
+        if let Some(node) = tree.nodes.get(head) {
+            if node.total_difficulty == tree.head().total_difficulty {
+                tree.head = *head;
+            }
+        }
+
+        Some(tree)
+    }
+
+    pub fn head(&self) -> &ChainNode {
+        &self.nodes[&self.head]
+    }
+
+    pub fn root(&self) -> &ChainNode {
+        &self.nodes[&self.root]
+    }
+
+    /// All nodes, parents come before children
+    pub fn nodes(&self) -> Vec<ChainNode> {
+        let mut nodes: Vec<_> = self.nodes.values().cloned().collect();
+        nodes.sort_by_key(|node| (node.number, node.hash != self.root));
+        nodes
+    }
+
+    pub fn get(&self, hash: &H256) -> Option<&ChainNode> {
+        self.nodes.get(hash)
+    }
+
+    pub fn contains(&self, hash: &H256) -> bool {
+        self.nodes.contains_key(hash)
+    }
+
+    /// Difficulty of block after `parent` at `timestamp`
+    pub fn next_difficulty(&self, parent: &ChainNode, timestamp: u64) -> U256 {
+        if parent.difficulty.is_zero() {
+            self.config.initial_difficulty
+        } else {
+            self.config
+                .next_difficulty(parent.difficulty, parent.timestamp, timestamp)
+        }
+    }
+
+    /// Check sealed header extends a known block, return the parent
+    ///
+    /// Timestamp may be at most `MAX_FUTURE_BLOCK_TIME` after `now`, so a block
+    /// can't lower difficulty of its children by claiming a late timestamp.
+    pub fn validate(&self, header: &EthereumHeader, now: u64) -> Result<&ChainNode> {
+        let h = &header.header;
+
+        let parent = self
+            .nodes
+            .get(&h.parent_hash)
+            .ok_or(Error::UnknownParent(h.parent_hash))?;
+
+        let number = parent.number + 1;
+
+        if h.number != number.into() {
+            return Err(Error::InvalidNumber(number, h.number));
+        }
+
+        if h.timestamp <= parent.timestamp {
+            return Err(Error::InvalidTimestamp(parent.timestamp, h.timestamp));
+        }
+
+        if h.timestamp > now.saturating_add(MAX_FUTURE_BLOCK_TIME) {
+            return Err(Error::FutureTimestamp(h.timestamp, now));
+        }
+
+        let difficulty = self.next_difficulty(parent, h.timestamp);
+
+        if h.difficulty != difficulty {
+            return Err(Error::DifficultyMismatch(difficulty, h.difficulty));
+        }
+
+        if !verify_seal(header) {
+            return Err(Error::InvalidSeal(header.hash()));
+        }
+
+        Ok(parent)
+    }
+
+    /// Insert sealed header, return true if it becomes head
+    pub fn insert(&mut self, header: &EthereumHeader, now: u64) -> Result<bool> {
+        let hash = execution_hash(header);
+
+        if self.nodes.contains_key(&hash) {
+            return Ok(false);
+        }
+
+        let parent = self.validate(header, now)?;
+
+        let h = &header.header;
+
+        let node = ChainNode {
+            hash,
+            parent: parent.hash,
+            number: parent.number + 1,
+            timestamp: h.timestamp,
+            difficulty: h.difficulty,
+            nonce: h.nonce,
+            total_difficulty: parent.total_difficulty + h.difficulty,
+        };
+
+        let is_head = node.total_difficulty > self.head().total_difficulty;
+
+        self.nodes.insert(hash, node);
+
+        if is_head {
+            self.head = hash;
+        }
+
+        Ok(is_head)
+    }
+
+    /// Blocks leaving and joining the chain when head moves from `from` to `to`
+    ///
+    /// Both are ordered by number, the common ancestor isn't included.
+    pub fn route(&self, from: &H256, to: &H256) -> (Vec<H256>, Vec<H256>) {
+        let (mut retracted, mut enacted) = (Vec::new(), Vec::new());
+
+        let (mut from, mut to) = (&self.nodes[from], &self.nodes[to]);
+
+        while from.hash != to.hash {
+            if from.number >= to.number {
+                retracted.push(from.hash);
+                from = &self.nodes[&from.parent];
+            } else {
+                enacted.push(to.hash);
+                to = &self.nodes[&to.parent];
+            }
+        }
+
+        retracted.reverse();
+        enacted.reverse();
+
+        (retracted, enacted)
+    }
+
+    /// Move root up to ancestor of head `depth` blocks before it, drop blocks not
+    /// descending from new root
+    ///
+    /// Forks branching before root can't be followed anymore. Return false if root
+    /// doesn't move.
+    pub fn prune(&mut self, depth: u64) -> bool {
+        let number = self.head().number.saturating_sub(depth);
+
+        if number <= self.root().number {
+            return false;
+        }
+
+        let mut root = self.head();
+
+        while root.number > number {
+            root = &self.nodes[&root.parent];
+        }
+
+        let root = root.hash;
+
+        let mut kept = HashMap::new();
+        kept.insert(root, self.nodes[&root].clone());
+
+        // parents come before children
+        for node in self.nodes() {
+            if node.number > number && kept.contains_key(&node.parent) {
+                kept.insert(node.hash, node);
+            }
+        }
+
+        self.nodes = kept;
+        self.root = root;
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use ethers_core::types::{H256, U256};
+    use fluct_core::{EthereumHeader, Header};
+
+    use crate::{
+        execution_hash, search_nonce, verify_seal, DifficultyConfig, Error, ForkTree,
+        MAX_FUTURE_BLOCK_TIME,
+    };
+
+    const NOW: u64 = 1000;
+
+    fn config() -> DifficultyConfig {
+        DifficultyConfig {
+            target_block_time: Duration::from_secs(10),
+            initial_difficulty: 4096.into(),
+            min_difficulty: 1024.into(),
+        }
+    }
+
+    fn mine(tree: &ForkTree, parent: H256, timestamp: u64, extra: u8) -> EthereumHeader {
+        let p = tree.get(&parent).unwrap();
+
+        let mut header = EthereumHeader::from(Header {
+            parent_hash: parent,
+            ommers_hash: Default::default(),
+            beneficiary: Default::default(),
+            state_root: Default::default(),
+            transactions_root: Default::default(),
+            receipts_root: Default::default(),
+            logs_bloom: Default::default(),
+            difficulty: tree.next_difficulty(p, timestamp),
+            number: (p.number + 1).into(),
+            gas_limit: 30_000_000.into(),
+            gas_used: 0.into(),
+            timestamp,
+            extra_data: vec![extra],
+            mix_hash: Default::default(),
+            nonce: Default::default(),
+        });
+
+        search_nonce(&mut header, 0, u64::MAX).unwrap();
+
+        header
+    }
+
+    #[test]
+    fn test_fork_choice() {
+        let root = H256::from_low_u64_be(1);
+        let mut tree = ForkTree::new(config(), root, 0, 100);
+
+        let a1 = mine(&tree, root, 110, 0);
+        assert!(tree.insert(&a1, NOW).unwrap());
+        assert_eq!(tree.head().total_difficulty, 4096.into());
+
+        // same total difficulty, first seen stays head
+        let b1 = mine(&tree, root, 120, 1);
+        assert!(!tree.insert(&b1, NOW).unwrap());
+        assert_eq!(tree.head().hash, execution_hash(&a1));
+
+        // longer fork has more work
+        let b2 = mine(&tree, execution_hash(&b1), 125, 1);
+        assert!(tree.insert(&b2, NOW).unwrap());
+        assert_eq!(tree.head().hash, execution_hash(&b2));
+        assert_eq!(tree.head().total_difficulty, (4096 + 4098).into());
+        assert_eq!(
+            tree.route(&execution_hash(&a1), &execution_hash(&b2)),
+            (
+                vec![execution_hash(&a1)],
+                vec![execution_hash(&b1), execution_hash(&b2)]
+            )
+        );
+
+        // already known
+        assert!(!tree.insert(&b2, NOW).unwrap());
+
+        let b3 = mine(&tree, execution_hash(&b2), 130, 2);
+        let mut orphan = b3.clone();
+        orphan.header.parent_hash = H256::from_low_u64_be(2);
+        assert!(matches!(
+            tree.insert(&orphan, NOW),
+            Err(Error::UnknownParent(_))
+        ));
+
+        let mut easy = b3.clone();
+        easy.header.difficulty = 1024.into();
+        assert!(matches!(
+            tree.insert(&easy, NOW),
+            Err(Error::DifficultyMismatch(_, _))
+        ));
+
+        let mut far = b3.clone();
+        far.header.number = U256::MAX;
+        assert!(matches!(
+            tree.insert(&far, NOW),
+            Err(Error::InvalidNumber(3, n)) if n == U256::MAX
+        ));
+
+        let mut early = b3.clone();
+        early.header.timestamp = 125;
+        assert!(matches!(
+            tree.insert(&early, NOW),
+            Err(Error::InvalidTimestamp(125, 125))
+        ));
+
+        let future = mine(
+            &tree,
+            execution_hash(&b2),
+            NOW + MAX_FUTURE_BLOCK_TIME + 1,
+            2,
+        );
+        assert!(matches!(
+            tree.insert(&future, NOW),
+            Err(Error::FutureTimestamp(_, NOW))
+        ));
+
+        let mut forged = b3.clone();
+        forged.header.extra_data = vec![3];
+        // forged header may meet target by chance
+        if !verify_seal(&forged) {
+            assert!(matches!(
+                tree.insert(&forged, NOW),
+                Err(Error::InvalidSeal(_))
+            ));
+        }
+
+        assert!(tree.insert(&b3, NOW).unwrap());
+        assert_eq!(tree.head().number, 3);
+    }
+
+    #[test]
+    fn test_prune_restore() {
+        let root = H256::from_low_u64_be(1);
+        let mut tree = ForkTree::new(config(), root, 0, 100);
+
+        // a1 - a2 - a3 - a4
+        //    \ b2
+        let mut a = vec![root];
+
+        for n in 1..5 {
+            let header = mine(&tree, a[n - 1], 100 + n as u64 * 10, 0);
+            assert!(tree.insert(&header, NOW).unwrap());
+            a.push(execution_hash(&header));
+        }
+
+        let b2 = mine(&tree, a[1], 125, 1);
+        assert!(!tree.insert(&b2, NOW).unwrap());
+
+        let head = tree.head().clone();
+
+        // restored tree keeps total difficulty
+        let nodes = tree.nodes();
+        assert_eq!(nodes[0].hash, root);
+
+        let restored = ForkTree::restore(config(), nodes.clone(), &head.hash).unwrap();
+        assert_eq!(restored.head(), &head);
+        assert_eq!(restored.nodes(), nodes);
+
+        assert!(!tree.prune(4));
+        assert!(tree.prune(2));
+        assert_eq!(tree.root().hash, a[2]);
+        assert_eq!(tree.nodes().len(), 3);
+        assert!(!tree.contains(&execution_hash(&b2)));
+        assert_eq!(tree.head(), &head);
+
+        // fork before root is unknown now
+        let b3 = mine(&restored, execution_hash(&b2), 130, 1);
+        assert!(matches!(
+            tree.insert(&b3, NOW),
+            Err(Error::UnknownParent(_))
+        ));
+
+        let a5 = mine(&tree, a[4], 150, 0);
+        assert!(tree.insert(&a5, NOW).unwrap());
+        assert_eq!(
+            tree.head().total_difficulty,
+            head.total_difficulty + tree.head().difficulty
+        );
+
+        // ties keep given head
+        let nodes = vec![tree.root().clone(), tree.get(&a[3]).unwrap().clone()];
+        let mut tie = tree.get(&a[4]).unwrap().clone();
+        tie.hash = H256::from_low_u64_be(2);
+        tie.parent = a[2];
+        tie.total_difficulty = nodes[1].total_difficulty;
+
+        let restored = ForkTree::restore(config(), [nodes.clone(), vec![tie]].concat(), &a[3]);
+        assert_eq!(restored.unwrap().head().hash, a[3]);
+
+        let restored = ForkTree::restore(config(), nodes, &root);
+        assert_eq!(restored.unwrap().head().hash, a[3]);
+    }
+}
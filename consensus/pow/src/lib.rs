@@ -0,0 +1,32 @@
+//! Consensus sealing blocks with proof of work
+//!
+//! Blocks are built by execution engine, then sealed by searching a nonce making
+//! hash of header below target of difficulty. Header hash with zero difficulty and
+//! nonce is the block hash known by engine. Among competing forks, the one with most
+//! total difficulty is head.
+
+mod seal;
+pub use seal::*;
+
+mod difficulty;
+pub use difficulty::*;
+
+mod fork_tree;
+pub use fork_tree::*;
+
+mod store;
+
+mod config;
+pub use config::*;
+
+mod consensus;
+pub use consensus::*;
+
+mod api;
+pub use api::*;
+
+mod error;
+pub use error::*;
+
+mod types;
+pub use types::*;
@@ -0,0 +1,98 @@
+use ethers_core::types::{H256, H64, U256};
+use fluct_core::EthereumHeader;
+
+/// Max value of header hash meeting difficulty
+pub fn target(difficulty: U256) -> U256 {
+    if difficulty <= U256::one() {
+        U256::MAX
+    } else {
+        U256::MAX / difficulty
+    }
+}
+
+/// Hash of block known by execution engine, seal fields are zero in it
+pub fn execution_hash(header: &EthereumHeader) -> H256 {
+    let mut header = header.clone();
+
+    header.header.difficulty = U256::zero();
+    header.header.nonce = H64::zero();
+
+    header.hash()
+}
+
+/// Check hash of header meets its difficulty
+pub fn verify_seal(header: &EthereumHeader) -> bool {
+    let difficulty = header.header.difficulty;
+
+    if difficulty.is_zero() {
+        return false;
+    }
+
+    U256::from_big_endian(header.hash().as_bytes()) <= target(difficulty)
+}
+
+/// Try `count` nonces start from `start`, set nonce of header and return it if found
+pub fn search_nonce(header: &mut EthereumHeader, start: u64, count: u64) -> Option<u64> {
+    let target = target(header.header.difficulty);
+
+    for nonce in start..start.saturating_add(count) {
+        header.header.nonce = H64::from_low_u64_be(nonce);
+
+        if U256::from_big_endian(header.hash().as_bytes()) <= target {
+            return Some(nonce);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers_core::types::{H64, U256};
+    use fluct_core::{EthereumHeader, Header};
+
+    use super::{execution_hash, search_nonce, verify_seal};
+
+    fn header(difficulty: u64) -> EthereumHeader {
+        let mut header = EthereumHeader::from(Header {
+            parent_hash: Default::default(),
+            ommers_hash: Default::default(),
+            beneficiary: Default::default(),
+            state_root: Default::default(),
+            transactions_root: Default::default(),
+            receipts_root: Default::default(),
+            logs_bloom: Default::default(),
+            difficulty: difficulty.into(),
+            number: 1.into(),
+            gas_limit: 30_000_000.into(),
+            gas_used: 0.into(),
+            timestamp: 1,
+            extra_data: Vec::new(),
+            mix_hash: Default::default(),
+            nonce: H64::zero(),
+        });
+        header.base_fee_per_gas = Some(7.into());
+        header
+    }
+
+    #[test]
+    fn test_seal() {
+        let mut h = header(1000);
+        let hash = execution_hash(&h);
+
+        let nonce = search_nonce(&mut h, 0, 1_000_000).unwrap();
+        assert_eq!(h.header.nonce, H64::from_low_u64_be(nonce));
+        assert!(verify_seal(&h));
+
+        // seal fields don't change block hash
+        assert_eq!(execution_hash(&h), hash);
+        assert_ne!(h.hash(), hash);
+
+        let mut forged = h.clone();
+        forged.header.difficulty = U256::MAX / 2;
+        assert!(!verify_seal(&forged));
+
+        forged.header.difficulty = U256::zero();
+        assert!(!verify_seal(&forged));
+    }
+}
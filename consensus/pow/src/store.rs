@@ -0,0 +1,166 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use ethers_core::utils::rlp::{DecoderError, Rlp, RlpStream};
+
+use crate::{ChainNode, Result};
+
+/// On-disk store of fork tree
+///
+/// Each record is a RLP list `[hash, parent, number, timestamp, difficulty, nonce,
+/// total_difficulty]` of a block, the engine doesn't know seal of blocks. Records are
+/// appended when blocks are inserted, and the file is rewritten with blocks kept
+/// when tree is pruned. The first record is root of tree.
+pub(crate) struct ChainStore {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl ChainStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        let writer = Self::appender(&path)?;
+
+        Ok(Self { path, writer })
+    }
+
+    fn appender(path: &Path) -> Result<BufWriter<File>> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(BufWriter::new(file))
+    }
+
+    fn encode(node: &ChainNode) -> Vec<u8> {
+        let mut s = RlpStream::new_list(7);
+        s.append(&node.hash);
+        s.append(&node.parent);
+        s.append(&node.number);
+        s.append(&node.timestamp);
+        s.append(&node.difficulty);
+        s.append(&node.nonce);
+        s.append(&node.total_difficulty);
+        s.out().to_vec()
+    }
+
+    fn decode(rlp: &Rlp) -> std::result::Result<ChainNode, DecoderError> {
+        Ok(ChainNode {
+            hash: rlp.val_at(0)?,
+            parent: rlp.val_at(1)?,
+            number: rlp.val_at(2)?,
+            timestamp: rlp.val_at(3)?,
+            difficulty: rlp.val_at(4)?,
+            nonce: rlp.val_at(5)?,
+            total_difficulty: rlp.val_at(6)?,
+        })
+    }
+
+    /// Record block inserted into tree
+    pub fn append(&mut self, node: &ChainNode) -> Result<()> {
+        self.writer.write_all(&Self::encode(node))?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Blocks in added order
+    ///
+    /// A broken record at the end, like written partially before crash, is ignored.
+    pub fn load(&self) -> Result<Vec<ChainNode>> {
+        let data = fs::read(&self.path)?;
+
+        let mut nodes = Vec::new();
+        let mut offset = 0;
+
+        while offset < data.len() {
+            let rlp = Rlp::new(&data[offset..]);
+
+            let record = rlp
+                .payload_info()
+                .and_then(|info| Ok((info.header_len + info.value_len, Self::decode(&rlp)?)));
+
+            let Ok((len, node)) = record else {
+                log::warn!("Broken chain store record at {}, skip the rest", offset);
+                break;
+            };
+
+            nodes.push(node);
+            offset += len;
+        }
+
+        Ok(nodes)
+    }
+
+    /// Rewrite store with given blocks, root comes first
+    pub fn compact(&mut self, nodes: &[ChainNode]) -> Result<()> {
+        let tmp = self.path.with_extension("tmp");
+
+        {
+            let mut writer = BufWriter::new(File::create(&tmp)?);
+
+            for node in nodes {
+                writer.write_all(&Self::encode(node))?;
+            }
+
+            writer.flush()?;
+        }
+
+        fs::rename(&tmp, &self.path)?;
+
+        self.writer = Self::appender(&self.path)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::OpenOptions, io::Write};
+
+    use ethers_core::types::{H256, H64};
+
+    use crate::ChainNode;
+
+    use super::ChainStore;
+
+    fn node(n: u64) -> ChainNode {
+        ChainNode {
+            hash: H256::from_low_u64_be(n + 1),
+            parent: H256::from_low_u64_be(n),
+            number: n,
+            timestamp: 100 + n,
+            difficulty: (4096 + n).into(),
+            nonce: H64::from_low_u64_be(n * 7),
+            total_difficulty: (4096 * n).into(),
+        }
+    }
+
+    #[test]
+    fn test_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chain.rlp");
+
+        let mut store = ChainStore::open(&path).unwrap();
+        assert!(store.load().unwrap().is_empty());
+
+        for n in 0..3 {
+            store.append(&node(n)).unwrap();
+        }
+        drop(store);
+
+        let mut store = ChainStore::open(&path).unwrap();
+        assert_eq!(store.load().unwrap(), vec![node(0), node(1), node(2)]);
+
+        store.compact(&[node(1), node(2)]).unwrap();
+        store.append(&node(3)).unwrap();
+        assert_eq!(store.load().unwrap(), vec![node(1), node(2), node(3)]);
+
+        // partial record written before crash
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[0xf8, 0xff, 0x00]).unwrap();
+        assert_eq!(store.load().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,10 @@
+use fluct_core::{Block, ForkChoiceState};
+
+pub enum ApiRequest {
+    AddBlock(Box<Block>),
+    GetChainState,
+}
+
+pub enum ApiResponse {
+    GetChainState(ForkChoiceState),
+}
@@ -25,7 +25,7 @@ impl ConsensusApi for SingleConsensusApi {
 
         block.fill_hash(ohash);
 
-        self.call.send(ApiRequest::AddBlock(block))?;
+        self.call.send(ApiRequest::AddBlock(Box::new(block)))?;
 
         Ok(())
     }
@@ -1,20 +1,17 @@
+use std::marker::PhantomData;
+
 use async_trait::async_trait;
 use ethers_core::types::H160;
 use ethers_signers::Signer;
-use fluct_core::{
-    ConsensusGenesis, EngineApi, ForkChoiceState, SequencerApi, Transaction, Web3Api,
-};
+use fluct_core::SequencerApi;
 use fluct_service::{AsyncStepService, AsyncStepServiceWapper1};
 
 use crate::Error;
 
 pub struct SingleConsensus<S, SA> {
-    sequencer_api: Option<SA>,
-    engine_api: Option<Box<dyn EngineApi + Send + Sync>>,
     signer: Option<S>,
-    state: ForkChoiceState,
     proposer: H160,
-    init_txs: Vec<Transaction>,
+    _sequencer: PhantomData<SA>,
 }
 
 impl<S, SA> SingleConsensus<S, SA>
@@ -25,25 +22,28 @@ where
         let proposer = signer.address();
 
         Self {
-            sequencer_api: None,
-            engine_api: None,
-            state: Default::default(),
             signer: Some(signer),
             proposer,
-            init_txs: Vec::new(),
+            _sequencer: PhantomData,
         }
     }
 
     pub fn new_follower(proposer: H160) -> Self {
         Self {
-            sequencer_api: None,
-            engine_api: None,
-            state: Default::default(),
             signer: None,
             proposer,
-            init_txs: Vec::new(),
+            _sequencer: PhantomData,
         }
     }
+
+    pub fn proposer(&self) -> H160 {
+        self.proposer
+    }
+
+    /// Local node signs blocks
+    pub fn is_proposer(&self) -> bool {
+        self.signer.is_some()
+    }
 }
 
 #[async_trait]
@@ -60,32 +60,3 @@ where
 }
 
 pub struct SingleConsensusService<S, SA>(AsyncStepServiceWapper1<SingleConsensus<S, SA>>);
-
-/* impl<S, SA> SingleConsensus<S, SA>
-where
-    SA: SequencerApi,
-    S: Signer,
-{
-    fn init(
-        &mut self,
-        genesis: ConsensusGenesis<Transaction>,
-        state: ForkChoiceState,
-    ) -> Result<(), Error<SA>> {
-        self.init_txs = genesis.transactions;
-        self.state = state;
-
-        Ok(())
-    }
-
-    fn set_api(
-        &mut self,
-        engine_api: impl EngineApi + 'static,
-        _web3_api: impl Web3Api,
-        seqencer_api: SA,
-    ) -> Result<(), Error<SA>> {
-        self.engine_api = Some(Box::new(engine_api));
-        self.sequencer_api = Some(seqencer_api);
-
-        Ok(())
-    }
-} */
@@ -1,7 +1,4 @@
-use std::{
-    fmt::{Debug, Display},
-    sync::Arc,
-};
+use std::fmt::Debug;
 
 use ethers_core::types::H256;
 use fluct_service::{CallError, StepError};
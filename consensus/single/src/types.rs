@@ -1,7 +1,7 @@
 use fluct_core::{Block, ForkChoiceState};
 
 pub enum ApiRequest {
-    AddBlock(Block),
+    AddBlock(Box<Block>),
     GetChainState,
 }
 
@@ -1,5 +1,11 @@
-use ethereum::Header;
-use ethers_core::types::{Block as EthBlock, H256};
+pub use ethereum::Header;
+use ethers_core::{
+    types::{Block as EthBlock, H256, U256},
+    utils::{
+        keccak256,
+        rlp::{Encodable, RlpStream},
+    },
+};
 
 use crate::Transaction;
 
@@ -22,8 +28,99 @@ impl Block {
     }
 }
 
+/// Ethereum header, with optional fields added by later forks
+///
+/// Optional fields must be set in order, a field is only encoded when all fields before it are set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthereumHeader {
+    pub header: Header,
+    /// London
+    pub base_fee_per_gas: Option<U256>,
+    /// Shanghai
+    pub withdrawals_root: Option<H256>,
+    /// Cancun
+    pub blob_gas_used: Option<U256>,
+    /// Cancun
+    pub excess_blob_gas: Option<U256>,
+    /// Cancun
+    pub parent_beacon_block_root: Option<H256>,
+}
+
+impl From<Header> for EthereumHeader {
+    fn from(header: Header) -> Self {
+        Self {
+            header,
+            base_fee_per_gas: None,
+            withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
+        }
+    }
+}
+
+impl Encodable for EthereumHeader {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        let h = &self.header;
+
+        let mut count = 15;
+
+        let base_fee = self.base_fee_per_gas;
+        let withdrawals_root = base_fee.and(self.withdrawals_root);
+        let blob_gas_used = withdrawals_root.and(self.blob_gas_used);
+        let excess_blob_gas = blob_gas_used.and(self.excess_blob_gas);
+        let beacon_root = excess_blob_gas.and(self.parent_beacon_block_root);
+
+        count += base_fee.is_some() as usize;
+        count += withdrawals_root.is_some() as usize;
+        count += blob_gas_used.is_some() as usize;
+        count += excess_blob_gas.is_some() as usize;
+        count += beacon_root.is_some() as usize;
+
+        s.begin_list(count);
+
+        s.append(&h.parent_hash);
+        s.append(&h.ommers_hash);
+        s.append(&h.beneficiary);
+        s.append(&h.state_root);
+        s.append(&h.transactions_root);
+        s.append(&h.receipts_root);
+        s.append(&h.logs_bloom);
+        s.append(&h.difficulty);
+        s.append(&h.number);
+        s.append(&h.gas_limit);
+        s.append(&h.gas_used);
+        s.append(&h.timestamp);
+        s.append(&h.extra_data);
+        s.append(&h.mix_hash);
+        s.append(&h.nonce);
+
+        if let Some(v) = base_fee {
+            s.append(&v);
+        }
+        if let Some(v) = withdrawals_root {
+            s.append(&v);
+        }
+        if let Some(v) = blob_gas_used {
+            s.append(&v);
+        }
+        if let Some(v) = excess_blob_gas {
+            s.append(&v);
+        }
+        if let Some(v) = beacon_root {
+            s.append(&v);
+        }
+    }
+}
+
+impl EthereumHeader {
+    pub fn hash(&self) -> H256 {
+        keccak256(self.rlp_bytes()).into()
+    }
+}
+
 pub enum BlockHeader {
-    Ethereum(Header),
+    Ethereum(EthereumHeader),
 }
 
 impl From<Block> for BlockHeader {
@@ -54,7 +151,14 @@ impl<TX> From<EthBlock<TX>> for BlockHeader {
             nonce: b.nonce.unwrap_or_default(),
         };
 
-        Self::Ethereum(header)
+        Self::Ethereum(EthereumHeader {
+            header,
+            base_fee_per_gas: b.base_fee_per_gas,
+            withdrawals_root: b.withdrawals_root,
+            blob_gas_used: b.blob_gas_used,
+            excess_blob_gas: b.excess_blob_gas,
+            parent_beacon_block_root: b.parent_beacon_block_root,
+        })
     }
 }
 
@@ -65,3 +169,49 @@ impl BlockHeader {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ethereum::Header;
+    use ethers_core::{
+        types::{H256, H64},
+        utils::hex,
+    };
+
+    use crate::{state_utils, EthereumHeader};
+
+    #[test]
+    fn test_mainnet_genesis_hash() {
+        let header = Header {
+            parent_hash: H256::zero(),
+            ommers_hash: state_utils::EMPTY_OMMERS_HASH,
+            beneficiary: Default::default(),
+            state_root: "0xd7f8974fb5ac78d9ac099b9ad5018bedc2ce0a72dad1827a1709da30580f0544"
+                .parse()
+                .unwrap(),
+            transactions_root: state_utils::EMPTY_ROOT,
+            receipts_root: state_utils::EMPTY_ROOT,
+            logs_bloom: Default::default(),
+            difficulty: 0x400000000u64.into(),
+            number: 0.into(),
+            gas_limit: 5000.into(),
+            gas_used: 0.into(),
+            timestamp: 0,
+            extra_data: hex::decode(
+                "11bbe8db4e347b4e8c937c1c8370e4b5ed33adb3db69cbdb7a38e1e50b1b82fa",
+            )
+            .unwrap(),
+            mix_hash: H256::zero(),
+            nonce: H64::from_low_u64_be(0x42),
+        };
+
+        let header = EthereumHeader::from(header);
+
+        assert_eq!(
+            header.hash(),
+            "0xd4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+                .parse()
+                .unwrap()
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use ethers_core::utils::rlp::DecoderError;
+use ethers_core::{types::H256, utils::rlp::DecoderError};
 use thiserror::Error;
 
 /// Error
@@ -6,4 +6,10 @@ use thiserror::Error;
 pub enum Error {
     #[error(transparent)]
     RlpDecodeError(#[from] DecoderError),
+
+    #[error("Genesis block hash mismatch, expect: {0:?}, got: {1:?}")]
+    GenesisHashMismatch(H256, H256),
+
+    #[error("Invalid genesis: {0}")]
+    InvalidGenesis(String),
 }
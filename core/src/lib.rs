@@ -7,6 +7,12 @@ pub use types::*;
 mod transaction;
 pub use transaction::*;
 
+mod state;
+pub use state::*;
+
+mod rpc;
+pub use rpc::*;
+
 mod block;
 pub use block::*;
 
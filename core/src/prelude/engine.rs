@@ -4,7 +4,25 @@ use ethers_core::types::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::{types, EngineError, Service, Transaction, Web3Error};
+use crate::{
+    types, BlockHeader, ConsensusGenesis, EngineError, Error, Service, Transaction, Web3Error,
+};
+
+/// Genesis of execution engine
+pub trait ExecutionGenesis: Serialize + for<'de> Deserialize<'de> {
+    /// Build genesis block header, include state root computed from genesis state
+    fn block_header(&self) -> BlockHeader;
+
+    /// Hash of genesis block
+    fn block_hash(&self) -> H256 {
+        self.block_header().hash()
+    }
+
+    /// Check genesis is consistent with consensus genesis, such as chain id
+    fn validate_with<T>(&self, _consensus: &ConsensusGenesis<T>) -> Result<(), Error> {
+        Ok(())
+    }
+}
 
 /// Service of Execution Engine
 #[async_trait]
@@ -15,7 +33,7 @@ pub trait ExecutionService: Service {
     type Web3Api: Web3Api;
 
     /// Execution genesis type
-    type Genesis: Serialize + for<'de> Deserialize<'de>;
+    type Genesis: ExecutionGenesis;
 
     /// Create engine api instance
     fn engine_api(&self) -> Result<Self::EngineApi, Self::Error>;
@@ -24,6 +42,8 @@ pub trait ExecutionService: Service {
     fn web3_api(&self) -> Result<Self::Web3Api, Self::Error>;
 
     /// Init chain
+    ///
+    /// If chain is already inited, only check the genesis is the same one.
     fn init(&mut self, genesis: Self::Genesis) -> Result<(), Self::Error>;
 
     /// Remove all data in engine.
@@ -34,7 +54,7 @@ pub trait ExecutionService: Service {
 
 /// Api of Engine
 #[async_trait]
-pub trait EngineApi {
+pub trait EngineApi: Send + Sync + 'static {
     /// Choice block chain fork.
     ///
     /// Spec: [`engine_forkchoiceUpdatedV1`](https://github.com/ethereum/execution-apis/blob/769c53c94c4e487337ad0edea9ee0dce49c79bfa/src/engine/specification.md#engine_forkchoiceupdatedv1)
@@ -86,6 +106,13 @@ pub trait Web3Api: Send + Sync + 'static {
     /// Get account balance
     async fn balance(&mut self, address: H160, block: Option<BlockId>) -> Result<U256, Web3Error>;
 
+    /// Get account nonce, aka transaction count
+    async fn transaction_count(
+        &mut self,
+        address: H160,
+        block: Option<BlockId>,
+    ) -> Result<U256, Web3Error>;
+
     /// Get account code
     async fn code(&mut self, address: H160, block: Option<BlockId>) -> Result<Bytes, Web3Error>;
 
@@ -1,9 +1,13 @@
 use std::error::Error;
 
 use async_trait::async_trait;
-use ethers_core::types::H256;
+use ethers_core::types::{TxpoolContent, H256, U256};
+use futures_util::stream::{self, BoxStream, StreamExt};
 
-use crate::{Service, Transaction, Web3Api};
+use crate::{
+    transaction_utils, ConsensusGenesis, PreConfirmation, Service, Transaction, TxAdmission,
+    TxEvent, Web3Api,
+};
 
 /// Api of sequencer
 ///
@@ -15,16 +19,92 @@ pub trait SequencerApi: Clone {
 
     /// Broadcast a transaction
     ///
-    /// Put transaction into mempool
-    fn broadcast_tx(&self, tx: Transaction) -> Result<(), Self::Error>;
+    /// Put transaction into mempool, return error if transaction is rejected.
+    /// Sequencer with a signing key returns pre-confirmation of the transaction.
+    async fn broadcast_tx(&self, tx: Transaction) -> Result<Option<PreConfirmation>, Self::Error>;
+
+    /// Put transaction into mempool and wait for admission result
+    ///
+    /// Rejected transaction is reported as `TxAdmission::Rejected`, error only means
+    /// sequencer failed to handle it.
+    async fn submit_tx(&self, tx: Transaction) -> Result<TxAdmission, Self::Error>;
 
     /// Comfirm a transaction
     ///
     /// Remove transaction from mempool
     fn comfirm_tx(&self, txhash: H256) -> Result<(), Self::Error>;
 
+    /// Get pre-confirmation of transaction in mempool
+    ///
+    /// Default implementation signs no pre-confirmation.
+    async fn pre_confirmation(
+        &self,
+        _txhash: H256,
+    ) -> Result<Option<PreConfirmation>, Self::Error> {
+        Ok(None)
+    }
+
     /// Get transacion seqence.
+    ///
+    /// Only executable transactions are returned, transactions after a nonce gap are held.
     async fn txs(&self) -> Result<Vec<Transaction>, Self::Error>;
+
+    /// Get transactions for next block
+    ///
+    /// Return the prefix of sequence fitting in `gas_limit` and `byte_limit`, usually
+    /// `block_gas_limit` and `block_block_size` of `ConsensusGenesis`. Transactions
+    /// can't pay `base_fee` are skipped.
+    async fn txs_for_block(
+        &self,
+        gas_limit: U256,
+        byte_limit: u64,
+        base_fee: U256,
+    ) -> Result<Vec<Transaction>, Self::Error> {
+        let txs = self.txs().await?;
+
+        Ok(transaction_utils::select_for_block(
+            txs, gas_limit, byte_limit, base_fee,
+        ))
+    }
+
+    /// Transactions in mempool, grouped by sender and nonce
+    ///
+    /// Default implementation only knows executable transactions, all are pending.
+    async fn content(&self) -> Result<TxpoolContent, Self::Error> {
+        let mut content = TxpoolContent::default();
+
+        for tx in self.txs().await? {
+            content
+                .pending
+                .entry(tx.from)
+                .or_default()
+                .insert(tx.nonce.to_string(), tx);
+        }
+
+        Ok(content)
+    }
+
+    /// Get transaction in mempool
+    async fn get_tx(&self, txhash: H256) -> Result<Option<Transaction>, Self::Error> {
+        let content = self.content().await?;
+
+        let tx = content
+            .pending
+            .into_values()
+            .chain(content.queued.into_values())
+            .flat_map(|txs| txs.into_values())
+            .find(|tx| tx.hash == txhash);
+
+        Ok(tx)
+    }
+
+    /// Subscribe events of mempool
+    ///
+    /// Each subscriber gets events happened after subscribing. Default implementation
+    /// has no event.
+    fn subscribe(&self) -> BoxStream<'static, TxEvent> {
+        stream::empty().boxed()
+    }
 }
 
 /// Service of Sequencer, aka mempool(txpool) service
@@ -36,6 +116,11 @@ pub trait SequencerService: Service {
     fn api(&self) -> Self::Api;
 
     fn set_api(&mut self, web3_api: impl Web3Api);
+
+    /// Init sequencer using consensus genesis
+    ///
+    /// Chain id and block gas limit are used to check transactions before admission.
+    fn init(&mut self, genesis: &ConsensusGenesis<Transaction>);
 }
 
 /// Error Type of SequencerApi from SequencerService
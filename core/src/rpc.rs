@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use ethers_core::types::{
+    TxpoolContent, TxpoolInspect, TxpoolInspectSummary, TxpoolStatus, H256, U64,
+};
+use fluct_jsonrpc::{server::RpcHandler, RpcError};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{transaction_utils, PreConfirmation, SequencerApi, Transaction};
+
+/// JSONRPC methods served from sequencer
+///
+/// Serve `txpool_content`, `txpool_status`, `txpool_inspect`, `eth_getTransactionByHash`
+/// for transactions in mempool, and `fluct_getPreConfirmation`.
+///
+/// Node mounts it in a `RpcProxy` in front of execution, other calls are forwarded.
+pub struct SequencerRpc<SA> {
+    api: SA,
+}
+
+impl<SA> SequencerRpc<SA>
+where
+    SA: SequencerApi + Sync,
+{
+    pub fn new(api: SA) -> Self {
+        Self { api }
+    }
+
+    /// Handle a JSONRPC call
+    ///
+    /// Return `None` if method isn't served here, or transaction isn't in mempool,
+    /// the call should be forwarded to execution.
+    pub async fn handle(&self, method: &str, params: &Value) -> Option<Result<Value, RpcError>> {
+        match method {
+            "txpool_content" => Some(self.content().await.and_then(to_value)),
+            "txpool_status" => Some(self.status().await.and_then(to_value)),
+            "txpool_inspect" => Some(self.inspect().await.and_then(to_value)),
+            "eth_getTransactionByHash" => {
+                let txhash = match parse_txhash(params) {
+                    Ok(v) => v,
+                    Err(e) => return Some(Err(e)),
+                };
+
+                match self.transaction(txhash).await {
+                    Ok(Some(tx)) => Some(to_value(tx)),
+                    Ok(None) => None,
+                    Err(e) => Some(Err(e)),
+                }
+            }
+            "fluct_getPreConfirmation" => {
+                let result = match parse_txhash(params) {
+                    Ok(txhash) => self.pre_confirmation(txhash).await,
+                    Err(e) => Err(e),
+                };
+
+                Some(result.and_then(to_value))
+            }
+            _ => None,
+        }
+    }
+
+    pub async fn content(&self) -> Result<TxpoolContent, RpcError> {
+        self.api.content().await.map_err(internal_error)
+    }
+
+    pub async fn status(&self) -> Result<TxpoolStatus, RpcError> {
+        let content = self.content().await?;
+
+        let count = |txs: &BTreeMap<_, BTreeMap<_, _>>| {
+            U64::from(txs.values().map(|v| v.len()).sum::<usize>())
+        };
+
+        Ok(TxpoolStatus {
+            pending: count(&content.pending),
+            queued: count(&content.queued),
+        })
+    }
+
+    pub async fn inspect(&self) -> Result<TxpoolInspect, RpcError> {
+        let content = self.content().await?;
+
+        let summary = |tx: Transaction| TxpoolInspectSummary {
+            to: tx.to,
+            value: tx.value,
+            gas: tx.gas,
+            gas_price: transaction_utils::fee_caps(&tx).0,
+        };
+
+        let inspect = |txs: BTreeMap<_, BTreeMap<_, _>>| {
+            txs.into_iter()
+                .map(|(from, txs)| {
+                    let txs = txs
+                        .into_iter()
+                        .map(|(nonce, tx)| (nonce, summary(tx)))
+                        .collect();
+                    (from, txs)
+                })
+                .collect()
+        };
+
+        Ok(TxpoolInspect {
+            pending: inspect(content.pending),
+            queued: inspect(content.queued),
+        })
+    }
+
+    /// Pending transaction by hash
+    pub async fn transaction(&self, txhash: H256) -> Result<Option<Transaction>, RpcError> {
+        self.api.get_tx(txhash).await.map_err(internal_error)
+    }
+
+    /// Pre-confirmation signed by sequencer, `None` if not signed
+    pub async fn pre_confirmation(
+        &self,
+        txhash: H256,
+    ) -> Result<Option<PreConfirmation>, RpcError> {
+        self.api
+            .pre_confirmation(txhash)
+            .await
+            .map_err(internal_error)
+    }
+}
+
+#[async_trait]
+impl<SA> RpcHandler for SequencerRpc<SA>
+where
+    SA: SequencerApi + Send + Sync,
+{
+    async fn handle(&self, method: &str, params: &Value) -> Option<Result<Value, RpcError>> {
+        SequencerRpc::handle(self, method, params).await
+    }
+}
+
+fn internal_error(e: impl std::error::Error) -> RpcError {
+    RpcError::internal_error_with(e.to_string())
+}
+
+fn to_value(v: impl Serialize) -> Result<Value, RpcError> {
+    serde_json::to_value(v).map_err(internal_error)
+}
+
+fn parse_txhash(params: &Value) -> Result<H256, RpcError> {
+    let (txhash,): (H256,) = serde_json::from_value(params.clone())
+        .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+
+    Ok(txhash)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use async_trait::async_trait;
+    use ethers_core::types::{H160, H256};
+    use serde_json::{json, Value};
+
+    use crate::{PreConfirmation, SequencerApi, Transaction, TxAdmission};
+
+    use super::SequencerRpc;
+
+    #[derive(Clone)]
+    struct MockApi(Vec<Transaction>);
+
+    #[async_trait]
+    impl SequencerApi for MockApi {
+        type Error = io::Error;
+
+        async fn broadcast_tx(&self, _tx: Transaction) -> io::Result<Option<PreConfirmation>> {
+            Ok(None)
+        }
+
+        async fn submit_tx(&self, _tx: Transaction) -> io::Result<TxAdmission> {
+            Ok(TxAdmission::Added)
+        }
+
+        fn comfirm_tx(&self, _txhash: H256) -> io::Result<()> {
+            Ok(())
+        }
+
+        async fn txs(&self) -> io::Result<Vec<Transaction>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn test_handle() {
+        let tx = Transaction {
+            hash: H256::from_low_u64_be(1),
+            from: H160::from_low_u64_be(2),
+            to: Some(H160::from_low_u64_be(3)),
+            nonce: 7.into(),
+            gas: 21000.into(),
+            gas_price: Some(10.into()),
+            ..Default::default()
+        };
+
+        let rpc = SequencerRpc::new(MockApi(vec![tx.clone()]));
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        let call = |method: &str, params: Value| rt.block_on(rpc.handle(method, &params));
+
+        let status = call("txpool_status", json!([])).unwrap().unwrap();
+        assert_eq!(status, json!({ "pending": "0x1", "queued": "0x0" }));
+
+        let inspect = call("txpool_inspect", json!([])).unwrap().unwrap();
+        assert_eq!(
+            inspect["pending"][format!("{:?}", tx.from)]["7"],
+            json!(format!("{:?}: 0 wei + 21000 gas × 10 wei", tx.to.unwrap()))
+        );
+
+        let content = call("txpool_content", json!([])).unwrap().unwrap();
+        assert_eq!(
+            content["pending"][format!("{:?}", tx.from)]["7"]["hash"],
+            json!(tx.hash)
+        );
+
+        let found = call("eth_getTransactionByHash", json!([tx.hash]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(found["hash"], json!(tx.hash));
+
+        let missing = H256::from_low_u64_be(9);
+        assert!(call("eth_getTransactionByHash", json!([missing])).is_none());
+        assert!(call("eth_getTransactionByHash", json!([]))
+            .unwrap()
+            .is_err());
+        assert!(call("eth_blockNumber", json!([])).is_none());
+
+        let preconf = call("fluct_getPreConfirmation", json!([tx.hash]));
+        assert_eq!(preconf.unwrap().unwrap(), Value::Null);
+    }
+}
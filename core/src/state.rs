@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+
+use ethers_core::types::{Bytes, H256, U256};
+
+/// Account in world state
+#[derive(Debug, Clone, Default)]
+pub struct StateAccount {
+    pub nonce: U256,
+    pub balance: U256,
+    pub code: Bytes,
+    pub storage: BTreeMap<H256, H256>,
+}
+
+/// Utils for world state
+pub mod state_utils {
+    use ethers_core::{
+        types::{H160, H256, U256},
+        utils::{keccak256, rlp::RlpStream},
+    };
+
+    use crate::StateAccount;
+
+    /// Root of empty trie
+    pub const EMPTY_ROOT: H256 = H256([
+        0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8,
+        0x6e, 0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63,
+        0xb4, 0x21,
+    ]);
+
+    /// Hash of empty ommers list
+    pub const EMPTY_OMMERS_HASH: H256 = H256([
+        0x1d, 0xcc, 0x4d, 0xe8, 0xde, 0xc7, 0x5d, 0x7a, 0xab, 0x85, 0xb5, 0x67, 0xb6, 0xcc, 0xd4,
+        0x1a, 0xd3, 0x12, 0x45, 0x1b, 0x94, 0x8a, 0x74, 0x13, 0xf0, 0xa1, 0x42, 0xfd, 0x40, 0xd4,
+        0x93, 0x47,
+    ]);
+
+    /// Compute storage root, zero values are ignored
+    pub fn storage_root<'a>(storage: impl IntoIterator<Item = (&'a H256, &'a H256)>) -> H256 {
+        let items = storage
+            .into_iter()
+            .filter(|(_, v)| !v.is_zero())
+            .map(|(k, v)| {
+                let value = U256::from_big_endian(v.as_bytes());
+                (k.0, ethers_core::utils::rlp::encode(&value))
+            });
+
+        ethereum::util::sec_trie_root(items)
+    }
+
+    /// Encode account as it stored in state trie
+    pub fn account_rlp(account: &StateAccount) -> Vec<u8> {
+        let mut s = RlpStream::new_list(4);
+
+        s.append(&account.nonce);
+        s.append(&account.balance);
+        s.append(&storage_root(&account.storage));
+        s.append(&H256::from(keccak256(&account.code)));
+
+        s.out().to_vec()
+    }
+
+    /// Compute state root of accounts
+    pub fn state_root<'a>(
+        accounts: impl IntoIterator<Item = (&'a H160, &'a StateAccount)>,
+    ) -> H256 {
+        let items = accounts
+            .into_iter()
+            .map(|(address, account)| (address.0, account_rlp(account)));
+
+        ethereum::util::sec_trie_root(items)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use ethers_core::{
+            types::{H160, U256},
+            utils::keccak256,
+        };
+
+        use crate::StateAccount;
+
+        use super::{state_root, EMPTY_OMMERS_HASH, EMPTY_ROOT};
+
+        #[test]
+        fn test_empty() {
+            assert_eq!(EMPTY_ROOT.0, keccak256([0x80]));
+            assert_eq!(EMPTY_OMMERS_HASH.0, keccak256([0xc0]));
+
+            let accounts: Vec<(H160, StateAccount)> = Vec::new();
+            assert_eq!(state_root(accounts.iter().map(|(k, v)| (k, v))), EMPTY_ROOT);
+        }
+
+        #[test]
+        fn test_state_root() {
+            // Alloc of Morden testnet genesis
+            let account = |balance: U256| StateAccount {
+                nonce: U256::from(1u64 << 20),
+                balance,
+                ..Default::default()
+            };
+
+            let mut accounts: Vec<(H160, StateAccount)> = (1..=4)
+                .map(|i| (H160::from_low_u64_be(i), account(U256::one())))
+                .collect();
+
+            accounts.push((
+                "0x102e61f5d8f9bc71d0ad4a084df4e65e05ce0e1c"
+                    .parse()
+                    .unwrap(),
+                account(U256::one() << 200),
+            ));
+
+            assert_eq!(
+                state_root(accounts.iter().map(|(k, v)| (k, v))),
+                "0xf3f4696bbf3b3b07775128eb7a3763279a394e382130f27c21e70233e04946a9"
+                    .parse()
+                    .unwrap()
+            );
+        }
+    }
+}
@@ -2,8 +2,10 @@ pub use ethers_core::types::Transaction;
 
 /// Utils for transaction
 pub mod transaction_utils {
+    use std::collections::HashSet;
+
     use ethers_core::{
-        types::Bytes,
+        types::{Bytes, U256},
         utils::rlp::{Decodable, Rlp},
     };
 
@@ -32,4 +34,161 @@ pub mod transaction_utils {
 
         Ok(ret)
     }
+
+    /// Chain id of transaction, legacy transaction takes it from `v` (EIP-155)
+    pub fn chain_id(tx: &Transaction) -> Option<U256> {
+        if tx.chain_id.is_some() {
+            return tx.chain_id;
+        }
+
+        let v = tx.v.as_u64();
+
+        if v >= 35 {
+            Some(((v - 35) / 2).into())
+        } else {
+            None
+        }
+    }
+
+    /// Gas used before execution: base cost, calldata, contract creation and access list
+    pub fn intrinsic_gas(tx: &Transaction) -> u64 {
+        let mut gas = 21000u64;
+
+        if tx.to.is_none() {
+            gas += 32000;
+
+            // EIP-3860, 2 gas per word of initcode
+            gas += (tx.input.len() as u64).div_ceil(32) * 2;
+        }
+
+        for b in tx.input.iter() {
+            gas += if *b == 0 { 4 } else { 16 };
+        }
+
+        if let Some(list) = &tx.access_list {
+            for item in &list.0 {
+                gas += 2400 + 1900 * item.storage_keys.len() as u64;
+            }
+        }
+
+        gas
+    }
+
+    /// Max wei can be spent by transaction, `gas * fee_cap + value`
+    pub fn max_cost(tx: &Transaction) -> U256 {
+        let price = tx.max_fee_per_gas.or(tx.gas_price).unwrap_or_default();
+
+        tx.gas.saturating_mul(price).saturating_add(tx.value)
+    }
+
+    /// Fee cap and tip cap per gas, both are gas price for legacy transaction
+    pub fn fee_caps(tx: &Transaction) -> (U256, U256) {
+        match tx.max_fee_per_gas {
+            Some(max_fee) => (max_fee, tx.max_priority_fee_per_gas.unwrap_or(max_fee)),
+            None => {
+                let price = tx.gas_price.unwrap_or_default();
+                (price, price)
+            }
+        }
+    }
+
+    /// Is `new` paying enough to replace `old`
+    ///
+    /// Both fee cap and tip cap must be higher, and at least `bump` percent higher.
+    pub fn price_bumped(old: &Transaction, new: &Transaction, bump: u64) -> bool {
+        let (old_fee, old_tip) = fee_caps(old);
+        let (new_fee, new_tip) = fee_caps(new);
+
+        let threshold = |v: U256| v.saturating_mul((100 + bump).into()) / 100;
+
+        new_fee > old_fee
+            && new_tip > old_tip
+            && new_fee >= threshold(old_fee)
+            && new_tip >= threshold(old_tip)
+    }
+
+    /// Prefix of transaction sequence fitting in a block
+    ///
+    /// Selection stops at first transaction exceeding gas or byte limit, `byte_limit` of 0
+    /// means no limit. Transactions whose fee cap is below `base_fee` are skipped, with
+    /// later transactions of same sender, as their nonces are not executable anymore.
+    pub fn select_for_block(
+        txs: Vec<Transaction>,
+        gas_limit: U256,
+        byte_limit: u64,
+        base_fee: U256,
+    ) -> Vec<Transaction> {
+        let mut gas = U256::zero();
+        let mut bytes = 0u64;
+
+        let mut skipped = HashSet::new();
+
+        let mut selected = Vec::new();
+
+        for tx in txs {
+            if skipped.contains(&tx.from) || fee_caps(&tx).0 < base_fee {
+                skipped.insert(tx.from);
+                continue;
+            }
+
+            let size = tx.rlp().len() as u64;
+
+            if gas.saturating_add(tx.gas) > gas_limit
+                || (byte_limit != 0 && bytes + size > byte_limit)
+            {
+                break;
+            }
+
+            gas += tx.gas;
+            bytes += size;
+
+            selected.push(tx);
+        }
+
+        selected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers_core::types::{H160, H256, U256};
+
+    use crate::Transaction;
+
+    use super::transaction_utils;
+
+    fn tx(id: u64, from: u64, gas_price: u64) -> Transaction {
+        Transaction {
+            hash: H256::from_low_u64_be(id),
+            from: H160::from_low_u64_be(from),
+            gas: 21000.into(),
+            gas_price: Some(gas_price.into()),
+            ..Default::default()
+        }
+    }
+
+    fn ids(txs: &[Transaction]) -> Vec<u64> {
+        txs.iter().map(|tx| tx.hash.to_low_u64_be()).collect()
+    }
+
+    #[test]
+    fn test_select_for_block() {
+        let txs = vec![
+            tx(1, 1, 10),
+            tx(2, 2, 5),
+            tx(3, 1, 10),
+            tx(4, 2, 10),
+            tx(5, 3, 10),
+        ];
+
+        let selected = transaction_utils::select_for_block(txs.clone(), 63000.into(), 0, 8.into());
+        assert_eq!(ids(&selected), vec![1, 3, 5]);
+
+        let selected = transaction_utils::select_for_block(txs.clone(), 42000.into(), 0, 0.into());
+        assert_eq!(ids(&selected), vec![1, 2]);
+
+        let size = txs[0].rlp().len() as u64;
+        let selected = transaction_utils::select_for_block(txs, U256::MAX, size * 3 - 1, 0.into());
+        assert_eq!(ids(&selected), vec![1, 2]);
+    }
 }
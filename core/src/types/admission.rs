@@ -0,0 +1,259 @@
+use ethers_core::types::{H160, H256, U256};
+use fluct_jsonrpc::RpcError;
+use thiserror::Error;
+
+use crate::{transaction_utils, ConsensusGenesis, Transaction};
+
+/// Reason of rejecting transaction from mempool
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RejectReason {
+    #[error("Invalid signature")]
+    InvalidSignature,
+
+    #[error("Transaction hash mismatch, computed: {0:?}, got: {1:?}")]
+    HashMismatch(H256, H256),
+
+    #[error("Sender mismatch, recovered: {0:?}, got: {1:?}")]
+    SenderMismatch(H160, H160),
+
+    #[error("Only replay-protected (EIP-155) transactions allowed")]
+    MissingChainId,
+
+    #[error("Invalid chain id, expect: {0}, got: {1}")]
+    ChainIdMismatch(u64, U256),
+
+    #[error("Intrinsic gas too low, need: {0}, got: {1}")]
+    IntrinsicGasTooLow(u64, U256),
+
+    #[error("Exceeds block gas limit: {0}, got: {1}")]
+    GasLimitExceeded(U256, U256),
+
+    #[error("Insufficient funds for gas * price + value, balance: {0}, cost: {1}")]
+    InsufficientFunds(U256, U256),
+
+    #[error("Nonce too low, account nonce: {0}, got: {1}")]
+    NonceTooLow(U256, U256),
+
+    #[error("Replacement transaction underpriced")]
+    ReplacementUnderpriced,
+
+    #[error("Transaction underpriced, mempool is full")]
+    Underpriced,
+
+    #[error("Mempool is full")]
+    PoolFull,
+
+    #[error("Sender has too many transactions in mempool, limit: {0}")]
+    SenderSlotsFull(usize),
+}
+
+impl From<RejectReason> for RpcError {
+    fn from(reason: RejectReason) -> Self {
+        RpcError::transaction_rejected(reason.to_string())
+    }
+}
+
+/// Result of putting transaction into mempool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxAdmission {
+    Added,
+    /// Transaction is in mempool or on chain
+    AlreadyKnown,
+    /// Transaction replaced another one of same sender and nonce, hold hash of replaced one
+    Replaced(H256),
+    Rejected(RejectReason),
+}
+
+impl TxAdmission {
+    /// Map to result of `eth_sendRawTransaction`, rejection becomes `TransactionRejected`
+    pub fn into_rpc_result(self, hash: H256) -> Result<H256, RpcError> {
+        match self {
+            Self::Rejected(reason) => Err(reason.into()),
+            _ => Ok(hash),
+        }
+    }
+}
+
+/// Admission checks of transaction
+#[derive(Debug, Clone)]
+pub struct TxValidator {
+    pub chain_id: u64,
+    pub block_gas_limit: U256,
+}
+
+impl<T> From<&ConsensusGenesis<T>> for TxValidator {
+    fn from(genesis: &ConsensusGenesis<T>) -> Self {
+        Self {
+            chain_id: genesis.chain_id,
+            block_gas_limit: genesis.block_gas_limit,
+        }
+    }
+}
+
+impl TxValidator {
+    /// Checks without account state, return recovered sender
+    ///
+    /// Mempools are keyed by `tx.hash`, so it must be the hash of the transaction.
+    pub fn validate(&self, tx: &Transaction) -> Result<H160, RejectReason> {
+        let hash = tx.hash();
+
+        if tx.hash != hash {
+            return Err(RejectReason::HashMismatch(hash, tx.hash));
+        }
+
+        let sender = tx
+            .recover_from()
+            .map_err(|_| RejectReason::InvalidSignature)?;
+
+        if !tx.from.is_zero() && tx.from != sender {
+            return Err(RejectReason::SenderMismatch(sender, tx.from));
+        }
+
+        let chain_id = transaction_utils::chain_id(tx).ok_or(RejectReason::MissingChainId)?;
+
+        if chain_id != self.chain_id.into() {
+            return Err(RejectReason::ChainIdMismatch(self.chain_id, chain_id));
+        }
+
+        let intrinsic_gas = transaction_utils::intrinsic_gas(tx);
+
+        if tx.gas < intrinsic_gas.into() {
+            return Err(RejectReason::IntrinsicGasTooLow(intrinsic_gas, tx.gas));
+        }
+
+        if tx.gas > self.block_gas_limit {
+            return Err(RejectReason::GasLimitExceeded(self.block_gas_limit, tx.gas));
+        }
+
+        Ok(sender)
+    }
+
+    /// Checks with account balance and nonce
+    pub fn validate_state(
+        &self,
+        tx: &Transaction,
+        balance: U256,
+        nonce: U256,
+    ) -> Result<(), RejectReason> {
+        if tx.nonce < nonce {
+            return Err(RejectReason::NonceTooLow(nonce, tx.nonce));
+        }
+
+        let cost = transaction_utils::max_cost(tx);
+
+        if balance < cost {
+            return Err(RejectReason::InsufficientFunds(balance, cost));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers_core::{
+        types::{transaction::eip2718::TypedTransaction, TransactionRequest, H256, U256},
+        utils::rlp::Rlp,
+    };
+    use ethers_signers::{LocalWallet, Signer};
+    use fluct_jsonrpc::ErrorCode;
+
+    use crate::{RejectReason, Transaction, TxAdmission, TxValidator};
+
+    fn signed_tx(chain_id: u64, gas: u64) -> Transaction {
+        let wallet: LocalWallet =
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                .parse()
+                .unwrap();
+
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(wallet.address())
+            .nonce(1)
+            .gas(gas)
+            .gas_price(10)
+            .value(100)
+            .chain_id(chain_id)
+            .into();
+
+        let signature = wallet
+            .with_chain_id(chain_id)
+            .sign_transaction_sync(&tx)
+            .unwrap();
+        let bytes = tx.rlp_signed(&signature);
+
+        Rlp::new(&bytes).as_val().unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        let validator = TxValidator {
+            chain_id: 901,
+            block_gas_limit: 30_000_000u64.into(),
+        };
+
+        let tx = signed_tx(901, 21000);
+        let sender = validator.validate(&tx).unwrap();
+        assert_eq!(
+            sender,
+            "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+                .parse()
+                .unwrap()
+        );
+
+        assert_eq!(
+            validator.validate(&signed_tx(1, 21000)),
+            Err(RejectReason::ChainIdMismatch(901, 1.into()))
+        );
+        assert_eq!(
+            validator.validate(&signed_tx(901, 20999)),
+            Err(RejectReason::IntrinsicGasTooLow(21000, 20999.into()))
+        );
+        assert_eq!(
+            validator.validate(&signed_tx(901, 30_000_001)),
+            Err(RejectReason::GasLimitExceeded(
+                30_000_000u64.into(),
+                30_000_001u64.into()
+            ))
+        );
+
+        let mut forged = tx.clone();
+        forged.value = 1000.into();
+        assert_ne!(validator.validate(&forged), Ok(sender));
+
+        let mut forged = tx.clone();
+        forged.hash = H256::from_low_u64_be(1);
+        assert_eq!(
+            validator.validate(&forged),
+            Err(RejectReason::HashMismatch(tx.hash, forged.hash))
+        );
+
+        // cost = 21000 * 10 + 100
+        let cost = U256::from(210100);
+        assert_eq!(validator.validate_state(&tx, cost, 1.into()), Ok(()));
+        assert_eq!(
+            validator.validate_state(&tx, cost - 1, 1.into()),
+            Err(RejectReason::InsufficientFunds(cost - 1, cost))
+        );
+        assert_eq!(
+            validator.validate_state(&tx, cost, 2.into()),
+            Err(RejectReason::NonceTooLow(2.into(), 1.into()))
+        );
+    }
+
+    #[test]
+    fn test_into_rpc_result() {
+        let hash = H256::from_low_u64_be(1);
+
+        assert_eq!(
+            TxAdmission::AlreadyKnown.into_rpc_result(hash).unwrap(),
+            hash
+        );
+
+        let err = TxAdmission::Rejected(RejectReason::ReplacementUnderpriced)
+            .into_rpc_result(hash)
+            .unwrap_err();
+
+        assert_eq!(err.code, ErrorCode::TransactionRejected);
+        assert_eq!(err.message, "Replacement transaction underpriced");
+    }
+}
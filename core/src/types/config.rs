@@ -1,3 +1,5 @@
+use std::net::SocketAddr;
+
 use serde::{Deserialize, Serialize};
 
 /// Config for all service
@@ -6,4 +8,17 @@ pub struct Config {
     pub chain_state: String,
     pub genesis: String,
     pub store_state: bool,
+    /// JSONRPC server of node, not served if not set
+    #[serde(default)]
+    pub rpc: Option<RpcConfig>,
+}
+
+/// Config of JSONRPC server
+///
+/// Sequencer serves mempool calls, other calls are forwarded to execution.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RpcConfig {
+    pub listen: SocketAddr,
+    /// JSONRPC endpoint of execution
+    pub upstream: String,
 }
@@ -21,6 +21,16 @@ pub struct PayloadStatus {
     validation_error: Option<String>,
 }
 
+impl PayloadStatus {
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+
+    pub fn validation_error(&self) -> Option<&str> {
+        self.validation_error.as_deref()
+    }
+}
+
 /// Result of fork choice
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -28,3 +38,14 @@ pub struct ForkChoiceResult {
     payload_status: PayloadStatus,
     payload_id: Option<Bytes>,
 }
+
+impl ForkChoiceResult {
+    pub fn payload_status(&self) -> &PayloadStatus {
+        &self.payload_status
+    }
+
+    /// Id of payload being built, only returned when payload attributes given
+    pub fn payload_id(&self) -> Option<&Bytes> {
+        self.payload_id.as_ref()
+    }
+}
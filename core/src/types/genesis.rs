@@ -1,7 +1,7 @@
-use ethers_core::types::{Bytes, H256, U256};
+use ethers_core::types::{Bytes, H160, H256, U256};
 use serde::{Deserialize, Serialize};
 
-use crate::{transaction_utils, Error, Transaction};
+use crate::{transaction_utils, Error, ExecutionGenesis, Transaction};
 
 /// Genesis for all service
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,12 +15,17 @@ pub struct Genesis<T, E> {
 pub struct ConsensusGenesis<T> {
     pub chain_id: u64,
     pub earliest_block_height: u64,
+    /// Derived from execution genesis if it is zero and earliest block height is 0.
+    #[serde(default)]
     pub earliest_block_hash: H256,
     pub extra_data: Bytes,
     pub block_gas_limit: U256,
     pub block_block_size: u64,
     pub transactions: Vec<T>,
     pub timestamp: u64,
+    /// Fixed validator set, used by BFT consensus.
+    #[serde(default)]
+    pub validators: Vec<H160>,
 }
 
 impl<E> Genesis<Bytes, E> {
@@ -35,6 +40,38 @@ impl<E> Genesis<Bytes, E> {
     }
 }
 
+impl<T, E> Genesis<T, E>
+where
+    E: ExecutionGenesis,
+{
+    /// Fill earliest block hash from execution genesis, or check it if already set.
+    ///
+    /// Only work when chain starts from block 0.
+    pub fn derive_earliest_block_hash(&mut self) -> Result<(), Error> {
+        if self.consensus.earliest_block_height != 0 {
+            return Ok(());
+        }
+
+        let hash = self.execution.block_hash();
+
+        if self.consensus.earliest_block_hash.is_zero() {
+            self.consensus.earliest_block_hash = hash;
+        } else if self.consensus.earliest_block_hash != hash {
+            return Err(Error::GenesisHashMismatch(
+                self.consensus.earliest_block_hash,
+                hash,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Check execution genesis is consistent with consensus genesis
+    pub fn validate(&self) -> Result<(), Error> {
+        self.execution.validate_with(&self.consensus)
+    }
+}
+
 impl From<(ConsensusGenesis<Bytes>, Vec<Transaction>)> for ConsensusGenesis<Transaction> {
     fn from(value: (ConsensusGenesis<Bytes>, Vec<Transaction>)) -> Self {
         Self {
@@ -46,6 +83,7 @@ impl From<(ConsensusGenesis<Bytes>, Vec<Transaction>)> for ConsensusGenesis<Tran
             block_block_size: value.0.block_block_size,
             transactions: value.1,
             timestamp: value.0.timestamp,
+            validators: value.0.validators,
         }
     }
 }
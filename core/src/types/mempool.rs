@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use ethers_core::types::H256;
+
+/// Limits of mempool
+#[derive(Debug, Clone)]
+pub struct MempoolLimits {
+    /// Max count of transactions
+    pub max_count: usize,
+    /// Max bytes of RLP encoded transactions
+    pub max_bytes: usize,
+    /// Max count of transactions of one sender
+    pub max_per_sender: usize,
+    /// Unconfirmed transaction is dropped after this time
+    pub ttl: Duration,
+}
+
+impl Default for MempoolLimits {
+    fn default() -> Self {
+        Self {
+            max_count: 5120,
+            max_bytes: 32 * 1024 * 1024,
+            max_per_sender: 64,
+            ttl: Duration::from_secs(3 * 3600),
+        }
+    }
+}
+
+/// Metrics of mempool
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MempoolMetrics {
+    /// Count of transactions in pool
+    pub count: usize,
+    /// Bytes of transactions in pool
+    pub bytes: usize,
+    /// Transactions dropped after time to live
+    pub expired: u64,
+    /// Transactions evicted to make room for new ones
+    pub evicted: u64,
+    /// Transactions rejected because pool is full
+    pub rejected_pool_full: u64,
+    /// Transactions rejected because sender used all slots
+    pub rejected_sender_full: u64,
+}
+
+/// Reason of dropping transaction without execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictReason {
+    /// Lived longer than time to live
+    Expired,
+    /// Evicted to make room for another transaction
+    PoolFull,
+    /// Nonce is used by an executed transaction
+    Outdated,
+    /// Can't be executed with account state, like balance isn't enough
+    Invalid,
+}
+
+/// Event of transaction entering or leaving mempool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxEvent {
+    Added(H256),
+    Replaced { old: H256, new: H256 },
+    Confirmed(H256),
+    Evicted(H256, EvictReason),
+}
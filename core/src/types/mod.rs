@@ -16,5 +16,14 @@ pub use genesis::*;
 mod config;
 pub use config::*;
 
+mod admission;
+pub use admission::*;
+
+mod mempool;
+pub use mempool::*;
+
+mod preconf;
+pub use preconf::*;
+
 mod error;
 pub use error::*;
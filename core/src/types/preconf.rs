@@ -0,0 +1,92 @@
+use ethers_core::{
+    types::{Signature, SignatureError, H160, H256},
+    utils::{keccak256, rlp::RlpStream},
+};
+use serde::{Deserialize, Serialize};
+
+/// Domain of pre-confirmation digest, a signature can't be replayed as another message
+pub const PRECONF_DOMAIN: &[u8] = b"fluct-preconfirmation-v1";
+
+/// Promise of sequencer to include transaction at a position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreConfirmation {
+    /// Chain the promise is made on
+    pub chain_id: u64,
+    pub tx_hash: H256,
+    /// Position of transaction in sequence
+    pub index: u64,
+    /// Block transaction is expected to be included
+    pub block_number: u64,
+    /// EIP-191 signature of `digest` by sequencer
+    pub signature: Signature,
+}
+
+impl PreConfirmation {
+    /// Message to sign, `keccak256(rlp([PRECONF_DOMAIN, chain_id, tx_hash, index, block_number]))`
+    pub fn digest(chain_id: u64, tx_hash: H256, index: u64, block_number: u64) -> H256 {
+        let mut s = RlpStream::new_list(5);
+        s.append(&PRECONF_DOMAIN);
+        s.append(&chain_id);
+        s.append(&tx_hash);
+        s.append(&index);
+        s.append(&block_number);
+
+        keccak256(s.out()).into()
+    }
+
+    fn own_digest(&self) -> H256 {
+        Self::digest(self.chain_id, self.tx_hash, self.index, self.block_number)
+    }
+
+    /// Address signed the pre-confirmation
+    pub fn signer(&self) -> Result<H160, SignatureError> {
+        self.signature.recover(self.own_digest().as_bytes())
+    }
+
+    /// Check pre-confirmation is signed by sequencer
+    pub fn verify(&self, sequencer: H160) -> Result<(), SignatureError> {
+        self.signature
+            .verify(self.own_digest().as_bytes(), sequencer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers_core::types::H256;
+    use ethers_signers::{LocalWallet, Signer};
+
+    use crate::PreConfirmation;
+
+    #[test]
+    fn test_verify() {
+        let wallet = LocalWallet::from_bytes(H256::from_low_u64_be(1).as_bytes()).unwrap();
+
+        let tx_hash = H256::from_low_u64_be(2);
+        let digest = PreConfirmation::digest(901, tx_hash, 3, 4);
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let signature = rt.block_on(wallet.sign_message(digest)).unwrap();
+
+        let mut preconf = PreConfirmation {
+            chain_id: 901,
+            tx_hash,
+            index: 3,
+            block_number: 4,
+            signature,
+        };
+
+        assert_eq!(preconf.signer().unwrap(), wallet.address());
+        assert!(preconf.verify(wallet.address()).is_ok());
+
+        preconf.index = 5;
+        assert!(preconf.verify(wallet.address()).is_err());
+
+        // same promise on another chain
+        preconf.index = 3;
+        preconf.chain_id = 1;
+        assert!(preconf.verify(wallet.address()).is_err());
+    }
+}
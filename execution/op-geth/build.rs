@@ -1,9 +1,107 @@
+//! Prepare op-geth binary for embedding.
+//!
+//! When feature `embed` is enabled, the binary is placed in `$OUT_DIR/bin/geth`:
+//!
+//! - `FLUCT_GETH_BIN`: Copy binary from this local path instead of downloading it.
+//! - `FLUCT_GETH_SHA256`: Expected sha256 (hex) of binary, build fails when mismatch.
+//!
+//! When feature `embed` is disabled, nothing is embedded and `geth` is resolved at runtime.
+//!
+//! Deployed bytecode of OP Stack predeploys is embedded from `$OUT_DIR/predeploys.rs`:
+//!
+//! - `FLUCT_PREDEPLOY_ARTIFACTS`: `forge-artifacts` directory of `contracts-bedrock`. Build
+//!   fails if proxy or a predeploy required by OP Stack is missing in it.
+
 use std::{
     env,
     fs::{self, OpenOptions},
     path::Path,
 };
 
+use sha2::{Digest, Sha256};
+
+/// Contracts embedded for predeploys, same as `Predeploy::ALL` and `Proxy`
+const PREDEPLOY_CONTRACTS: [&str; 18] = [
+    "Proxy",
+    "LegacyMessagePasser",
+    "DeployerWhitelist",
+    "WETH9",
+    "L2CrossDomainMessenger",
+    "GasPriceOracle",
+    "L2StandardBridge",
+    "SequencerFeeVault",
+    "OptimismMintableERC20Factory",
+    "L1BlockNumber",
+    "L2ERC721Bridge",
+    "L1Block",
+    "L2ToL1MessagePasser",
+    "OptimismMintableERC721Factory",
+    "ProxyAdmin",
+    "BaseFeeVault",
+    "L1FeeVault",
+    "GovernanceToken",
+];
+
+/// Contracts OP Stack can't work without, same as `Predeploy::is_required` and `Proxy`
+const REQUIRED_CONTRACTS: [&str; 5] = [
+    "Proxy",
+    "ProxyAdmin",
+    "L1Block",
+    "GasPriceOracle",
+    "L2ToL1MessagePasser",
+];
+
+/// Extract deployed bytecode from forge artifacts, and generate list of `include_bytes!`
+fn embed_predeploys(out_dir: &Path) {
+    println!("cargo:rerun-if-env-changed=FLUCT_PREDEPLOY_ARTIFACTS");
+
+    let target = out_dir.join("predeploys");
+    fs::create_dir_all(&target).unwrap();
+
+    let mut entries = Vec::new();
+
+    if let Ok(dir) = env::var("FLUCT_PREDEPLOY_ARTIFACTS") {
+        println!("cargo:rerun-if-changed={}", dir);
+
+        for name in PREDEPLOY_CONTRACTS {
+            let path = Path::new(&dir)
+                .join(format!("{name}.sol"))
+                .join(format!("{name}.json"));
+
+            let Ok(data) = fs::read(&path) else {
+                if REQUIRED_CONTRACTS.contains(&name) {
+                    panic!("Required predeploy {} not found in {}", name, dir);
+                }
+
+                println!("cargo:warning=Predeploy {} not found in {}", name, dir);
+                continue;
+            };
+
+            let artifact: serde_json::Value = serde_json::from_slice(&data)
+                .unwrap_or_else(|e| panic!("Invalid artifact {}: {}", path.display(), e));
+
+            let object = artifact["deployedBytecode"]["object"]
+                .as_str()
+                .unwrap_or_else(|| panic!("No deployed bytecode in {}", path.display()));
+
+            let bytecode = hex::decode(object.trim_start_matches("0x"))
+                .unwrap_or_else(|e| panic!("Invalid bytecode in {}: {}", path.display(), e));
+
+            let bin = target.join(format!("{name}.bin"));
+            fs::write(&bin, bytecode).unwrap();
+
+            entries.push(format!("    ({:?}, include_bytes!({:?})),", name, bin));
+        }
+    }
+
+    let code = format!(
+        "pub const EMBEDDED_PREDEPLOYS: &[(&str, &[u8])] = &[\n{}\n];\n",
+        entries.join("\n")
+    );
+
+    fs::write(out_dir.join("predeploys.rs"), code).unwrap();
+}
+
 pub const UPSTREAM_URL: &str =
     "https://media.githubusercontent.com/media/tiannian/go-prebuild-binary/main/op-geth/geth-v1.101200.0";
 
@@ -11,16 +109,36 @@ fn main() {
     #[cfg(unix)]
     use std::os::unix::fs::OpenOptionsExt;
 
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=FLUCT_GETH_BIN");
+    println!("cargo:rerun-if-env-changed=FLUCT_GETH_SHA256");
+
     let out_dir = env::var("OUT_DIR").unwrap();
 
+    embed_predeploys(Path::new(&out_dir));
+
+    if env::var_os("CARGO_FEATURE_EMBED").is_none() {
+        return;
+    }
+
     let target = Path::new(&out_dir).join("bin");
 
     fs::create_dir_all(&target).unwrap();
 
     let target = target.join("geth");
 
-    if !target.exists() {
-        let response = attohttpc::get(UPSTREAM_URL).send().unwrap();
+    if let Ok(local) = env::var("FLUCT_GETH_BIN") {
+        println!("cargo:rerun-if-changed={}", local);
+
+        fs::copy(&local, &target)
+            .unwrap_or_else(|e| panic!("Failed to copy geth binary from {}: {}", local, e));
+    } else if !target.exists() {
+        let response = attohttpc::get(UPSTREAM_URL).send().unwrap_or_else(|e| {
+            panic!(
+                "Failed to download geth binary: {}, set FLUCT_GETH_BIN to build offline",
+                e
+            )
+        });
 
         let mut open = OpenOptions::new();
         open.write(true).create(true);
@@ -28,8 +146,29 @@ fn main() {
         #[cfg(unix)]
         open.mode(0o776);
 
-        let file = open.open(target).unwrap();
+        let file = open.open(&target).unwrap();
 
         response.write_to(file).unwrap();
     }
+
+    let data = fs::read(&target).unwrap();
+    let actual = hex::encode(Sha256::digest(data));
+
+    match env::var("FLUCT_GETH_SHA256") {
+        Ok(expect) => {
+            if !expect.trim().eq_ignore_ascii_case(&actual) {
+                let _ = fs::remove_file(&target);
+
+                panic!(
+                    "Checksum of geth binary mismatch, expect: {}, got: {}",
+                    expect.trim(),
+                    actual
+                );
+            }
+        }
+        Err(_) => println!(
+            "cargo:warning=FLUCT_GETH_SHA256 not set, sha256 of embedded geth is {}",
+            actual
+        ),
+    }
 }
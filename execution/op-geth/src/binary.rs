@@ -0,0 +1,235 @@
+//! Locate and inspect geth binary
+
+use std::{
+    env,
+    fmt::{self, Display},
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use sha2::{Digest, Sha256};
+use subprocess::{Popen, PopenConfig, Redirection};
+
+use crate::{config::GethBinary, Error, Result};
+
+/// Environment variable of geth binary path
+pub const GETH_BIN_ENV: &str = "FLUCT_GETH_BIN";
+
+/// Environment variable of expected geth binary sha256
+pub const GETH_SHA256_ENV: &str = "FLUCT_GETH_SHA256";
+
+/// Compute sha256 of file, encoded as hex
+pub fn sha256_of(path: &Path) -> Result<String> {
+    let data = fs::read(path)?;
+
+    Ok(hex::encode(Sha256::digest(data)))
+}
+
+/// Verify checksum of file
+pub fn verify_checksum(path: &Path, expect: &str) -> Result<()> {
+    let actual = sha256_of(path)?;
+    let expect = expect.trim();
+
+    if expect.eq_ignore_ascii_case(&actual) {
+        Ok(())
+    } else {
+        Err(Error::ChecksumMismatch(expect.to_string(), actual))
+    }
+}
+
+/// Find executable in `PATH`
+pub fn find_in_path(name: &str) -> Option<PathBuf> {
+    let paths = env::var_os("PATH")?;
+
+    env::split_paths(&paths)
+        .map(|p| p.join(name))
+        .find(|p| p.is_file())
+}
+
+#[cfg(feature = "embed")]
+#[derive(rust_embed::RustEmbed)]
+#[folder = "$OUT_DIR/bin/"]
+#[include = "geth"]
+struct Assets;
+
+#[cfg(feature = "embed")]
+fn install_embedded(work_dir: &Path) -> Result<PathBuf> {
+    let bin_path = work_dir.join("geth");
+
+    let bin = Assets::get("geth").ok_or(Error::NoGethBinaryFound)?;
+
+    fs::write(&bin_path, bin.data)?;
+
+    #[cfg(unix)]
+    {
+        use std::{
+            fs::{metadata, set_permissions},
+            os::unix::fs::PermissionsExt,
+        };
+
+        let mut permission = metadata(&bin_path)?.permissions();
+        permission.set_mode(0o755);
+        set_permissions(&bin_path, permission)?;
+    }
+
+    Ok(bin_path)
+}
+
+/// Get path of geth binary, embedded binary is written into `work_dir`
+///
+/// Binary which isn't embedded is verified if `FLUCT_GETH_SHA256` is set.
+pub(crate) fn install(binary: &GethBinary, _work_dir: &Path) -> Result<PathBuf> {
+    let path = match binary {
+        #[cfg(feature = "embed")]
+        GethBinary::Embedded => return install_embedded(_work_dir),
+        GethBinary::Path(p) => p.clone(),
+        GethBinary::System => match env::var_os(GETH_BIN_ENV) {
+            Some(p) => PathBuf::from(p),
+            None => find_in_path("geth").ok_or(Error::NoGethBinaryFound)?,
+        },
+    };
+
+    if !path.is_file() {
+        return Err(Error::NoGethBinaryFound);
+    }
+
+    if let Ok(expect) = env::var(GETH_SHA256_ENV) {
+        verify_checksum(&path, &expect)?;
+    }
+
+    Ok(path)
+}
+
+/// Version of geth
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GethVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    /// Suffix like `stable` or `unstable`, not used in comparing
+    pub meta: Option<String>,
+}
+
+impl GethVersion {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            meta: None,
+        }
+    }
+
+    /// Parse output of `geth version`
+    pub fn from_output(output: &str) -> Result<Self> {
+        let line = output
+            .lines()
+            .find_map(|l| l.trim().strip_prefix("Version:"))
+            .ok_or_else(|| Error::VersionParseError(output.to_string()))?;
+
+        line.trim().parse()
+    }
+
+    fn cmp_key(&self) -> (u64, u64, u64) {
+        (self.major, self.minor, self.patch)
+    }
+
+    /// Is this version equal or newer than other, ignore meta
+    pub fn at_least(&self, other: &GethVersion) -> bool {
+        self.cmp_key() >= other.cmp_key()
+    }
+}
+
+impl FromStr for GethVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let err = || Error::VersionParseError(s.to_string());
+
+        let (version, meta) = match s.split_once('-') {
+            Some((v, m)) => (v, Some(m.to_string())),
+            None => (s, None),
+        };
+
+        let mut parts = version.trim_start_matches('v').split('.');
+
+        let mut next =
+            || -> Result<u64> { parts.next().ok_or_else(err)?.parse().map_err(|_| err()) };
+
+        let major = next()?;
+        let minor = next()?;
+        let patch = next()?;
+
+        Ok(Self {
+            major,
+            minor,
+            patch,
+            meta,
+        })
+    }
+}
+
+impl Display for GethVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+
+        if let Some(meta) = &self.meta {
+            write!(f, "-{}", meta)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Get version of geth binary by running `geth version`
+pub fn version(bin: &Path) -> Result<GethVersion> {
+    let config = PopenConfig {
+        stdout: Redirection::Pipe,
+        stderr: Redirection::Pipe,
+        ..Default::default()
+    };
+
+    let mut r = Popen::create(&[bin.as_os_str(), "version".as_ref()], config)?;
+
+    let mut out = String::new();
+    if let Some(f) = &mut r.stdout {
+        f.read_to_string(&mut out)?;
+    }
+
+    if r.wait()?.success() {
+        GethVersion::from_output(&out)
+    } else {
+        let mut buf = String::new();
+
+        if let Some(f) = &mut r.stderr {
+            f.read_to_string(&mut buf)?;
+        }
+
+        Err(Error::SubprocessExecuteError(buf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GethVersion;
+
+    #[test]
+    fn test_parse_version() {
+        let output = "Geth\nVersion: 1.101200.0-stable\nGit Commit: 0000\nArchitecture: amd64\n";
+
+        let v = GethVersion::from_output(output).unwrap();
+
+        assert_eq!(v.major, 1);
+        assert_eq!(v.minor, 101200);
+        assert_eq!(v.patch, 0);
+        assert_eq!(v.meta.as_deref(), Some("stable"));
+        assert_eq!(v.to_string(), "1.101200.0-stable");
+
+        assert!(v.at_least(&GethVersion::new(1, 101200, 0)));
+        assert!(!v.at_least(&GethVersion::new(1, 101301, 0)));
+
+        assert!(GethVersion::from_output("Geth\n").is_err());
+    }
+}
@@ -5,6 +5,8 @@ use std::{
     path::{Path, PathBuf},
 };
 
+use crate::binary::GethVersion;
+
 /// Http Configure
 pub struct Http {
     pub listen: SocketAddr,
@@ -48,9 +50,39 @@ impl GcMode {
     }
 }
 
+/// Where to get geth binary
+#[derive(Debug, Clone)]
+pub enum GethBinary {
+    /// Binary embedded in library
+    #[cfg(feature = "embed")]
+    Embedded,
+    /// Binary at explicit path
+    Path(PathBuf),
+    /// Binary from `FLUCT_GETH_BIN` or discovered on `PATH`
+    System,
+}
+
+impl Default for GethBinary {
+    #[cfg(feature = "embed")]
+    fn default() -> Self {
+        Self::Embedded
+    }
+
+    #[cfg(not(feature = "embed"))]
+    fn default() -> Self {
+        Self::System
+    }
+}
+
 /// Configure for OpGeth
 pub struct Config {
+    pub binary: GethBinary,
+    pub min_version: Option<GethVersion>,
     pub datadir: PathBuf,
+    /// Directory to store jwt key and other files, default is `datadir/fluct`
+    pub work_dir: Option<PathBuf>,
+    /// JWT secret of engine api, reuse or generate key in work dir if unset
+    pub jwt_secret: Option<[u8; 32]>,
     pub http: Option<Http>,
     pub ws: Option<Ws>,
     pub chainid: u64,
@@ -60,7 +92,11 @@ pub struct Config {
 impl Config {
     pub fn new(chainid: u64, datadir: impl AsRef<Path>) -> Self {
         Self {
+            binary: GethBinary::default(),
+            min_version: None,
             datadir: datadir.as_ref().to_path_buf(),
+            work_dir: None,
+            jwt_secret: None,
             http: Some(Http::default()),
             ws: None,
             chainid,
@@ -1,17 +1,41 @@
+use ethers_core::types::U256;
 use thiserror::Error;
 
+use crate::genesis::Fork;
+
 /// OpGeth Error
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("No Geth Binary Found")]
     NoGethBinaryFound,
 
+    #[error("Checksum of geth binary mismatch, expect: {0}, got: {1}")]
+    ChecksumMismatch(String, String),
+
+    #[error("Failed to parse geth version: {0}")]
+    VersionParseError(String),
+
+    #[error("Unsupported geth version: {0}, require at least: {1}")]
+    UnsupportedVersion(crate::binary::GethVersion, crate::binary::GethVersion),
+
     #[error("Execute Error: {0}")]
     SubprocessExecuteError(String),
 
     #[error("Failed to convert path to utf8")]
     FailedToGetUTF8Path,
 
+    #[error("Genesis mismatch, stored: {0:?}, expect: {1:?}")]
+    GenesisMismatch(ethers_core::types::H256, ethers_core::types::H256),
+
+    #[error("Genesis hash computed by geth: {1} is not same as fluct: {0:?}")]
+    GenesisHashMismatch(ethers_core::types::H256, String),
+
+    #[error("Invalid genesis hash file: {0}")]
+    InvalidGenesisHashFile(String),
+
+    #[error("Invalid jwt secret, expect 32 bytes hex")]
+    InvalidJwtSecret,
+
     #[error("No instance start")]
     NoInstanceStart,
 
@@ -26,6 +50,43 @@ pub enum Error {
 
     #[error(transparent)]
     JsonError(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    GenesisError(#[from] GenesisError),
+
+    #[error(transparent)]
+    WalletError(#[from] ethers_signers::WalletError),
+}
+
+/// Genesis validation error
+#[derive(Debug, Error)]
+pub enum GenesisError {
+    #[error("Chain id mismatch, execution: {0}, consensus: {1}")]
+    ChainIdMismatch(u64, u64),
+
+    #[error("Gas limit {0} is lower than minimum {1}")]
+    GasLimitTooLow(U256, u64),
+
+    #[error("Gas limit {0} is higher than maximum {1}")]
+    GasLimitTooHigh(U256, u64),
+
+    #[error("Genesis {0} {1} exceeds u64")]
+    Overflow(&'static str, U256),
+
+    #[error("Block gas limit of consensus {0} exceeds genesis gas limit {1}")]
+    BlockGasLimitExceeded(U256, U256),
+
+    #[error("Fork {0:?} is enabled but required fork {1:?} is not")]
+    MissingFork(Fork, Fork),
+
+    #[error("Unsupported fork ordering, {0:?} enabled at {1}, but {2:?} enabled at {3}")]
+    ForkOrder(Fork, u64, Fork, u64),
+
+    #[error("Canyon is enabled but eip1559DenominatorCanyon is missing")]
+    MissingCanyonDenominator,
+
+    #[error("Missing deployed bytecode of predeploy: {0}")]
+    MissingPredeployBytecode(String),
 }
 
 /// Result alias of OpGeth Error
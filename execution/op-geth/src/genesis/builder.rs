@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use ethers_core::types::{Bytes, H160, H256, U256};
+
+use crate::GenesisError;
+
+use super::{AllocItem, ChainConfig, Genesis, OpStackPredeploys};
+
+/// Default chain id of builder
+pub const DEFAULT_CHAIN_ID: u64 = 43965;
+
+/// Default gas limit of builder
+pub const DEFAULT_BUILDER_GAS_LIMIT: u64 = 30_000_000;
+
+/// Address of SequencerFeeVault, default coinbase
+pub const SEQUENCER_FEE_VAULT: H160 = H160([
+    0x42, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x11,
+]);
+
+/// Builder of genesis
+///
+/// ```
+/// use fluct_op_geth::Genesis;
+///
+/// let genesis = Genesis::builder()
+///     .chain_id(901)
+///     .fund([1u8; 20].into(), 1_000_000_000_000_000_000u64)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(genesis.config.chain_id(), 901);
+/// ```
+pub struct GenesisBuilder {
+    genesis: Genesis,
+    predeploys: Option<OpStackPredeploys>,
+}
+
+impl Default for GenesisBuilder {
+    fn default() -> Self {
+        Self {
+            genesis: Genesis {
+                config: ChainConfig::new(DEFAULT_CHAIN_ID),
+                nonce: U256::zero(),
+                timestamp: U256::zero(),
+                extra_data: Bytes::default(),
+                gas_limit: DEFAULT_BUILDER_GAS_LIMIT.into(),
+                difficulty: U256::zero(),
+                mix_hash: H256::zero(),
+                coinbase: SEQUENCER_FEE_VAULT,
+                base_fee_per_gas: None,
+                alloc: HashMap::new(),
+            },
+            predeploys: None,
+        }
+    }
+}
+
+impl GenesisBuilder {
+    /// Set chain id, keep other chain config
+    pub fn chain_id(mut self, chain_id: u64) -> Self {
+        self.genesis.config.set_chain_id(chain_id);
+        self
+    }
+
+    /// Replace chain config
+    pub fn config(mut self, config: ChainConfig) -> Self {
+        self.genesis.config = config;
+        self
+    }
+
+    /// Modify chain config, like fork times
+    pub fn with_config(mut self, f: impl FnOnce(&mut ChainConfig)) -> Self {
+        f(&mut self.genesis.config);
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.genesis.timestamp = timestamp.into();
+        self
+    }
+
+    pub fn gas_limit(mut self, gas_limit: impl Into<U256>) -> Self {
+        self.genesis.gas_limit = gas_limit.into();
+        self
+    }
+
+    pub fn extra_data(mut self, extra_data: impl Into<Bytes>) -> Self {
+        self.genesis.extra_data = extra_data.into();
+        self
+    }
+
+    pub fn coinbase(mut self, coinbase: H160) -> Self {
+        self.genesis.coinbase = coinbase;
+        self
+    }
+
+    pub fn base_fee(mut self, base_fee: impl Into<U256>) -> Self {
+        self.genesis.base_fee_per_gas = Some(base_fee.into());
+        self
+    }
+
+    /// Add balance to account
+    pub fn fund(mut self, address: H160, wei: impl Into<U256>) -> Self {
+        let item = self.genesis.alloc.entry(address).or_default();
+        item.balance += wei.into();
+        self
+    }
+
+    /// Deploy code with storage at address, keep balance of account
+    pub fn deploy(
+        mut self,
+        address: H160,
+        code: impl Into<Bytes>,
+        storage: HashMap<H256, H256>,
+    ) -> Self {
+        let item = self.genesis.alloc.entry(address).or_default();
+        item.code = code.into();
+        item.storage = storage;
+        self
+    }
+
+    /// Replace account
+    pub fn alloc(mut self, address: H160, item: AllocItem) -> Self {
+        self.genesis.alloc.insert(address, item);
+        self
+    }
+
+    /// Inject OP Stack predeploys when building
+    pub fn predeploys(mut self, predeploys: OpStackPredeploys) -> Self {
+        self.predeploys = Some(predeploys);
+        self
+    }
+
+    /// Build and validate genesis
+    pub fn build(self) -> Result<Genesis, GenesisError> {
+        let mut genesis = self.genesis;
+
+        if let Some(predeploys) = &self.predeploys {
+            predeploys.apply(&mut genesis)?;
+        }
+
+        genesis.validate()?;
+
+        Ok(genesis)
+    }
+}
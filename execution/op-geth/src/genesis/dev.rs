@@ -0,0 +1,50 @@
+use ethers_core::types::{H160, H256, U256};
+use ethers_signers::{coins_bip39::English, MnemonicBuilder, Signer};
+
+use crate::Result;
+
+use super::GenesisBuilder;
+
+/// Mnemonic of default dev accounts, same as anvil and hardhat
+pub const DEFAULT_DEV_MNEMONIC: &str =
+    "test test test test test test test test test test test junk";
+
+/// Balance of each dev account, 10000 ETH
+pub const DEFAULT_DEV_BALANCE: u128 = 10_000_000_000_000_000_000_000;
+
+/// Funded account for local testing
+#[derive(Debug, Clone)]
+pub struct DevAccount {
+    pub address: H160,
+    pub private_key: H256,
+}
+
+/// Derive accounts at `m/44'/60'/0'/0/{index}` from BIP-39 mnemonic
+pub fn dev_accounts(mnemonic: &str, count: u32) -> Result<Vec<DevAccount>> {
+    let mut accounts = Vec::with_capacity(count as usize);
+
+    for index in 0..count {
+        let wallet = MnemonicBuilder::<English>::default()
+            .phrase(mnemonic)
+            .index(index)?
+            .build()?;
+
+        accounts.push(DevAccount {
+            address: wallet.address(),
+            private_key: H256::from_slice(&wallet.signer().to_bytes()),
+        });
+    }
+
+    Ok(accounts)
+}
+
+impl GenesisBuilder {
+    /// Fund dev accounts with balance
+    pub fn dev_accounts(self, accounts: &[DevAccount], balance: impl Into<U256>) -> Self {
+        let balance = balance.into();
+
+        accounts.iter().fold(self, |builder, account| {
+            builder.fund(account.address, balance)
+        })
+    }
+}
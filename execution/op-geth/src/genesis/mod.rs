@@ -0,0 +1,898 @@
+//! Genesis types
+
+use std::collections::{BTreeMap, HashMap};
+
+use ethers_core::types::{Bytes, H160, H256, H64, U256};
+use fluct_core::{
+    state_utils, BlockHeader, ConsensusGenesis, EthereumHeader, ExecutionGenesis, Header,
+    StateAccount,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::GenesisError;
+
+mod builder;
+pub use builder::*;
+
+mod predeploy;
+pub use predeploy::*;
+
+mod dev;
+pub use dev::*;
+
+/// Gas limit used by geth when genesis gas limit is 0
+pub const DEFAULT_GAS_LIMIT: u64 = 4712388;
+
+/// Minimum gas limit of block
+pub const MIN_GAS_LIMIT: u64 = 5000;
+
+/// Maximum gas limit of block
+pub const MAX_GAS_LIMIT: u64 = i64::MAX as u64;
+
+/// Base fee used by geth when london is active at genesis without base fee
+pub const INITIAL_BASE_FEE: u64 = 1_000_000_000;
+
+/// Chain configure for optimism
+#[derive(Debug, Clone, Deserialize, Serialize, getset::CopyGetters, getset::Setters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get_copy = "pub", set = "pub")]
+pub struct Optimism {
+    eip1559_elasticity: u64,
+    eip1559_denominator: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    eip1559_denominator_canyon: Option<u64>,
+}
+
+/// Hardfork of op-geth, in activation order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Fork {
+    Frontier,
+    Homestead,
+    Tangerine,
+    SpuriousDragon,
+    Byzantium,
+    Constantinople,
+    Petersburg,
+    Istanbul,
+    MuirGlacier,
+    Berlin,
+    London,
+    ArrowGlacier,
+    GrayGlacier,
+    Paris,
+    Bedrock,
+    Regolith,
+    Shanghai,
+    Canyon,
+    Cancun,
+    Delta,
+    Ecotone,
+    Fjord,
+}
+
+impl Optimism {
+    pub fn new(eip1559_elasticity: u64, eip1559_denominator: u64) -> Self {
+        Self {
+            eip1559_elasticity,
+            eip1559_denominator,
+            eip1559_denominator_canyon: None,
+        }
+    }
+}
+
+/// Chain configure
+///
+/// Fork is disabled if its block or time is unset.
+#[derive(
+    Debug, Clone, Deserialize, Serialize, getset::CopyGetters, getset::Getters, getset::Setters,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainConfig {
+    #[getset(get_copy = "pub", set = "pub")]
+    chain_id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    homestead_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    eip150_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    eip155_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    eip158_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    byzantium_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    constantinople_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    petersburg_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    istanbul_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    muir_glacier_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    berlin_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    london_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    arrow_glacier_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    gray_glacier_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    merge_netsplit_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    shanghai_time: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    cancun_time: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    bedrock_block: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    regolith_time: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    canyon_time: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    delta_time: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    ecotone_time: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    fjord_time: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get_copy = "pub", set = "pub")]
+    terminal_total_difficulty: Option<u128>,
+    #[serde(default)]
+    #[getset(get_copy = "pub", set = "pub")]
+    terminal_total_difficulty_passed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[getset(get = "pub", set = "pub")]
+    optimism: Option<Optimism>,
+}
+
+fn check_order(forks: &[(Fork, Option<u64>, bool)]) -> Result<(), GenesisError> {
+    let mut last: Option<(Fork, Option<u64>)> = None;
+
+    for (fork, activation, optional) in forks {
+        if let Some((last_fork, last_activation)) = last {
+            match (last_activation, activation) {
+                (None, Some(_)) => return Err(GenesisError::MissingFork(*fork, last_fork)),
+                (Some(l), Some(c)) if l > *c => {
+                    return Err(GenesisError::ForkOrder(last_fork, l, *fork, *c))
+                }
+                _ => {}
+            }
+        }
+
+        if !optional || activation.is_some() {
+            last = Some((*fork, *activation));
+        }
+    }
+
+    Ok(())
+}
+
+fn is_block_active(block: Option<u64>, number: u64) -> bool {
+    block.map(|b| number >= b).unwrap_or(false)
+}
+
+fn is_time_active(time: Option<u64>, timestamp: u64) -> bool {
+    time.map(|t| timestamp >= t).unwrap_or(false)
+}
+
+impl ChainConfig {
+    /// Optimism chain with all forks until regolith active at genesis
+    pub fn new(chain_id: u64) -> Self {
+        Self {
+            chain_id,
+            homestead_block: Some(0),
+            eip150_block: Some(0),
+            eip155_block: Some(0),
+            eip158_block: Some(0),
+            byzantium_block: Some(0),
+            constantinople_block: Some(0),
+            petersburg_block: Some(0),
+            istanbul_block: Some(0),
+            muir_glacier_block: Some(0),
+            berlin_block: Some(0),
+            london_block: Some(0),
+            arrow_glacier_block: Some(0),
+            gray_glacier_block: Some(0),
+            merge_netsplit_block: Some(0),
+            shanghai_time: None,
+            cancun_time: None,
+            bedrock_block: Some(0),
+            regolith_time: Some(0),
+            canyon_time: None,
+            delta_time: None,
+            ecotone_time: None,
+            fjord_time: None,
+            terminal_total_difficulty: Some(0),
+            terminal_total_difficulty_passed: true,
+            optimism: Some(Optimism::new(10, 50)),
+        }
+    }
+
+    /// Check forks are enabled in order, same as geth's `CheckConfigForkOrder`
+    pub fn check_fork_order(&self) -> Result<(), GenesisError> {
+        // (fork, activation, optional)
+        let block_forks = [
+            (Fork::Homestead, self.homestead_block, false),
+            (Fork::Tangerine, self.eip150_block, false),
+            (Fork::SpuriousDragon, self.eip155_block, false),
+            (Fork::SpuriousDragon, self.eip158_block, false),
+            (Fork::Byzantium, self.byzantium_block, false),
+            (Fork::Constantinople, self.constantinople_block, false),
+            (Fork::Petersburg, self.petersburg_block, false),
+            (Fork::Istanbul, self.istanbul_block, false),
+            (Fork::MuirGlacier, self.muir_glacier_block, true),
+            (Fork::Berlin, self.berlin_block, false),
+            (Fork::London, self.london_block, false),
+            (Fork::ArrowGlacier, self.arrow_glacier_block, true),
+            (Fork::GrayGlacier, self.gray_glacier_block, true),
+            (Fork::Paris, self.merge_netsplit_block, true),
+        ];
+
+        let time_forks = [
+            (Fork::Shanghai, self.shanghai_time, false),
+            (Fork::Cancun, self.cancun_time, false),
+        ];
+
+        let op_forks = [
+            (Fork::Regolith, self.regolith_time, false),
+            (Fork::Canyon, self.canyon_time, false),
+            (Fork::Delta, self.delta_time, false),
+            (Fork::Ecotone, self.ecotone_time, false),
+            (Fork::Fjord, self.fjord_time, false),
+        ];
+
+        check_order(&block_forks)?;
+        check_order(&time_forks)?;
+        check_order(&op_forks)?;
+
+        if self.shanghai_time.is_some() && self.london_block.is_none() {
+            return Err(GenesisError::MissingFork(Fork::Shanghai, Fork::London));
+        }
+
+        for (fork, time, require, require_time) in [
+            (
+                Fork::Canyon,
+                self.canyon_time,
+                Fork::Shanghai,
+                self.shanghai_time,
+            ),
+            (
+                Fork::Ecotone,
+                self.ecotone_time,
+                Fork::Cancun,
+                self.cancun_time,
+            ),
+        ] {
+            if let Some(time) = time {
+                match require_time {
+                    None => return Err(GenesisError::MissingFork(fork, require)),
+                    Some(r) if r > time => {
+                        return Err(GenesisError::ForkOrder(require, r, fork, time))
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some(optimism) = &self.optimism {
+            if self.canyon_time.is_some() && optimism.eip1559_denominator_canyon.is_none() {
+                return Err(GenesisError::MissingCanyonDenominator);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn is_london(&self, number: u64) -> bool {
+        is_block_active(self.london_block, number)
+    }
+
+    pub fn is_bedrock(&self, number: u64) -> bool {
+        is_block_active(self.bedrock_block, number)
+    }
+
+    pub fn is_shanghai(&self, number: u64, timestamp: u64) -> bool {
+        self.is_london(number) && is_time_active(self.shanghai_time, timestamp)
+    }
+
+    pub fn is_cancun(&self, number: u64, timestamp: u64) -> bool {
+        self.is_london(number) && is_time_active(self.cancun_time, timestamp)
+    }
+
+    pub fn is_regolith(&self, timestamp: u64) -> bool {
+        is_time_active(self.regolith_time, timestamp)
+    }
+
+    pub fn is_canyon(&self, timestamp: u64) -> bool {
+        is_time_active(self.canyon_time, timestamp)
+    }
+
+    pub fn is_delta(&self, timestamp: u64) -> bool {
+        is_time_active(self.delta_time, timestamp)
+    }
+
+    pub fn is_ecotone(&self, timestamp: u64) -> bool {
+        is_time_active(self.ecotone_time, timestamp)
+    }
+
+    pub fn is_fjord(&self, timestamp: u64) -> bool {
+        is_time_active(self.fjord_time, timestamp)
+    }
+
+    /// Is chain optimism
+    pub fn is_optimism(&self) -> bool {
+        self.optimism.is_some()
+    }
+
+    /// Latest fork active at block number and timestamp
+    pub fn fork_at(&self, number: u64, timestamp: u64) -> Fork {
+        let time_forks = [
+            (Fork::Fjord, self.fjord_time),
+            (Fork::Ecotone, self.ecotone_time),
+            (Fork::Delta, self.delta_time),
+            (Fork::Cancun, self.cancun_time),
+            (Fork::Canyon, self.canyon_time),
+            (Fork::Shanghai, self.shanghai_time),
+            (Fork::Regolith, self.regolith_time),
+        ];
+
+        if self.is_london(number) {
+            for (fork, time) in time_forks {
+                if is_time_active(time, timestamp) {
+                    return fork;
+                }
+            }
+        }
+
+        let block_forks = [
+            (Fork::Bedrock, self.bedrock_block),
+            (Fork::Paris, self.merge_netsplit_block),
+            (Fork::GrayGlacier, self.gray_glacier_block),
+            (Fork::ArrowGlacier, self.arrow_glacier_block),
+            (Fork::London, self.london_block),
+            (Fork::Berlin, self.berlin_block),
+            (Fork::MuirGlacier, self.muir_glacier_block),
+            (Fork::Istanbul, self.istanbul_block),
+            (Fork::Petersburg, self.petersburg_block),
+            (Fork::Constantinople, self.constantinople_block),
+            (Fork::Byzantium, self.byzantium_block),
+            (Fork::SpuriousDragon, self.eip158_block),
+            (Fork::Tangerine, self.eip150_block),
+            (Fork::Homestead, self.homestead_block),
+        ];
+
+        for (fork, block) in block_forks {
+            if is_block_active(block, number) {
+                return fork;
+            }
+        }
+
+        Fork::Frontier
+    }
+
+    /// Latest fork active at timestamp, assume all block based forks are active
+    pub fn fork_at_time(&self, timestamp: u64) -> Fork {
+        self.fork_at(u64::MAX, timestamp)
+    }
+
+    /// Version of engine api should be used for payload at block number and timestamp
+    ///
+    /// Derived from active forks rather than `Fork` order, op forks like Delta may be
+    /// active before Cancun.
+    pub fn engine_api_version(&self, number: u64, timestamp: u64) -> u8 {
+        if self.is_cancun(number, timestamp) {
+            3
+        } else if self.is_shanghai(number, timestamp) {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+/// Genesis
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Genesis {
+    pub config: ChainConfig,
+    pub nonce: U256,
+    pub timestamp: U256,
+    pub extra_data: Bytes,
+    pub gas_limit: U256,
+    pub difficulty: U256,
+    pub mix_hash: H256,
+    pub coinbase: H160,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_fee_per_gas: Option<U256>,
+    pub alloc: HashMap<H160, AllocItem>,
+}
+
+/// Premint token, code and storage
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AllocItem {
+    #[serde(default, skip_serializing_if = "is_empty_bytes")]
+    pub code: Bytes,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub storage: HashMap<H256, H256>,
+    #[serde(default)]
+    pub balance: U256,
+    #[serde(default, skip_serializing_if = "U256::is_zero")]
+    pub nonce: U256,
+}
+
+fn is_empty_bytes(b: &Bytes) -> bool {
+    b.is_empty()
+}
+
+impl AllocItem {
+    fn to_state_account(&self) -> StateAccount {
+        StateAccount {
+            nonce: self.nonce,
+            balance: self.balance,
+            code: self.code.clone(),
+            storage: self.storage.iter().map(|(k, v)| (*k, *v)).collect(),
+        }
+    }
+}
+
+impl Genesis {
+    /// Create builder of genesis
+    pub fn builder() -> GenesisBuilder {
+        GenesisBuilder::default()
+    }
+
+    /// Gas limit of genesis block, geth uses `DEFAULT_GAS_LIMIT` if it is 0
+    pub fn block_gas_limit(&self) -> U256 {
+        if self.gas_limit.is_zero() {
+            DEFAULT_GAS_LIMIT.into()
+        } else {
+            self.gas_limit
+        }
+    }
+
+    /// Check genesis is consistent
+    pub fn validate(&self) -> Result<(), GenesisError> {
+        let gas_limit = self.block_gas_limit();
+
+        if gas_limit < MIN_GAS_LIMIT.into() {
+            return Err(GenesisError::GasLimitTooLow(gas_limit, MIN_GAS_LIMIT));
+        }
+
+        if gas_limit > MAX_GAS_LIMIT.into() {
+            return Err(GenesisError::GasLimitTooHigh(gas_limit, MAX_GAS_LIMIT));
+        }
+
+        for (field, value) in [("nonce", self.nonce), ("timestamp", self.timestamp)] {
+            if value > u64::MAX.into() {
+                return Err(GenesisError::Overflow(field, value));
+            }
+        }
+
+        self.config.check_fork_order()
+    }
+
+    /// Check genesis is consistent with consensus genesis
+    pub fn validate_with<T>(&self, consensus: &ConsensusGenesis<T>) -> Result<(), GenesisError> {
+        self.validate()?;
+
+        if self.config.chain_id != consensus.chain_id {
+            return Err(GenesisError::ChainIdMismatch(
+                self.config.chain_id,
+                consensus.chain_id,
+            ));
+        }
+
+        if consensus.block_gas_limit > self.block_gas_limit() {
+            return Err(GenesisError::BlockGasLimitExceeded(
+                consensus.block_gas_limit,
+                self.block_gas_limit(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// State root computed from alloc
+    pub fn state_root(&self) -> H256 {
+        let accounts: BTreeMap<H160, StateAccount> = self
+            .alloc
+            .iter()
+            .map(|(k, v)| (*k, v.to_state_account()))
+            .collect();
+
+        state_utils::state_root(&accounts)
+    }
+
+    /// Build genesis header, same as geth's `Genesis.ToBlock`
+    ///
+    /// Nonce and timestamp are truncated to u64, they are checked by `validate`.
+    pub fn header(&self) -> EthereumHeader {
+        let header = Header {
+            parent_hash: H256::zero(),
+            ommers_hash: state_utils::EMPTY_OMMERS_HASH,
+            beneficiary: self.coinbase,
+            state_root: self.state_root(),
+            transactions_root: state_utils::EMPTY_ROOT,
+            receipts_root: state_utils::EMPTY_ROOT,
+            logs_bloom: Default::default(),
+            difficulty: self.difficulty,
+            number: U256::zero(),
+            gas_limit: self.block_gas_limit(),
+            gas_used: U256::zero(),
+            timestamp: self.timestamp.low_u64(),
+            extra_data: self.extra_data.to_vec(),
+            mix_hash: self.mix_hash,
+            nonce: H64::from_low_u64_be(self.nonce.low_u64()),
+        };
+
+        let mut header = EthereumHeader::from(header);
+
+        let timestamp = header.header.timestamp;
+
+        if self.config.is_london(0) {
+            header.base_fee_per_gas =
+                Some(self.base_fee_per_gas.unwrap_or(INITIAL_BASE_FEE.into()));
+        }
+
+        if self.config.is_shanghai(0, timestamp) {
+            header.withdrawals_root = Some(state_utils::EMPTY_ROOT);
+        }
+
+        if self.config.is_cancun(0, timestamp) {
+            header.blob_gas_used = Some(U256::zero());
+            header.excess_blob_gas = Some(U256::zero());
+            header.parent_beacon_block_root = Some(H256::zero());
+        }
+
+        header
+    }
+}
+
+impl ExecutionGenesis for Genesis {
+    fn block_header(&self) -> BlockHeader {
+        BlockHeader::Ethereum(self.header())
+    }
+
+    fn validate_with<T>(&self, consensus: &ConsensusGenesis<T>) -> Result<(), fluct_core::Error> {
+        Genesis::validate_with(self, consensus)
+            .map_err(|e| fluct_core::Error::InvalidGenesis(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers_core::types::{Bytes, H160, H256, U256};
+    use fluct_core::{state_utils, ConsensusGenesis, ExecutionGenesis};
+
+    use crate::{
+        genesis::{
+            dev_accounts, ChainConfig, Fork, OpStackPredeploys, Predeploy, PredeployArtifacts,
+            DEFAULT_DEV_BALANCE, DEFAULT_DEV_MNEMONIC, DEFAULT_GAS_LIMIT, INITIAL_BASE_FEE,
+            PREDEPLOY_PROXY_COUNT, PROXY_ADMIN_SLOT, PROXY_IMPLEMENTATION_SLOT,
+        },
+        Genesis, GenesisError,
+    };
+
+    #[test]
+    fn test() {
+        let s = include_str!("../../genesis.json");
+
+        let r: Genesis = serde_json::from_str(s).unwrap();
+        r.validate().unwrap();
+
+        assert_eq!(r.config.chain_id(), 43965);
+        assert_eq!(r.config.fork_at(0, 0), Fork::Regolith);
+
+        let header = r.header();
+        assert_eq!(header.header.state_root, state_utils::EMPTY_ROOT);
+        assert_eq!(header.header.gas_limit, 30_000_000u64.into());
+        assert_eq!(header.header.timestamp, 0x64dfb160);
+        assert_eq!(header.header.extra_data, b"BEDROCK".to_vec());
+        assert_eq!(header.base_fee_per_gas, Some(INITIAL_BASE_FEE.into()));
+        assert_eq!(header.withdrawals_root, None);
+        assert_eq!(header.parent_beacon_block_root, None);
+
+        let json = serde_json::to_string(&r).unwrap();
+        let decoded: Genesis = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.block_hash(), r.block_hash());
+    }
+
+    #[test]
+    fn test_known_genesis_hash() {
+        // Genesis of Morden testnet, accounts start at nonce 2^20
+        let s = r#"{
+            "config": { "chainId": 2 },
+            "nonce": "0x00006d6f7264656e",
+            "timestamp": "0x0",
+            "extraData": "0x",
+            "gasLimit": "0x2fefd8",
+            "difficulty": "0x20000",
+            "mixHash": "0x00000000000000000000000000000000000000647572616c65787365646c6578",
+            "coinbase": "0x0000000000000000000000000000000000000000",
+            "alloc": {
+                "0000000000000000000000000000000000000001": { "balance": "0x1", "nonce": "0x100000" },
+                "0000000000000000000000000000000000000002": { "balance": "0x1", "nonce": "0x100000" },
+                "0000000000000000000000000000000000000003": { "balance": "0x1", "nonce": "0x100000" },
+                "0000000000000000000000000000000000000004": { "balance": "0x1", "nonce": "0x100000" },
+                "102e61f5d8f9bc71d0ad4a084df4e65e05ce0e1c": {
+                    "balance": "0x100000000000000000000000000000000000000000000000000",
+                    "nonce": "0x100000"
+                }
+            }
+        }"#;
+
+        let genesis: Genesis = serde_json::from_str(s).unwrap();
+
+        assert_eq!(
+            genesis.state_root(),
+            "0xf3f4696bbf3b3b07775128eb7a3763279a394e382130f27c21e70233e04946a9"
+                .parse()
+                .unwrap()
+        );
+        assert_eq!(
+            genesis.block_hash(),
+            "0x0cd786a2425d16f152c658316c423e6ce1181e15c3295826d7c9904cba9ce303"
+                .parse()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fork_at() {
+        let s = r#"{
+            "chainId": 901,
+            "homesteadBlock": 0,
+            "eip150Block": 0,
+            "eip155Block": 0,
+            "eip158Block": 0,
+            "byzantiumBlock": 0,
+            "constantinopleBlock": 0,
+            "petersburgBlock": 0,
+            "istanbulBlock": 0,
+            "muirGlacierBlock": 0,
+            "berlinBlock": 0,
+            "londonBlock": 0,
+            "arrowGlacierBlock": 0,
+            "grayGlacierBlock": 0,
+            "mergeNetsplitBlock": 0,
+            "shanghaiTime": 10,
+            "cancunTime": 30,
+            "bedrockBlock": 0,
+            "regolithTime": 0,
+            "canyonTime": 10,
+            "deltaTime": 20,
+            "ecotoneTime": 30,
+            "terminalTotalDifficulty": 0,
+            "terminalTotalDifficultyPassed": true,
+            "optimism": {
+                "eip1559Elasticity": 6,
+                "eip1559Denominator": 50,
+                "eip1559DenominatorCanyon": 250
+            }
+        }"#;
+
+        let c: ChainConfig = serde_json::from_str(s).unwrap();
+
+        assert_eq!(c.fork_at_time(0), Fork::Regolith);
+        assert_eq!(c.fork_at_time(10), Fork::Canyon);
+        assert_eq!(c.fork_at_time(25), Fork::Delta);
+        assert_eq!(c.fork_at_time(30), Fork::Ecotone);
+        assert_eq!(c.fork_at_time(u64::MAX), Fork::Ecotone);
+        assert!(!c.is_fjord(u64::MAX));
+
+        assert_eq!(c.engine_api_version(0, 0), 1);
+        assert_eq!(c.engine_api_version(0, 10), 2);
+        assert_eq!(c.engine_api_version(0, 25), 2);
+        assert_eq!(c.engine_api_version(0, 30), 3);
+
+        assert_eq!(
+            c.optimism().as_ref().unwrap().eip1559_denominator_canyon(),
+            Some(250)
+        );
+    }
+
+    #[test]
+    fn test_builder_validate() {
+        let address = H160::from_low_u64_be(1);
+
+        let genesis = Genesis::builder()
+            .chain_id(901)
+            .fund(address, 100u64)
+            .fund(address, 1u64)
+            .deploy(address, vec![0x60, 0x00], Default::default())
+            .build()
+            .unwrap();
+
+        let item = &genesis.alloc[&address];
+        assert_eq!(item.balance, 101.into());
+        assert_eq!(item.code.len(), 2);
+
+        let r = Genesis::builder()
+            .with_config(|c| {
+                c.set_canyon_time(Some(0));
+            })
+            .build();
+        assert!(matches!(
+            r,
+            Err(GenesisError::MissingFork(Fork::Canyon, Fork::Shanghai))
+        ));
+
+        let r = Genesis::builder()
+            .with_config(|c| {
+                c.set_shanghai_time(Some(20)).set_cancun_time(Some(10));
+            })
+            .build();
+        assert!(matches!(
+            r,
+            Err(GenesisError::ForkOrder(
+                Fork::Shanghai,
+                20,
+                Fork::Cancun,
+                10
+            ))
+        ));
+
+        let r = Genesis::builder().gas_limit(100u64).build();
+        assert!(matches!(r, Err(GenesisError::GasLimitTooLow(_, _))));
+
+        let mut zero = Genesis::builder().gas_limit(0u64).build().unwrap();
+        assert_eq!(zero.block_gas_limit(), DEFAULT_GAS_LIMIT.into());
+        assert_eq!(zero.header().header.gas_limit, DEFAULT_GAS_LIMIT.into());
+
+        zero.timestamp = U256::MAX;
+        assert!(matches!(
+            zero.validate(),
+            Err(GenesisError::Overflow("timestamp", _))
+        ));
+
+        let consensus = ConsensusGenesis::<Bytes> {
+            chain_id: 902,
+            earliest_block_height: 0,
+            earliest_block_hash: H256::zero(),
+            extra_data: Bytes::default(),
+            block_gas_limit: 30_000_000u64.into(),
+            block_block_size: 0,
+            transactions: Vec::new(),
+            timestamp: 0,
+            validators: Vec::new(),
+        };
+        assert!(matches!(
+            genesis.validate_with(&consensus),
+            Err(GenesisError::ChainIdMismatch(901, 902))
+        ));
+    }
+
+    #[test]
+    fn test_predeploys() {
+        let admin = H160::from_low_u64_be(0xad);
+
+        let mut artifacts = PredeployArtifacts::default();
+        artifacts.insert(PredeployArtifacts::PROXY, vec![0x01]);
+        for p in Predeploy::ALL.iter().filter(|p| p.is_required()) {
+            artifacts.insert(p.name(), vec![0x02]);
+        }
+        artifacts.insert(Predeploy::Weth9.name(), vec![0x03]);
+        assert!(artifacts.is_complete());
+        assert!(!PredeployArtifacts::default().is_complete());
+        assert_eq!(
+            PredeployArtifacts::default().missing(),
+            [
+                "Proxy",
+                "GasPriceOracle",
+                "L1Block",
+                "L2ToL1MessagePasser",
+                "ProxyAdmin"
+            ]
+        );
+
+        let r = Genesis::builder()
+            .predeploys(OpStackPredeploys::new(admin, PredeployArtifacts::default()))
+            .build();
+        assert!(matches!(r, Err(GenesisError::MissingPredeployBytecode(_))));
+
+        let genesis = Genesis::builder()
+            .predeploys(OpStackPredeploys::new(admin, artifacts))
+            .build()
+            .unwrap();
+
+        let l1_block = Predeploy::L1Block;
+        assert_eq!(
+            l1_block.address(),
+            "0x4200000000000000000000000000000000000015"
+                .parse()
+                .unwrap()
+        );
+        assert_eq!(
+            l1_block.implementation(),
+            "0xc0d3c0d3c0d3c0d3c0d3c0d3c0d3c0d3c0d30015"
+                .parse()
+                .unwrap()
+        );
+
+        let proxy = &genesis.alloc[&l1_block.address()];
+        assert_eq!(proxy.code.as_ref(), &[0x01]);
+        assert_eq!(
+            proxy.storage[&PROXY_IMPLEMENTATION_SLOT],
+            H256::from(l1_block.implementation())
+        );
+        assert_eq!(
+            proxy.storage[&PROXY_ADMIN_SLOT],
+            H256::from(Predeploy::ProxyAdmin.address())
+        );
+        assert_eq!(
+            genesis.alloc[&l1_block.implementation()].code.as_ref(),
+            &[0x02]
+        );
+
+        let proxy_admin = &genesis.alloc[&Predeploy::ProxyAdmin.address()];
+        assert_eq!(proxy_admin.storage[&H256::zero()], H256::from(admin));
+
+        assert!(!genesis
+            .alloc
+            .contains_key(&Predeploy::L1FeeVault.implementation()));
+
+        let weth = &genesis.alloc[&Predeploy::Weth9.address()];
+        assert_eq!(weth.code.as_ref(), &[0x03]);
+        assert!(weth.storage.is_empty());
+        assert!(!genesis
+            .alloc
+            .contains_key(&Predeploy::GovernanceToken.address()));
+
+        // Proxies without WETH9 and GovernanceToken, WETH9 and 4 implementations
+        assert_eq!(
+            genesis.alloc.len(),
+            PREDEPLOY_PROXY_COUNT as usize - 2 + 1 + 4
+        );
+    }
+
+    #[test]
+    fn test_dev_accounts() {
+        let accounts = dev_accounts(DEFAULT_DEV_MNEMONIC, 2).unwrap();
+
+        assert_eq!(
+            accounts[0].address,
+            "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+                .parse()
+                .unwrap()
+        );
+        assert_eq!(
+            accounts[0].private_key,
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                .parse()
+                .unwrap()
+        );
+
+        let genesis = Genesis::builder()
+            .dev_accounts(&accounts, DEFAULT_DEV_BALANCE)
+            .build()
+            .unwrap();
+
+        assert_eq!(genesis.alloc.len(), 2);
+        assert_eq!(
+            genesis.alloc[&accounts[1].address].balance,
+            DEFAULT_DEV_BALANCE.into()
+        );
+    }
+}
@@ -0,0 +1,339 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use ethers_core::types::{Bytes, H160, H256};
+use serde::Deserialize;
+
+use crate::{GenesisError, Result};
+
+use super::{AllocItem, Genesis};
+
+/// Count of proxies under predeploy namespace `0x4200..0000`
+pub const PREDEPLOY_PROXY_COUNT: u16 = 2048;
+
+/// EIP-1967 admin slot of proxy
+pub const PROXY_ADMIN_SLOT: H256 = H256([
+    0xb5, 0x31, 0x27, 0x68, 0x4a, 0x56, 0x8b, 0x31, 0x73, 0xae, 0x13, 0xb9, 0xf8, 0xa6, 0x01, 0x6e,
+    0x24, 0x3e, 0x63, 0xb6, 0xe8, 0xee, 0x11, 0x78, 0xd6, 0xa7, 0x17, 0x85, 0x0b, 0x5d, 0x61, 0x03,
+]);
+
+/// EIP-1967 implementation slot of proxy
+pub const PROXY_IMPLEMENTATION_SLOT: H256 = H256([
+    0x36, 0x08, 0x94, 0xa1, 0x3b, 0xa1, 0xa3, 0x21, 0x06, 0x67, 0xc8, 0x28, 0x49, 0x2d, 0xb9, 0x8d,
+    0xca, 0x3e, 0x20, 0x76, 0xcc, 0x37, 0x35, 0xa9, 0x20, 0xa3, 0xca, 0x50, 0x5d, 0x38, 0x2b, 0xbc,
+]);
+
+/// Predeploy contracts of OP Stack
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Predeploy {
+    LegacyMessagePasser,
+    DeployerWhitelist,
+    Weth9,
+    L2CrossDomainMessenger,
+    GasPriceOracle,
+    L2StandardBridge,
+    SequencerFeeVault,
+    OptimismMintableERC20Factory,
+    L1BlockNumber,
+    L2ERC721Bridge,
+    L1Block,
+    L2ToL1MessagePasser,
+    OptimismMintableERC721Factory,
+    ProxyAdmin,
+    BaseFeeVault,
+    L1FeeVault,
+    GovernanceToken,
+}
+
+impl Predeploy {
+    pub const ALL: [Predeploy; 17] = [
+        Self::LegacyMessagePasser,
+        Self::DeployerWhitelist,
+        Self::Weth9,
+        Self::L2CrossDomainMessenger,
+        Self::GasPriceOracle,
+        Self::L2StandardBridge,
+        Self::SequencerFeeVault,
+        Self::OptimismMintableERC20Factory,
+        Self::L1BlockNumber,
+        Self::L2ERC721Bridge,
+        Self::L1Block,
+        Self::L2ToL1MessagePasser,
+        Self::OptimismMintableERC721Factory,
+        Self::ProxyAdmin,
+        Self::BaseFeeVault,
+        Self::L1FeeVault,
+        Self::GovernanceToken,
+    ];
+
+    /// Contract name in forge artifacts
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::LegacyMessagePasser => "LegacyMessagePasser",
+            Self::DeployerWhitelist => "DeployerWhitelist",
+            Self::Weth9 => "WETH9",
+            Self::L2CrossDomainMessenger => "L2CrossDomainMessenger",
+            Self::GasPriceOracle => "GasPriceOracle",
+            Self::L2StandardBridge => "L2StandardBridge",
+            Self::SequencerFeeVault => "SequencerFeeVault",
+            Self::OptimismMintableERC20Factory => "OptimismMintableERC20Factory",
+            Self::L1BlockNumber => "L1BlockNumber",
+            Self::L2ERC721Bridge => "L2ERC721Bridge",
+            Self::L1Block => "L1Block",
+            Self::L2ToL1MessagePasser => "L2ToL1MessagePasser",
+            Self::OptimismMintableERC721Factory => "OptimismMintableERC721Factory",
+            Self::ProxyAdmin => "ProxyAdmin",
+            Self::BaseFeeVault => "BaseFeeVault",
+            Self::L1FeeVault => "L1FeeVault",
+            Self::GovernanceToken => "GovernanceToken",
+        }
+    }
+
+    /// Last two bytes of predeploy address
+    fn index(&self) -> u16 {
+        match self {
+            Self::LegacyMessagePasser => 0x00,
+            Self::DeployerWhitelist => 0x02,
+            Self::Weth9 => 0x06,
+            Self::L2CrossDomainMessenger => 0x07,
+            Self::GasPriceOracle => 0x0f,
+            Self::L2StandardBridge => 0x10,
+            Self::SequencerFeeVault => 0x11,
+            Self::OptimismMintableERC20Factory => 0x12,
+            Self::L1BlockNumber => 0x13,
+            Self::L2ERC721Bridge => 0x14,
+            Self::L1Block => 0x15,
+            Self::L2ToL1MessagePasser => 0x16,
+            Self::OptimismMintableERC721Factory => 0x17,
+            Self::ProxyAdmin => 0x18,
+            Self::BaseFeeVault => 0x19,
+            Self::L1FeeVault => 0x1a,
+            Self::GovernanceToken => 0x42,
+        }
+    }
+
+    /// WETH9 and GovernanceToken are deployed at their address without proxy
+    pub fn is_proxied(&self) -> bool {
+        !matches!(self, Self::Weth9 | Self::GovernanceToken)
+    }
+
+    /// Genesis can't work as OP Stack chain without these predeploys
+    pub fn is_required(&self) -> bool {
+        matches!(
+            self,
+            Self::ProxyAdmin | Self::L1Block | Self::GasPriceOracle | Self::L2ToL1MessagePasser
+        )
+    }
+
+    /// Address of predeploy, proxy if it is proxied
+    pub fn address(&self) -> H160 {
+        predeploy_address(self.index())
+    }
+
+    /// Address of implementation, in code namespace `0xc0d3..0000`
+    pub fn implementation(&self) -> H160 {
+        let mut address = H160([0xc0; 20]);
+
+        for (i, b) in address.0.iter_mut().enumerate() {
+            if i % 2 == 1 {
+                *b = 0xd3;
+            }
+        }
+
+        address.0[18..].copy_from_slice(&self.index().to_be_bytes());
+        address
+    }
+}
+
+/// Address in predeploy namespace
+pub fn predeploy_address(index: u16) -> H160 {
+    let mut address = H160::zero();
+    address.0[0] = 0x42;
+    address.0[18..].copy_from_slice(&index.to_be_bytes());
+    address
+}
+
+fn address_to_slot_value(address: H160) -> H256 {
+    H256::from(address)
+}
+
+mod embedded {
+    include!(concat!(env!("OUT_DIR"), "/predeploys.rs"));
+}
+
+/// Deployed bytecode of predeploys, `Proxy` is used for all proxies
+#[derive(Debug, Clone, Default)]
+pub struct PredeployArtifacts {
+    bytecodes: HashMap<String, Bytes>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ForgeArtifact {
+    deployed_bytecode: ForgeBytecode,
+}
+
+#[derive(Deserialize)]
+struct ForgeBytecode {
+    object: Bytes,
+}
+
+impl PredeployArtifacts {
+    /// Name of proxy contract
+    pub const PROXY: &'static str = "Proxy";
+
+    /// Set deployed bytecode of contract
+    pub fn insert(&mut self, name: impl Into<String>, bytecode: impl Into<Bytes>) -> &mut Self {
+        self.bytecodes.insert(name.into(), bytecode.into());
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Bytes> {
+        self.bytecodes.get(name)
+    }
+
+    /// Bytecode embedded at build time, see `FLUCT_PREDEPLOY_ARTIFACTS` in build script
+    pub fn embedded() -> Self {
+        let mut artifacts = Self::default();
+
+        for (name, bytecode) in embedded::EMBEDDED_PREDEPLOYS {
+            artifacts.insert(*name, bytecode.to_vec());
+        }
+
+        artifacts
+    }
+
+    /// Proxy and all required predeploys are present
+    pub fn is_complete(&self) -> bool {
+        self.missing().is_empty()
+    }
+
+    /// Names of proxy and required predeploys without bytecode
+    pub fn missing(&self) -> Vec<&'static str> {
+        let required = Predeploy::ALL
+            .iter()
+            .filter(|p| p.is_required())
+            .map(|p| p.name());
+
+        [Self::PROXY]
+            .into_iter()
+            .chain(required)
+            .filter(|name| !self.bytecodes.contains_key(*name))
+            .collect()
+    }
+
+    /// Load from forge artifacts directory, like `contracts-bedrock/forge-artifacts`
+    ///
+    /// Contract is read from `{dir}/{Name}.sol/{Name}.json`, missing contract is skipped.
+    pub fn from_forge_artifacts(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+
+        let mut artifacts = Self::default();
+
+        let names = Predeploy::ALL.iter().map(|p| p.name()).chain([Self::PROXY]);
+
+        for name in names {
+            let path = dir.join(format!("{name}.sol")).join(format!("{name}.json"));
+
+            if !path.exists() {
+                continue;
+            }
+
+            let artifact: ForgeArtifact = serde_json::from_slice(&fs::read(path)?)?;
+            artifacts.insert(name, artifact.deployed_bytecode.object);
+        }
+
+        Ok(artifacts)
+    }
+}
+
+/// Preset of OP Stack predeploys
+///
+/// All slots in predeploy namespace get a proxy owned by `ProxyAdmin`, except WETH9 and
+/// GovernanceToken, and `ProxyAdmin` is owned by admin. Predeploys not in artifacts are
+/// skipped unless required.
+#[derive(Debug, Clone)]
+pub struct OpStackPredeploys {
+    pub admin: H160,
+    pub artifacts: PredeployArtifacts,
+}
+
+impl OpStackPredeploys {
+    pub fn new(admin: H160, artifacts: PredeployArtifacts) -> Self {
+        Self { admin, artifacts }
+    }
+
+    /// Use bytecode embedded at build time
+    pub fn embedded(admin: H160) -> Self {
+        Self::new(admin, PredeployArtifacts::embedded())
+    }
+
+    fn bytecode(&self, name: &str) -> std::result::Result<Bytes, GenesisError> {
+        self.artifacts
+            .get(name)
+            .cloned()
+            .ok_or_else(|| GenesisError::MissingPredeployBytecode(name.to_string()))
+    }
+
+    /// Inject predeploys into alloc of genesis
+    pub fn apply(&self, genesis: &mut Genesis) -> std::result::Result<(), GenesisError> {
+        let proxy = self.bytecode(PredeployArtifacts::PROXY)?;
+
+        let proxy_admin = Predeploy::ProxyAdmin.address();
+
+        let unproxied: Vec<H160> = Predeploy::ALL
+            .iter()
+            .filter(|p| !p.is_proxied())
+            .map(|p| p.address())
+            .collect();
+
+        for index in 0..PREDEPLOY_PROXY_COUNT {
+            let address = predeploy_address(index);
+
+            if unproxied.contains(&address) {
+                continue;
+            }
+
+            let item = genesis.alloc.entry(address).or_default();
+
+            item.code = proxy.clone();
+            item.storage
+                .insert(PROXY_ADMIN_SLOT, address_to_slot_value(proxy_admin));
+        }
+
+        for predeploy in Predeploy::ALL {
+            let code = match self.bytecode(predeploy.name()) {
+                Ok(code) => code,
+                Err(e) if predeploy.is_required() => return Err(e),
+                Err(_) => continue,
+            };
+
+            if !predeploy.is_proxied() {
+                genesis.alloc.entry(predeploy.address()).or_default().code = code;
+                continue;
+            }
+
+            let implementation = predeploy.implementation();
+
+            genesis.alloc.insert(
+                implementation,
+                AllocItem {
+                    code,
+                    ..Default::default()
+                },
+            );
+
+            let item = genesis.alloc.entry(predeploy.address()).or_default();
+            item.storage.insert(
+                PROXY_IMPLEMENTATION_SLOT,
+                address_to_slot_value(implementation),
+            );
+        }
+
+        // Owner of ProxyAdmin at slot 0
+        let item = genesis.alloc.entry(proxy_admin).or_default();
+        item.storage
+            .insert(H256::zero(), address_to_slot_value(self.admin));
+
+        Ok(())
+    }
+}
@@ -1,26 +1,25 @@
 use std::{
     fs::{self, File},
     io::Read,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
-use fluct_core::{ExecutionService, Service};
+use ethers_core::types::H256;
+use fluct_core::{ExecutionGenesis, ExecutionService, Service};
 use rand::{rngs::OsRng, RngCore};
-use rust_embed::RustEmbed;
 use subprocess::{Popen, PopenConfig, Redirection};
-use tempfile::tempdir;
 
-use crate::{Config, Error, Genesis, GethEngineAPI, GethWeb3Api, Result};
-
-#[derive(RustEmbed)]
-#[folder = "$OUT_DIR/bin/"]
-#[include = "geth"]
-struct Assets;
+use crate::{
+    binary::{self, GethVersion},
+    Config, Error, Genesis, GethEngineAPI, GethWeb3Api, Result,
+};
 
 /// OpGeth instance
 pub struct Geth {
     work_dir: PathBuf,
 
+    bin_path: PathBuf,
+
     cleanup: bool,
 
     jwt: [u8; 32],
@@ -42,7 +41,7 @@ impl Drop for Geth {
 
 impl Geth {
     pub fn get_bin_dir(&self) -> PathBuf {
-        self.work_dir.join("geth")
+        self.bin_path.clone()
     }
 
     pub fn get_genesis_dir(&self) -> PathBuf {
@@ -55,34 +54,28 @@ impl Geth {
 
     /// Create OpGeth node
     pub fn new(config: Config) -> Result<Self> {
-        let work_dir = tempdir()?.into_path();
+        let work_dir = config
+            .work_dir
+            .clone()
+            .unwrap_or_else(|| config.datadir.join("fluct"));
 
-        let bin_path = work_dir.join("geth");
+        fs::create_dir_all(&work_dir)?;
 
-        let bin = Assets::get("geth").ok_or(Error::NoGethBinaryFound)?;
+        let bin_path = binary::install(&config.binary, &work_dir)?;
 
-        fs::write(&bin_path, bin.data)?;
+        if let Some(min) = &config.min_version {
+            let version = binary::version(&bin_path)?;
 
-        #[cfg(unix)]
-        {
-            use std::{
-                fs::{metadata, set_permissions},
-                os::unix::fs::PermissionsExt,
-            };
-
-            let mut permission = metadata(&bin_path)?.permissions();
-            permission.set_mode(0o755);
-            set_permissions(&bin_path, permission)?;
+            if !version.at_least(min) {
+                return Err(Error::UnsupportedVersion(version, min.clone()));
+            }
         }
 
-        let mut jwt = [0u8; 32];
-        OsRng.fill_bytes(&mut jwt);
-
-        let jwt_path = work_dir.join("jwt_key");
-        fs::write(jwt_path, hex::encode(jwt))?;
+        let jwt = Self::load_jwt(&work_dir.join("jwt_key"), config.jwt_secret)?;
 
         Ok(Self {
             work_dir,
+            bin_path,
             cleanup: true,
             config,
             jwt,
@@ -90,18 +83,148 @@ impl Geth {
         })
     }
 
+    /// Use given secret, or reuse existing key file, or generate a new one.
+    fn load_jwt(jwt_path: &Path, secret: Option<[u8; 32]>) -> Result<[u8; 32]> {
+        let jwt = if let Some(jwt) = secret {
+            jwt
+        } else if jwt_path.exists() {
+            let s = fs::read_to_string(jwt_path)?;
+            let s = s.trim();
+            let s = s.strip_prefix("0x").unwrap_or(s);
+
+            let bytes = hex::decode(s).map_err(|_| Error::InvalidJwtSecret)?;
+            bytes.try_into().map_err(|_| Error::InvalidJwtSecret)?
+        } else {
+            let mut jwt = [0u8; 32];
+            OsRng.fill_bytes(&mut jwt);
+            jwt
+        };
+
+        fs::write(jwt_path, hex::encode(jwt))?;
+
+        Ok(jwt)
+    }
+
+    /// JWT secret of engine api
+    pub fn jwt_secret(&self) -> [u8; 32] {
+        self.jwt
+    }
+
+    /// Get version of geth binary in use
+    pub fn version(&self) -> Result<GethVersion> {
+        binary::version(&self.bin_path)
+    }
+
     pub fn no_cleanup(&mut self) -> &mut Self {
         self.cleanup = false;
         self
     }
 
+    /// Remove generated files in work dir, jwt key is kept for restarting.
     pub fn cleanup(&mut self) -> Result<()> {
-        fs::remove_dir_all(&self.work_dir)?;
+        let mut paths = vec![self.get_genesis_dir()];
+
+        if self.bin_path.starts_with(&self.work_dir) {
+            paths.push(self.bin_path.clone());
+        }
+
+        for path in paths {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
 
         Ok(())
     }
 
+    /// Is datadir already holding a chain
+    pub fn is_initialized(&self) -> bool {
+        self.config.datadir.join("geth").join("chaindata").exists()
+    }
+
+    /// Parse `incompatible genesis (have {stored}, new {expect})` from geth output
+    fn parse_genesis_mismatch(output: &str) -> Option<Error> {
+        let s = output.split("incompatible genesis (have ").nth(1)?;
+        let (stored, s) = s.split_once(", new ")?;
+        let (expect, _) = s.split_once(')')?;
+
+        let stored = stored.trim().parse().ok()?;
+        let expect = expect.trim().parse().ok()?;
+
+        Some(Error::GenesisMismatch(stored, expect))
+    }
+
+    pub fn get_genesis_hash_dir(&self) -> PathBuf {
+        self.work_dir.join("genesis_hash")
+    }
+
+    /// Genesis hash recorded by last successful init
+    fn stored_genesis_hash(&self) -> Result<Option<H256>> {
+        let path = self.get_genesis_hash_dir();
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let s = fs::read_to_string(path)?;
+        let hash = s
+            .trim()
+            .parse()
+            .map_err(|_| Error::InvalidGenesisHashFile(s.trim().to_string()))?;
+
+        Ok(Some(hash))
+    }
+
+    /// Parse hashes in `Successfully wrote genesis state ... hash=d4e567..cb8fa3` of geth output
+    fn parse_genesis_hash(output: &str) -> Vec<&str> {
+        output
+            .lines()
+            .filter(|l| l.contains("Successfully wrote genesis state"))
+            .filter_map(|l| l.split("hash=").nth(1))
+            .filter_map(|s| s.split_whitespace().next())
+            .collect()
+    }
+
+    /// Compare hash with full or abbreviated one printed by geth
+    fn is_same_hash(hash: &H256, s: &str) -> bool {
+        let s = s.trim_matches('"');
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        let full = hex::encode(hash);
+
+        match s.split_once("..") {
+            Some((prefix, suffix)) => {
+                !prefix.is_empty()
+                    && !suffix.is_empty()
+                    && full.starts_with(prefix)
+                    && full.ends_with(suffix)
+            }
+            None => full == s,
+        }
+    }
+
+    /// Write genesis into datadir
+    ///
+    /// If datadir is initialized, the stored genesis hash is checked. geth also checks
+    /// the stored genesis, and the hash written by geth is cross-checked with the one
+    /// computed natively.
     fn _init(&self, genesis: &Genesis) -> Result<()> {
+        genesis.validate()?;
+
+        let hash = genesis.block_hash();
+
+        if self.is_initialized() {
+            log::info!(
+                "Datadir {} already initialized, check genesis",
+                self.config.datadir.display()
+            );
+
+            match self.stored_genesis_hash()? {
+                Some(stored) if stored == hash => return Ok(()),
+                Some(stored) => return Err(Error::GenesisMismatch(stored, hash)),
+                None => {}
+            }
+        }
+
         let config = PopenConfig {
             stderr: Redirection::Pipe,
             ..Default::default()
@@ -122,16 +245,43 @@ impl Geth {
             config,
         )?;
 
-        if r.wait()?.success() {
-            Ok(())
-        } else {
-            let mut buf = String::new();
+        let status = r.wait()?;
 
-            if let Some(f) = &mut r.stderr {
-                f.read_to_string(&mut buf)?;
+        let mut buf = String::new();
+
+        if let Some(f) = &mut r.stderr {
+            f.read_to_string(&mut buf)?;
+        }
+
+        if status.success() {
+            let written = Self::parse_genesis_hash(&buf);
+
+            if written.is_empty() {
+                log::warn!("No genesis hash found in geth output, skip check");
             }
 
-            Err(Error::SubprocessExecuteError(buf))
+            if let Some(s) = written.iter().find(|s| !Self::is_same_hash(&hash, s)) {
+                return Err(Error::GenesisHashMismatch(hash, s.to_string()));
+            }
+
+            fs::write(self.get_genesis_hash_dir(), format!("{:?}", hash))?;
+
+            Ok(())
+        } else {
+            match Self::parse_genesis_mismatch(&buf) {
+                Some(Error::GenesisMismatch(stored, expect)) => {
+                    if expect != hash {
+                        log::warn!(
+                            "Genesis hash computed by geth: {:?} is not same as fluct: {:?}",
+                            expect,
+                            hash
+                        );
+                    }
+
+                    Err(Error::GenesisMismatch(stored, expect))
+                }
+                _ => Err(Error::SubprocessExecuteError(buf)),
+            }
         }
     }
 
@@ -164,7 +314,7 @@ impl Geth {
             args.push("--http".to_string());
             args.push("--http.corsdomain=\"*\"".to_string());
             args.push("--http.vhosts=\"*\"".to_string());
-            args.push("--http.api=web3,debug,eth,txpool,net,engine".to_string());
+            args.push("--http.api=web3,debug,eth,net,engine".to_string());
             args.push(format!("--http.addr={}", http.listen.ip()));
             args.push(format!("--http.port={}", http.listen.port()));
         }
@@ -172,7 +322,7 @@ impl Geth {
         if let Some(ws) = &self.config.ws {
             args.push("--ws".to_string());
             args.push("--ws.origins=\"*\"".to_string());
-            args.push("--ws.api=debug,eth,txpool,net,engine".to_string());
+            args.push("--ws.api=debug,eth,net,engine".to_string());
             args.push(format!("--ws.addr={}", ws.listen.ip()));
             args.push(format!("--ws.port={}", ws.listen.port()));
         }
@@ -227,7 +377,20 @@ impl ExecutionService for Geth {
     }
 
     fn reset(&mut self) -> Result<()> {
-        fs::remove_dir_all(&self.config.datadir)?;
+        for entry in fs::read_dir(&self.config.datadir)? {
+            let path = entry?.path();
+
+            if path == self.work_dir {
+                continue;
+            }
+
+            if path.is_dir() {
+                fs::remove_dir_all(path)?;
+            } else {
+                fs::remove_file(path)?;
+            }
+        }
+
         Ok(())
     }
 }
@@ -236,9 +399,10 @@ impl ExecutionService for Geth {
 mod tests {
     use std::{fs, thread, time::Duration};
 
+    use ethers_core::types::H256;
     use fluct_core::{ExecutionService, Service};
 
-    use crate::{Config, Geth};
+    use crate::{Config, Error, Geth};
 
     #[test]
     fn test_start() {
@@ -261,4 +425,60 @@ mod tests {
 
         fs::remove_dir_all(datadir).unwrap();
     }
+
+    #[test]
+    fn test_parse_genesis_mismatch() {
+        let output =
+            "Fatal: Failed to write genesis block: database contains incompatible genesis \
+            (have 0000000000000000000000000000000000000000000000000000000000000001, \
+            new 0000000000000000000000000000000000000000000000000000000000000002)";
+
+        match Geth::parse_genesis_mismatch(output) {
+            Some(Error::GenesisMismatch(stored, expect)) => {
+                assert_eq!(stored, H256::from_low_u64_be(1));
+                assert_eq!(expect, H256::from_low_u64_be(2));
+            }
+            _ => panic!("Failed to parse genesis mismatch"),
+        }
+
+        assert!(Geth::parse_genesis_mismatch("Fatal: other error").is_none());
+    }
+
+    #[test]
+    fn test_parse_genesis_hash() {
+        let output = "INFO [10-19|06:30:45.123] Successfully wrote genesis state         \
+            database=chaindata hash=d4e567..cb8fa3\n\
+            INFO [10-19|06:30:45.124] Other log hash=000000..000000";
+
+        let hash: H256 = "0xd4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+            .parse()
+            .unwrap();
+
+        let written = Geth::parse_genesis_hash(output);
+        assert_eq!(written, vec!["d4e567..cb8fa3"]);
+
+        assert!(Geth::is_same_hash(&hash, written[0]));
+        assert!(Geth::is_same_hash(&hash, &format!("{:?}", hash)));
+        assert!(!Geth::is_same_hash(&hash, "d4e567..000000"));
+        assert!(!Geth::is_same_hash(&hash, ".."));
+    }
+
+    #[test]
+    fn test_jwt_reuse() {
+        let datadir = tempfile::tempdir().unwrap();
+
+        let jwt = Geth::new(Config::new(99999, datadir.path()))
+            .unwrap()
+            .jwt_secret();
+
+        let geth = Geth::new(Config::new(99999, datadir.path())).unwrap();
+        assert_eq!(geth.jwt_secret(), jwt);
+        drop(geth);
+
+        let mut config = Config::new(99999, datadir.path());
+        config.jwt_secret = Some([1u8; 32]);
+
+        let geth = Geth::new(config).unwrap();
+        assert_eq!(geth.jwt_secret(), [1u8; 32]);
+    }
 }
@@ -1,6 +1,8 @@
 mod geth;
 pub use geth::*;
 
+pub mod binary;
+
 pub mod config;
 #[doc(inline)]
 pub use config::Config;
@@ -4,7 +4,7 @@ use ethers_core::types::{
 };
 use fluct_core::{Transaction, Web3Api, Web3Error, Web3Result};
 use fluct_jsonrpc::client::{RpcClient, RpcResponse};
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 
 use crate::Error;
 
@@ -21,7 +21,7 @@ impl GethWeb3Api {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize)]
 #[serde(tag = "method", content = "params")]
 enum Web3Call {
     #[serde(rename = "eth_chainId")]
@@ -40,6 +40,10 @@ enum Web3Call {
     Balance((H160,)),
     #[serde(rename = "eth_getBalance")]
     BalanceWithBlock((H160, BlockId)),
+    #[serde(rename = "eth_getTransactionCount")]
+    TransactionCount((H160,)),
+    #[serde(rename = "eth_getTransactionCount")]
+    TransactionCountWithBlock((H160, BlockId)),
     #[serde(rename = "eth_getCode")]
     Code((H160,)),
     #[serde(rename = "eth_getCode")]
@@ -127,6 +131,24 @@ impl Web3Api for GethWeb3Api {
         Ok(res)
     }
 
+    /// Get account nonce
+    async fn transaction_count(
+        &mut self,
+        address: H160,
+        block: Option<BlockId>,
+    ) -> Result<U256, Web3Error> {
+        let req = if let Some(b) = block {
+            Web3Call::TransactionCountWithBlock((address, b))
+        } else {
+            Web3Call::TransactionCount((address,))
+        };
+
+        let res: RpcResponse<U256> = self.client.call(req).await?;
+        let res = res.into_result()?.ok_or(Web3Error::EmptyResponse)?;
+
+        Ok(res)
+    }
+
     /// Get account code
     async fn code(&mut self, address: H160, block: Option<BlockId>) -> Result<Bytes, Web3Error> {
         let req = if let Some(b) = block {
@@ -1,4 +1,5 @@
 pub mod client;
+pub mod server;
 
 mod error;
 pub use error::*;
@@ -0,0 +1,13 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::RpcError;
+
+/// Handler of JSONRPC calls
+#[async_trait]
+pub trait RpcHandler: Send + Sync {
+    /// Handle a JSONRPC call
+    ///
+    /// Return `None` if call isn't served by this handler.
+    async fn handle(&self, method: &str, params: &Value) -> Option<Result<Value, RpcError>>;
+}
@@ -0,0 +1,7 @@
+//! Server of JSONRPC
+
+mod handler;
+pub use handler::*;
+
+mod proxy;
+pub use proxy::*;
@@ -0,0 +1,297 @@
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+
+use http::{HeaderValue, Method, Request, Response, StatusCode, Uri};
+use hyper::{
+    body,
+    service::{make_service_fn, service_fn},
+    Body, Client, Server,
+};
+use serde_json::{json, Value};
+
+use crate::{Result, RpcError};
+
+use super::RpcHandler;
+
+/// JSONRPC server in front of another JSONRPC endpoint
+///
+/// Calls are served by handler first, calls it doesn't serve are forwarded to upstream.
+/// A batch is split, calls forwarded are sent to upstream as one batch.
+pub struct RpcProxy<H> {
+    handler: Arc<H>,
+    upstream: Uri,
+}
+
+impl<H> Clone for RpcProxy<H> {
+    fn clone(&self) -> Self {
+        Self {
+            handler: self.handler.clone(),
+            upstream: self.upstream.clone(),
+        }
+    }
+}
+
+impl<H> RpcProxy<H>
+where
+    H: RpcHandler + 'static,
+{
+    pub fn new(handler: H, upstream: &str) -> Result<Self> {
+        Ok(Self {
+            handler: Arc::new(handler),
+            upstream: upstream.parse()?,
+        })
+    }
+
+    /// Serve HTTP on `listen` until server fails
+    pub async fn serve(self, listen: SocketAddr) -> Result<()> {
+        log::info!("Serve JSONRPC on {}, upstream is {}", listen, self.upstream);
+
+        let make = make_service_fn(move |_| {
+            let proxy = self.clone();
+
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let proxy = proxy.clone();
+
+                    async move { Ok::<_, Infallible>(proxy.http(req).await) }
+                }))
+            }
+        });
+
+        Server::try_bind(&listen)?.serve(make).await?;
+
+        Ok(())
+    }
+
+    async fn http(&self, req: Request<Body>) -> Response<Body> {
+        if req.method() != Method::POST {
+            return status(StatusCode::METHOD_NOT_ALLOWED);
+        }
+
+        let bytes = match body::to_bytes(req.into_body()).await {
+            Ok(v) => v,
+            Err(e) => {
+                log::debug!("Failed to read JSONRPC request: {}", e);
+                return status(StatusCode::BAD_REQUEST);
+            }
+        };
+
+        let resp = self.handle(&bytes).await;
+
+        let mut response = Response::new(Body::from(resp.to_string()));
+        response
+            .headers_mut()
+            .insert("content-type", HeaderValue::from_static("application/json"));
+
+        response
+    }
+
+    /// Handle body of a JSONRPC request, single call or batch
+    pub async fn handle(&self, bytes: &[u8]) -> Value {
+        let req: Value = match serde_json::from_slice(bytes) {
+            Ok(v) => v,
+            Err(_) => return response(Value::Null, Err(RpcError::parse_error())),
+        };
+
+        match req {
+            Value::Array(calls) if calls.is_empty() => {
+                response(Value::Null, Err(RpcError::invalid_request()))
+            }
+            Value::Array(calls) => {
+                let mut resps = Vec::with_capacity(calls.len());
+                let mut forwards = Vec::new();
+
+                for call in calls {
+                    match self.call(&call).await {
+                        Some(v) => resps.push(v),
+                        None => forwards.push(call),
+                    }
+                }
+
+                if !forwards.is_empty() {
+                    match self.forward(&Value::Array(forwards.clone())).await {
+                        Ok(Value::Array(v)) => resps.extend(v),
+                        Ok(v) => resps.push(v),
+                        Err(e) => {
+                            resps.extend(forwards.iter().map(|call| upstream_error(call, &e)))
+                        }
+                    }
+                }
+
+                Value::Array(resps)
+            }
+            call => match self.call(&call).await {
+                Some(v) => v,
+                None => match self.forward(&call).await {
+                    Ok(v) => v,
+                    Err(e) => upstream_error(&call, &e),
+                },
+            },
+        }
+    }
+
+    /// Serve a call by handler, `None` if it should be forwarded
+    async fn call(&self, call: &Value) -> Option<Value> {
+        let id = call.get("id").cloned().unwrap_or(Value::Null);
+
+        let Some(method) = call.get("method").and_then(Value::as_str) else {
+            return Some(response(id, Err(RpcError::invalid_request())));
+        };
+
+        let params = call.get("params").cloned().unwrap_or(json!([]));
+
+        let result = self.handler.handle(method, &params).await?;
+
+        Some(response(id, result))
+    }
+
+    async fn forward(&self, req: &Value) -> Result<Value> {
+        let req = Request::post(&self.upstream)
+            .header("content-type", "application/json")
+            .body(Body::from(req.to_string()))?;
+
+        let resp = Client::new().request(req).await?;
+        let bytes = body::to_bytes(resp.into_body()).await?;
+
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+}
+
+fn status(code: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = code;
+    response
+}
+
+fn response(id: Value, result: std::result::Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+    }
+}
+
+fn upstream_error(call: &Value, e: &crate::Error) -> Value {
+    log::debug!("Failed to forward JSONRPC call: {}", e);
+
+    let id = call.get("id").cloned().unwrap_or(Value::Null);
+
+    response(id, Err(RpcError::internal_error_with(e.to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, net::SocketAddr};
+
+    use async_trait::async_trait;
+    use hyper::{
+        body,
+        service::{make_service_fn, service_fn},
+        Body, Response, Server,
+    };
+    use serde_json::{json, Value};
+
+    use crate::{server::RpcHandler, RpcError};
+
+    use super::RpcProxy;
+
+    struct MockHandler;
+
+    #[async_trait]
+    impl RpcHandler for MockHandler {
+        async fn handle(&self, method: &str, _params: &Value) -> Option<Result<Value, RpcError>> {
+            match method {
+                "txpool_status" => Some(Ok(json!({ "pending": "0x1", "queued": "0x0" }))),
+                "txpool_content" => Some(Err(RpcError::internal_error())),
+                _ => None,
+            }
+        }
+    }
+
+    /// Upstream answers every call with its method
+    fn upstream() -> SocketAddr {
+        let answer =
+            |call: &Value| json!({ "jsonrpc": "2.0", "id": call["id"], "result": call["method"] });
+
+        let make = make_service_fn(move |_| async move {
+            Ok::<_, Infallible>(service_fn(move |req: hyper::Request<Body>| async move {
+                let bytes = body::to_bytes(req.into_body()).await.unwrap();
+                let req: Value = serde_json::from_slice(&bytes).unwrap();
+
+                let resp = match &req {
+                    Value::Array(calls) => Value::Array(calls.iter().map(answer).collect()),
+                    call => answer(call),
+                };
+
+                Ok::<_, Infallible>(Response::new(Body::from(resp.to_string())))
+            }))
+        });
+
+        let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make);
+        let addr = server.local_addr();
+
+        tokio::spawn(server);
+
+        addr
+    }
+
+    #[test]
+    fn test_proxy() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        rt.block_on(async {
+            let addr = upstream();
+            let proxy = RpcProxy::new(MockHandler, &format!("http://{}", addr)).unwrap();
+
+            let resp = proxy
+                .handle(br#"{"jsonrpc":"2.0","id":1,"method":"txpool_status"}"#)
+                .await;
+            assert_eq!(resp["id"], 1);
+            assert_eq!(resp["result"]["pending"], "0x1");
+
+            let resp = proxy
+                .handle(br#"{"jsonrpc":"2.0","id":2,"method":"eth_chainId"}"#)
+                .await;
+            assert_eq!(resp["id"], 2);
+            assert_eq!(resp["result"], "eth_chainId");
+
+            let resp = proxy
+                .handle(
+                    br#"[
+                        {"jsonrpc":"2.0","id":3,"method":"eth_blockNumber"},
+                        {"jsonrpc":"2.0","id":4,"method":"txpool_content"},
+                        {"jsonrpc":"2.0","id":5,"method":"eth_chainId"}
+                    ]"#,
+                )
+                .await;
+            let resp = resp.as_array().unwrap();
+            assert_eq!(resp.len(), 3);
+            assert_eq!(resp[0]["id"], 4);
+            assert_eq!(resp[0]["error"]["code"], -32603);
+            assert_eq!(resp[1]["result"], "eth_blockNumber");
+            assert_eq!(resp[2]["result"], "eth_chainId");
+
+            let resp = proxy.handle(b"{").await;
+            assert_eq!(resp["error"]["code"], -32700);
+        });
+    }
+
+    #[test]
+    fn test_upstream_down() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        rt.block_on(async {
+            let proxy = RpcProxy::new(MockHandler, "http://127.0.0.1:1").unwrap();
+
+            let resp = proxy
+                .handle(br#"{"jsonrpc":"2.0","id":1,"method":"eth_chainId"}"#)
+                .await;
+            assert_eq!(resp["id"], 1);
+            assert_eq!(resp["error"]["code"], -32603);
+        });
+    }
+}
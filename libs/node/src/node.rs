@@ -1,10 +1,12 @@
-use std::{fs, path::Path};
+use std::{fs, path::Path, thread};
 
 use anyhow::Result;
 use ethers_core::types::Bytes;
 use fluct_core::{
-    Config, ConsensusService, ExecutionService, ForkChoiceState, Genesis, SequencerService,
+    Config, ConsensusService, ExecutionService, ForkChoiceState, Genesis, SequencerRpc,
+    SequencerService,
 };
+use fluct_jsonrpc::server::RpcProxy;
 
 /// Blockchain Node
 pub struct Node<C, E, S> {
@@ -18,6 +20,7 @@ impl<C, S, E> Node<C, E, S>
 where
     C: ConsensusService<S::Api>,
     S: SequencerService,
+    S::Api: Send + Sync + 'static,
     E: ExecutionService,
 {
     /// Create node
@@ -35,7 +38,9 @@ where
         // Genesis
         let gss = fs::read_to_string(&config.genesis)?;
         let genesis: Genesis<Bytes, E::Genesis> = serde_json::from_str(&gss)?;
-        let genesis = genesis.from_transaction()?;
+        let mut genesis = genesis.from_transaction()?;
+        genesis.derive_earliest_block_hash()?;
+        genesis.validate()?;
 
         // Chain State
         let csp = Path::new(&config.chain_state);
@@ -49,6 +54,7 @@ where
         };
 
         sequencer.set_api(wapi2);
+        sequencer.init(&genesis.consensus);
         consensus.set_api(eapi, wapi, sapi);
 
         consensus.init(genesis.consensus, state)?;
@@ -65,6 +71,8 @@ where
     pub fn start(&mut self) -> Result<()> {
         // Check is empty chain? Init it.
 
+        self.serve_rpc()?;
+
         self.sequencer.start()?;
         self.execution.start()?;
         self.consensus.start()?;
@@ -76,6 +84,28 @@ where
         Ok(())
     }
 
+    /// Serve JSONRPC in front of execution, mempool calls are served by sequencer
+    fn serve_rpc(&self) -> Result<()> {
+        let Some(rpc) = &self.config.rpc else {
+            return Ok(());
+        };
+
+        let proxy = RpcProxy::new(SequencerRpc::new(self.sequencer.api()), &rpc.upstream)?;
+        let listen = rpc.listen;
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+
+        thread::spawn(move || {
+            if let Err(e) = rt.block_on(proxy.serve(listen)) {
+                log::error!("JSONRPC server stopped: {}", e);
+            }
+        });
+
+        Ok(())
+    }
+
     /// Stop node
     pub fn stop(&self) -> Result<()> {
         Ok(())
@@ -1,3 +1,128 @@
-fn main() {
-    println!("Hello, world!");
+use std::{env, path::PathBuf, thread};
+
+use anyhow::{anyhow, Result};
+use fluct_core::{ExecutionService, Service};
+use fluct_op_geth::{
+    genesis::{
+        dev_accounts, DevAccount, OpStackPredeploys, PredeployArtifacts, DEFAULT_CHAIN_ID,
+        DEFAULT_DEV_BALANCE, DEFAULT_DEV_MNEMONIC,
+    },
+    Config, Genesis, Geth,
+};
+
+const USAGE: &str = "Usage: fluct-devnode [--dev-accounts <N>] [--mnemonic <PHRASE>] [--chain-id <ID>] [--datadir <DIR>] [--predeploy-artifacts <DIR>] [--no-predeploys]";
+
+struct Args {
+    dev_accounts: u32,
+    mnemonic: String,
+    chain_id: u64,
+    datadir: PathBuf,
+    /// Forge artifacts of predeploys, embedded bytecode is used if not set
+    predeploy_artifacts: Option<PathBuf>,
+    predeploys: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            dev_accounts: 10,
+            mnemonic: DEFAULT_DEV_MNEMONIC.to_string(),
+            chain_id: DEFAULT_CHAIN_ID,
+            datadir: PathBuf::from("devnode"),
+            predeploy_artifacts: None,
+            predeploys: true,
+        }
+    }
+}
+
+fn parse_args() -> Result<Args> {
+    let mut args = Args::default();
+
+    let mut iter = env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .ok_or_else(|| anyhow!("Missing value of {}", arg))
+        };
+
+        match arg.as_str() {
+            "--dev-accounts" => args.dev_accounts = value()?.parse()?,
+            "--mnemonic" => args.mnemonic = value()?,
+            "--chain-id" => args.chain_id = value()?.parse()?,
+            "--datadir" => args.datadir = value()?.into(),
+            "--predeploy-artifacts" => args.predeploy_artifacts = Some(value()?.into()),
+            "--no-predeploys" => args.predeploys = false,
+            _ => return Err(anyhow!("Unknown argument: {}\n{}", arg, USAGE)),
+        }
+    }
+
+    Ok(args)
+}
+
+fn print_accounts(accounts: &[DevAccount]) {
+    println!("Available Accounts");
+    println!("==================");
+    for (i, account) in accounts.iter().enumerate() {
+        println!(
+            "({}) {:?} ({} ETH)",
+            i,
+            account.address,
+            DEFAULT_DEV_BALANCE / 1_000_000_000_000_000_000
+        );
+    }
+
+    println!();
+    println!("Private Keys");
+    println!("==================");
+    for (i, account) in accounts.iter().enumerate() {
+        println!("({}) {:?}", i, account.private_key);
+    }
+    println!();
+}
+
+fn main() -> Result<()> {
+    let args = parse_args()?;
+
+    let accounts = dev_accounts(&args.mnemonic, args.dev_accounts)?;
+
+    let mut builder = Genesis::builder()
+        .chain_id(args.chain_id)
+        .dev_accounts(&accounts, DEFAULT_DEV_BALANCE);
+
+    if args.predeploys {
+        // ProxyAdmin is owned by first dev account
+        let admin = accounts
+            .first()
+            .ok_or_else(|| anyhow!("Predeploys need a dev account to own ProxyAdmin"))?;
+
+        let artifacts = match &args.predeploy_artifacts {
+            Some(dir) => PredeployArtifacts::from_forge_artifacts(dir)?,
+            None => PredeployArtifacts::embedded(),
+        };
+
+        let missing = artifacts.missing();
+
+        if !missing.is_empty() {
+            return Err(anyhow!(
+                "Bytecode of predeploys {:?} is missing, pass --predeploy-artifacts, \
+                 build with FLUCT_PREDEPLOY_ARTIFACTS or use --no-predeploys",
+                missing
+            ));
+        }
+
+        builder = builder.predeploys(OpStackPredeploys::new(admin.address, artifacts));
+    }
+
+    let genesis = builder.build()?;
+
+    print_accounts(&accounts);
+
+    let mut geth = Geth::new(Config::new(args.chain_id, &args.datadir))?;
+    geth.init(genesis)?;
+    geth.start()?;
+
+    loop {
+        thread::park();
+    }
 }
@@ -0,0 +1,96 @@
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use ethers_core::{k256::PublicKey, types::H256};
+use fluct_core::{
+    transaction_utils, PreConfirmation, SequencerApi, Transaction, TxAdmission, TxValidator,
+};
+use fluct_service::Caller;
+use tokio::sync::broadcast;
+
+use crate::{ApiRequest, ApiResponse, CommitmentRoot, Envelope, Error, Result};
+
+#[derive(Clone)]
+pub struct EncryptedSequencerApi {
+    pub(crate) caller: Caller<ApiRequest, ApiResponse>,
+    pub(crate) validator: Arc<OnceLock<TxValidator>>,
+    pub(crate) public_key: PublicKey,
+    pub(crate) commitments: broadcast::Sender<CommitmentRoot>,
+}
+
+impl EncryptedSequencerApi {
+    /// Public key to encrypt transactions to
+    pub fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    /// Subscribe roots of commitments, each is sent before its envelopes are revealed
+    pub fn subscribe_commitments(&self) -> broadcast::Receiver<CommitmentRoot> {
+        self.commitments.subscribe()
+    }
+
+    /// Put encrypted transaction into mempool
+    ///
+    /// Envelope can't be checked until revealed, invalid transaction is dropped then.
+    pub async fn submit_envelope(&self, envelope: Envelope) -> Result<TxAdmission> {
+        let resp = self
+            .caller
+            .call(ApiRequest::Envelope(Box::new(envelope)))
+            .await?;
+
+        match resp {
+            ApiResponse::Envelope(v) => Ok(v),
+            _ => Err(Error::UnexpectedResponse),
+        }
+    }
+
+    /// Check plain transaction and encrypt it
+    fn seal(&self, tx: &Transaction) -> Result<Envelope> {
+        let validator = self.validator.get().ok_or(Error::NotInited)?;
+        validator.validate(tx)?;
+
+        let raw = transaction_utils::transaction_to_bytes(std::slice::from_ref(tx));
+
+        Ok(Envelope::seal(&self.public_key, &raw[0]))
+    }
+}
+
+/// Plain transactions are encrypted by this node, content is hidden from other
+/// sequencers but not from this one.
+#[async_trait]
+impl SequencerApi for EncryptedSequencerApi {
+    type Error = Error;
+
+    async fn broadcast_tx(&self, tx: Transaction) -> Result<Option<PreConfirmation>> {
+        let envelope = self.seal(&tx)?;
+
+        self.caller.send(ApiRequest::Envelope(Box::new(envelope)))?;
+
+        Ok(None)
+    }
+
+    async fn submit_tx(&self, tx: Transaction) -> Result<TxAdmission> {
+        let envelope = match self.seal(&tx) {
+            Ok(v) => v,
+            Err(Error::Rejected(reason)) => return Ok(TxAdmission::Rejected(reason)),
+            Err(e) => return Err(e),
+        };
+
+        self.submit_envelope(envelope).await
+    }
+
+    fn comfirm_tx(&self, txhash: H256) -> Result<()> {
+        self.caller.send(ApiRequest::TxHash(txhash))?;
+
+        Ok(())
+    }
+
+    async fn txs(&self) -> Result<Vec<Transaction>> {
+        let resp = self.caller.call(ApiRequest::GetAllTransaction).await?;
+
+        match resp {
+            ApiResponse::GetAllTransaction(v) => Ok(v),
+            _ => Err(Error::UnexpectedResponse),
+        }
+    }
+}
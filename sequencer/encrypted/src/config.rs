@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+use fluct_core::MempoolLimits;
+
+/// Default interval to commit and reveal envelopes
+pub const DEFAULT_REVEAL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Config of encrypted sequencer
+#[derive(Debug, Clone)]
+pub struct EncryptedSequencerConfig {
+    /// Interval to commit order of waiting envelopes and reveal them
+    pub reveal_interval: Duration,
+
+    /// Count and bytes limit waiting envelopes, per sender limit and time to live
+    /// apply to revealed transactions
+    pub limits: MempoolLimits,
+}
+
+impl Default for EncryptedSequencerConfig {
+    fn default() -> Self {
+        Self {
+            reveal_interval: DEFAULT_REVEAL_INTERVAL,
+            limits: MempoolLimits::default(),
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use aes::cipher::{KeyIvInit, StreamCipher};
+use ethers_core::{
+    k256::{
+        elliptic_curve::{point::AffineCoordinates, sec1::ToEncodedPoint},
+        PublicKey, SecretKey,
+    },
+    rand::{thread_rng, RngCore},
+    types::{Bytes, H256},
+    utils::keccak256,
+};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::{Error, Result};
+
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Key to open one envelope, the ECDH shared secret of its ephemeral key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecryptionKey(pub H256);
+
+impl DecryptionKey {
+    /// ECDH of secret and public key, x coordinate of shared point
+    pub fn derive(secret: &SecretKey, public: &PublicKey) -> Self {
+        let shared = (public.to_projective() * *secret.to_nonzero_scalar()).to_affine();
+
+        Self(H256::from_slice(&shared.x()))
+    }
+
+    fn sub_key(&self, kind: u8) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.0.as_bytes());
+        hasher.update([kind]);
+        hasher.finalize().into()
+    }
+
+    fn enc_key(&self) -> [u8; 16] {
+        let mut key = [0u8; 16];
+        key.copy_from_slice(&self.sub_key(1)[..16]);
+        key
+    }
+
+    fn mac_key(&self) -> [u8; 32] {
+        self.sub_key(2)
+    }
+}
+
+/// Transaction encrypted to public key of key holder
+///
+/// ECIES with secp256k1, AES-128-CTR and HMAC-SHA256.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    /// Compressed ephemeral public key
+    pub ephemeral: Bytes,
+    pub iv: [u8; 16],
+    pub ciphertext: Bytes,
+    pub tag: H256,
+}
+
+impl Envelope {
+    /// Encrypt raw transaction
+    pub fn seal(public: &PublicKey, raw_tx: &[u8]) -> Self {
+        let mut rng = thread_rng();
+
+        let ephemeral = SecretKey::random(&mut rng);
+        let key = DecryptionKey::derive(&ephemeral, public);
+
+        let mut iv = [0u8; 16];
+        rng.fill_bytes(&mut iv);
+
+        let mut ciphertext = raw_tx.to_vec();
+        Aes128Ctr::new(&key.enc_key().into(), &iv.into()).apply_keystream(&mut ciphertext);
+
+        let tag = Self::mac(&key, &iv, &ciphertext);
+
+        let ephemeral = ephemeral.public_key().to_encoded_point(true);
+
+        Self {
+            ephemeral: ephemeral.as_bytes().to_vec().into(),
+            iv,
+            ciphertext: ciphertext.into(),
+            tag,
+        }
+    }
+
+    fn mac(key: &DecryptionKey, iv: &[u8], ciphertext: &[u8]) -> H256 {
+        let mut mac = HmacSha256::new_from_slice(&key.mac_key()).expect("any key size");
+        mac.update(iv);
+        mac.update(ciphertext);
+
+        H256::from_slice(&mac.finalize().into_bytes())
+    }
+
+    /// Bytes of envelope
+    pub fn size(&self) -> usize {
+        self.ephemeral.len() + 48 + self.ciphertext.len()
+    }
+
+    /// Hash of envelope, used as id before transaction is revealed
+    pub fn hash(&self) -> H256 {
+        let mut data = Vec::with_capacity(self.size());
+        data.extend_from_slice(&self.ephemeral);
+        data.extend_from_slice(&self.iv);
+        data.extend_from_slice(&self.ciphertext);
+        data.extend_from_slice(self.tag.as_bytes());
+
+        keccak256(data).into()
+    }
+
+    /// Ephemeral public key
+    pub fn ephemeral_key(&self) -> Result<PublicKey> {
+        PublicKey::from_sec1_bytes(&self.ephemeral).map_err(|_| Error::InvalidEnvelope)
+    }
+
+    /// Decrypt raw transaction
+    pub fn open(&self, key: &DecryptionKey) -> Result<Vec<u8>> {
+        if Self::mac(key, &self.iv, &self.ciphertext) != self.tag {
+            return Err(Error::InvalidEnvelope);
+        }
+
+        let mut raw_tx = self.ciphertext.to_vec();
+        Aes128Ctr::new(&key.enc_key().into(), &self.iv.into()).apply_keystream(&mut raw_tx);
+
+        Ok(raw_tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers_core::{k256::SecretKey, rand::thread_rng};
+
+    use super::{DecryptionKey, Envelope};
+
+    #[test]
+    fn test_seal_open() {
+        let secret = SecretKey::random(&mut thread_rng());
+
+        let envelope = Envelope::seal(&secret.public_key(), b"raw transaction");
+        assert_ne!(envelope.ciphertext.as_ref(), b"raw transaction");
+
+        let key = DecryptionKey::derive(&secret, &envelope.ephemeral_key().unwrap());
+        assert_eq!(envelope.open(&key).unwrap(), b"raw transaction");
+
+        let other = SecretKey::random(&mut thread_rng());
+        let key = DecryptionKey::derive(&other, &envelope.ephemeral_key().unwrap());
+        assert!(envelope.open(&key).is_err());
+    }
+}
@@ -0,0 +1,52 @@
+use fluct_core::{RejectReason, Web3Error};
+use fluct_service::{CallError, StepError};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    CallError(#[from] CallError),
+
+    #[error("No web3 api configed")]
+    NoWeb3ApiConfiged,
+
+    #[error(transparent)]
+    Web3Error(#[from] Web3Error),
+
+    #[error("Unexpected response from sequencer")]
+    UnexpectedResponse,
+
+    #[error("Sequencer not inited")]
+    NotInited,
+
+    #[error("Transaction rejected: {0}")]
+    Rejected(#[from] RejectReason),
+
+    #[error("Invalid envelope")]
+    InvalidEnvelope,
+
+    #[error("Unexpected commitment, expect height: {0}, got: {1}")]
+    UnexpectedCommitment(u64, u64),
+
+    #[error(transparent)]
+    FluctCoreError(#[from] fluct_core::Error),
+}
+
+impl StepError for Error {
+    fn is_exit(&self) -> bool {
+        match self {
+            Self::CallError(CallError::ChannelClosed) => true,
+            Self::CallError(CallError::SenderReject) => false,
+            Self::NoWeb3ApiConfiged => true,
+            Self::Web3Error(_) => true,
+            Self::UnexpectedResponse => false,
+            Self::NotInited => false,
+            Self::Rejected(_) => false,
+            Self::InvalidEnvelope => false,
+            Self::UnexpectedCommitment(_, _) => false,
+            Self::FluctCoreError(_) => false,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
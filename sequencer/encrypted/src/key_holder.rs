@@ -0,0 +1,100 @@
+use ethers_core::{
+    k256::{PublicKey, SecretKey},
+    types::H256,
+    utils::keccak256,
+};
+
+use crate::{DecryptionKey, Envelope, Error, Result};
+
+/// Envelopes in committed order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commitment {
+    pub height: u64,
+    pub envelopes: Vec<Envelope>,
+}
+
+impl Commitment {
+    /// Hash of envelope hashes in order
+    pub fn root(&self) -> H256 {
+        let data: Vec<u8> = self
+            .envelopes
+            .iter()
+            .flat_map(|e| e.hash().to_fixed_bytes())
+            .collect();
+
+        keccak256(data).into()
+    }
+}
+
+/// Root of commitment, published before its keys are revealed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitmentRoot {
+    pub height: u64,
+    pub root: H256,
+}
+
+impl From<&Commitment> for CommitmentRoot {
+    fn from(commitment: &Commitment) -> Self {
+        Self {
+            height: commitment.height,
+            root: commitment.root(),
+        }
+    }
+}
+
+/// Holder of decryption key, reveals keys of envelopes only after order is committed
+///
+/// A threshold decryption committee should implement this in production.
+pub trait KeyHolder: Send + Sync + 'static {
+    /// Public key users encrypt transactions to
+    fn public_key(&self) -> PublicKey;
+
+    /// Keys to open envelopes of commitment, in same order
+    ///
+    /// Key is `None` if envelope is broken.
+    fn reveal(&mut self, commitment: &Commitment) -> Result<Vec<Option<DecryptionKey>>>;
+}
+
+/// Key holder with whole secret in local, simulates threshold decryption
+pub struct LocalKeyHolder {
+    secret: SecretKey,
+    /// Next height to reveal, each commitment is revealed once and in order
+    height: u64,
+}
+
+impl LocalKeyHolder {
+    pub fn new(secret: SecretKey) -> Self {
+        Self { secret, height: 0 }
+    }
+}
+
+impl KeyHolder for LocalKeyHolder {
+    fn public_key(&self) -> PublicKey {
+        self.secret.public_key()
+    }
+
+    fn reveal(&mut self, commitment: &Commitment) -> Result<Vec<Option<DecryptionKey>>> {
+        if commitment.height != self.height {
+            return Err(Error::UnexpectedCommitment(self.height, commitment.height));
+        }
+
+        self.height += 1;
+
+        log::debug!(
+            "Reveal keys of commitment {} root {:?}",
+            commitment.height,
+            commitment.root()
+        );
+
+        let keys = commitment
+            .envelopes
+            .iter()
+            .map(|e| {
+                let public = e.ephemeral_key().ok()?;
+                Some(DecryptionKey::derive(&self.secret, &public))
+            })
+            .collect();
+
+        Ok(keys)
+    }
+}
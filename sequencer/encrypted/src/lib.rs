@@ -0,0 +1,30 @@
+//! Sequencer ordering encrypted transactions
+//!
+//! Users submit transactions encrypted to the key holder. Sequencer commits to an
+//! order of ciphertexts first, then key holder reveals decryption keys of the
+//! committed envelopes, so content of a transaction is unknown when its position
+//! is decided.
+
+mod envelope;
+pub use envelope::*;
+
+mod key_holder;
+pub use key_holder::*;
+
+mod pool;
+pub use pool::*;
+
+mod config;
+pub use config::*;
+
+mod sequencer;
+pub use sequencer::*;
+
+mod api;
+pub use api::*;
+
+mod error;
+pub use error::*;
+
+mod types;
+pub use types::*;
@@ -0,0 +1,442 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::Instant,
+};
+
+use ethers_core::types::{H160, H256, U256};
+use fluct_core::{
+    transaction_utils, MempoolLimits, RejectReason, Transaction, TxAdmission, TxValidator,
+};
+
+use crate::{Commitment, DecryptionKey, Envelope, Error};
+
+/// Pool of encrypted transactions
+///
+/// Envelopes wait in arrival order until committed, revealed transactions keep
+/// the committed order. A revealed transaction ahead of its sender's next nonce is
+/// deferred, and appended once the gap is filled.
+///
+/// Sender of envelope is unknown, so only count and bytes limit envelopes. Per sender
+/// limit and time to live apply to revealed and deferred transactions.
+pub struct EncryptedPool {
+    limits: MempoolLimits,
+    /// Next height to commit
+    height: u64,
+    envelopes: Vec<Envelope>,
+    /// Bytes of waiting envelopes
+    envelope_bytes: usize,
+    known: HashSet<H256>,
+    /// Revealed transactions and when they are revealed
+    revealed: Vec<(Transaction, Instant)>,
+    /// Next nonce of sender after its revealed transactions
+    nonces: HashMap<H160, U256>,
+    deferred: HashMap<H160, BTreeMap<U256, (Transaction, Instant)>>,
+}
+
+impl Default for EncryptedPool {
+    fn default() -> Self {
+        Self::new(MempoolLimits::default())
+    }
+}
+
+impl EncryptedPool {
+    pub fn new(limits: MempoolLimits) -> Self {
+        Self {
+            limits,
+            height: 0,
+            envelopes: Vec::new(),
+            envelope_bytes: 0,
+            known: HashSet::new(),
+            revealed: Vec::new(),
+            nonces: HashMap::new(),
+            deferred: HashMap::new(),
+        }
+    }
+
+    /// Add envelope, identified by its hash until revealed
+    pub fn add(&mut self, envelope: Envelope) -> TxAdmission {
+        let hash = envelope.hash();
+
+        if self.known.contains(&hash) {
+            return TxAdmission::AlreadyKnown;
+        }
+
+        let size = envelope.size();
+
+        if self.envelopes.len() >= self.limits.max_count
+            || self.envelope_bytes + size > self.limits.max_bytes
+        {
+            return TxAdmission::Rejected(RejectReason::PoolFull);
+        }
+
+        self.known.insert(hash);
+        self.envelope_bytes += size;
+        self.envelopes.push(envelope);
+
+        TxAdmission::Added
+    }
+
+    /// Fix order of waiting envelopes
+    pub fn commit(&mut self) -> Option<Commitment> {
+        if self.envelopes.is_empty() {
+            return None;
+        }
+
+        let envelopes = std::mem::take(&mut self.envelopes);
+        self.envelope_bytes = 0;
+
+        for envelope in &envelopes {
+            self.known.remove(&envelope.hash());
+        }
+
+        let commitment = Commitment {
+            height: self.height,
+            envelopes,
+        };
+
+        self.height += 1;
+
+        Some(commitment)
+    }
+
+    /// Decrypt and decode transactions of commitment in order
+    ///
+    /// Envelope failed to open, decode or validate is dropped, others keep their position.
+    pub fn open(
+        commitment: &Commitment,
+        keys: &[Option<DecryptionKey>],
+        validator: &TxValidator,
+    ) -> Vec<Transaction> {
+        let mut txs = Vec::new();
+
+        for (envelope, key) in commitment.envelopes.iter().zip(keys) {
+            let raw = key
+                .ok_or(Error::InvalidEnvelope)
+                .and_then(|key| envelope.open(&key));
+
+            let tx = raw.and_then(|raw| {
+                let mut tx = transaction_utils::bytes_to_transaction(&[raw.into()])?
+                    .pop()
+                    .ok_or(Error::InvalidEnvelope)?;
+
+                tx.from = validator.validate(&tx)?;
+
+                Ok(tx)
+            });
+
+            match tx {
+                Ok(tx) => txs.push(tx),
+                Err(e) => log::debug!("Drop envelope {:?}: {}", envelope.hash(), e),
+            }
+        }
+
+        txs
+    }
+
+    /// Append revealed transaction, `account_nonce` is nonce of sender in latest state
+    ///
+    /// Return false if transaction is dropped, its nonce is used by an earlier one, or
+    /// sender has `max_per_sender` transactions revealed or deferred.
+    pub fn push(&mut self, tx: Transaction, account_nonce: U256) -> bool {
+        let from = tx.from;
+
+        let next = match self.nonces.get(&from) {
+            Some(v) => account_nonce.max(*v),
+            None => account_nonce,
+        };
+
+        if tx.nonce < next {
+            return false;
+        }
+
+        let used = self
+            .revealed
+            .iter()
+            .filter(|(tx, _)| tx.from == from)
+            .count()
+            + self.deferred(&from);
+
+        if used >= self.limits.max_per_sender {
+            return false;
+        }
+
+        let now = Instant::now();
+
+        if tx.nonce > next {
+            let deferred = self.deferred.entry(from).or_default();
+
+            if deferred.contains_key(&tx.nonce) {
+                return false;
+            }
+
+            deferred.insert(tx.nonce, (tx, now));
+
+            return true;
+        }
+
+        self.revealed.push((tx, now));
+
+        let mut next = next + 1;
+
+        if let Some(deferred) = self.deferred.get_mut(&from) {
+            while let Some((tx, _)) = deferred.remove(&next) {
+                self.revealed.push((tx, now));
+                next += U256::one();
+            }
+
+            if deferred.is_empty() {
+                self.deferred.remove(&from);
+            }
+        }
+
+        self.nonces.insert(from, next);
+
+        true
+    }
+
+    /// Revealed transactions in committed order
+    pub fn revealed(&self) -> Vec<Transaction> {
+        self.revealed.iter().map(|(tx, _)| tx.clone()).collect()
+    }
+
+    /// Deferred transactions of sender, waiting for a nonce gap to be filled
+    pub fn deferred(&self, from: &H160) -> usize {
+        self.deferred.get(from).map_or(0, |v| v.len())
+    }
+
+    /// Transaction is executed, remove it
+    pub fn confirm(&mut self, txhash: &H256) {
+        let Some(pos) = self.revealed.iter().position(|(tx, _)| &tx.hash == txhash) else {
+            return;
+        };
+
+        let from = self.revealed.remove(pos).0.from;
+
+        let idle = self.revealed.iter().all(|(tx, _)| tx.from != from)
+            && !self.deferred.contains_key(&from);
+
+        if idle {
+            self.nonces.remove(&from);
+        }
+    }
+
+    /// Drop transactions live longer than time to live since revealed
+    ///
+    /// Later transactions of a sender can't execute once one is dropped, so all of
+    /// its revealed and deferred ones are dropped with it.
+    pub fn expire(&mut self, now: Instant) -> Vec<Transaction> {
+        let ttl = self.limits.ttl;
+        let expired = |added: &Instant| now.saturating_duration_since(*added) >= ttl;
+
+        let senders: HashSet<H160> = self
+            .revealed
+            .iter()
+            .filter(|(_, added)| expired(added))
+            .map(|(tx, _)| tx.from)
+            .collect();
+
+        let (dropped, kept) = std::mem::take(&mut self.revealed)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(tx, _)| senders.contains(&tx.from));
+
+        self.revealed = kept;
+
+        let mut dropped: Vec<_> = dropped.into_iter().map(|(tx, _)| tx).collect();
+
+        for from in &senders {
+            self.nonces.remove(from);
+
+            if let Some(deferred) = self.deferred.remove(from) {
+                dropped.extend(deferred.into_values().map(|(tx, _)| tx));
+            }
+        }
+
+        self.deferred.retain(|_, deferred| {
+            let (old, young) = std::mem::take(deferred)
+                .into_iter()
+                .partition::<BTreeMap<_, _>, _>(|(_, (_, added))| expired(added));
+
+            *deferred = young;
+            dropped.extend(old.into_values().map(|(tx, _)| tx));
+
+            !deferred.is_empty()
+        });
+
+        dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use ethers_core::{
+        k256::SecretKey,
+        rand::thread_rng,
+        types::{transaction::eip2718::TypedTransaction, TransactionRequest, H256, U256},
+        utils::rlp::Rlp,
+    };
+    use ethers_signers::{LocalWallet, Signer};
+    use fluct_core::{MempoolLimits, RejectReason, Transaction, TxAdmission, TxValidator};
+
+    use crate::{EncryptedPool, Envelope, Error, KeyHolder, LocalKeyHolder};
+
+    fn wallet(i: u64) -> LocalWallet {
+        LocalWallet::from_bytes(H256::from_low_u64_be(i).as_bytes()).unwrap()
+    }
+
+    fn raw_tx(wallet: &LocalWallet, nonce: u64) -> Vec<u8> {
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(wallet.address())
+            .nonce(nonce)
+            .gas(21000)
+            .gas_price(10)
+            .chain_id(901)
+            .into();
+
+        let signature = wallet
+            .clone()
+            .with_chain_id(901u64)
+            .sign_transaction_sync(&tx)
+            .unwrap();
+
+        tx.rlp_signed(&signature).to_vec()
+    }
+
+    #[test]
+    fn test_commit_reveal() {
+        let validator = TxValidator {
+            chain_id: 901,
+            block_gas_limit: 30_000_000u64.into(),
+        };
+
+        let mut key_holder = LocalKeyHolder::new(SecretKey::random(&mut thread_rng()));
+        let public = key_holder.public_key();
+
+        let mut pool = EncryptedPool::default();
+
+        let (a, b) = (wallet(1), wallet(2));
+
+        // committed order: a2, b0, a1, a0, b1
+        let raws = [
+            raw_tx(&a, 2),
+            raw_tx(&b, 0),
+            raw_tx(&a, 1),
+            raw_tx(&a, 0),
+            raw_tx(&b, 1),
+        ];
+
+        let envelopes: Vec<_> = raws
+            .iter()
+            .map(|raw| Envelope::seal(&public, raw))
+            .collect();
+
+        for envelope in &envelopes {
+            assert_eq!(pool.add(envelope.clone()), TxAdmission::Added);
+        }
+        assert_eq!(pool.add(envelopes[0].clone()), TxAdmission::AlreadyKnown);
+
+        // encrypted to another key, dropped at reveal
+        let other = SecretKey::random(&mut thread_rng()).public_key();
+        pool.add(Envelope::seal(&other, &raw_tx(&a, 3)));
+
+        let commitment = pool.commit().unwrap();
+        assert!(pool.commit().is_none());
+
+        let keys = key_holder.reveal(&commitment).unwrap();
+        assert!(matches!(
+            key_holder.reveal(&commitment),
+            Err(Error::UnexpectedCommitment(1, 0))
+        ));
+
+        let txs = EncryptedPool::open(&commitment, &keys, &validator);
+        assert_eq!(txs.len(), 5);
+        assert!(txs.iter().all(|tx| !tx.from.is_zero()));
+
+        for tx in txs {
+            assert!(pool.push(tx, U256::zero()));
+        }
+
+        // order across senders is kept, nonces of a sender come out in order
+        let order: Vec<_> = pool
+            .revealed()
+            .iter()
+            .map(|tx| (tx.from == a.address(), tx.nonce.as_u64()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![(false, 0), (true, 0), (true, 1), (true, 2), (false, 1)]
+        );
+
+        // nonce already used
+        let mut stale = pool.revealed()[1].clone();
+        stale.hash = H256::from_low_u64_be(1);
+        assert!(!pool.push(stale, U256::zero()));
+
+        // gap of sender b is held until filled
+        let mut ahead = pool.revealed()[4].clone();
+        ahead.nonce = 3.into();
+        assert!(pool.push(ahead, U256::zero()));
+        assert_eq!(pool.revealed().len(), 5);
+        assert_eq!(pool.deferred(&b.address()), 1);
+
+        let hash = pool.revealed()[0].hash;
+        pool.confirm(&hash);
+        assert_eq!(pool.revealed().len(), 4);
+    }
+
+    fn tx(wallet: &LocalWallet, nonce: u64) -> Transaction {
+        let mut tx: Transaction = Rlp::new(&raw_tx(wallet, nonce)).as_val().unwrap();
+        tx.from = wallet.address();
+        tx
+    }
+
+    #[test]
+    fn test_limits() {
+        let public = SecretKey::random(&mut thread_rng()).public_key();
+        let envelope = |nonce| Envelope::seal(&public, &raw_tx(&wallet(1), nonce));
+
+        let size = envelope(0).size();
+
+        let mut pool = EncryptedPool::new(MempoolLimits {
+            max_count: 3,
+            max_bytes: size * 2,
+            max_per_sender: 2,
+            ttl: Duration::from_secs(60),
+        });
+
+        // sender of envelope is unknown, only count and bytes apply
+        assert_eq!(pool.add(envelope(0)), TxAdmission::Added);
+        assert_eq!(pool.add(envelope(0)), TxAdmission::Added);
+        assert_eq!(
+            pool.add(envelope(0)),
+            TxAdmission::Rejected(RejectReason::PoolFull)
+        );
+
+        pool.commit().unwrap();
+        assert_eq!(pool.add(envelope(0)), TxAdmission::Added);
+
+        let (a, b) = (wallet(1), wallet(2));
+
+        assert!(pool.push(tx(&a, 0), U256::zero()));
+        assert!(pool.push(tx(&a, 2), U256::zero()));
+        assert!(!pool.push(tx(&a, 1), U256::zero()));
+        assert!(!pool.push(tx(&a, 3), U256::zero()));
+        assert_eq!(pool.deferred(&a.address()), 1);
+
+        assert!(pool.push(tx(&b, 0), U256::zero()));
+        assert!(pool.expire(Instant::now()).is_empty());
+
+        // revealed transaction expires with later ones of its sender
+        let expired = pool.expire(Instant::now() + Duration::from_secs(61));
+        let hashes: Vec<_> = expired.iter().map(|tx| tx.hash).collect();
+        assert_eq!(hashes.len(), 3);
+        assert!(hashes.contains(&tx(&a, 2).hash));
+        assert!(pool.revealed().is_empty());
+        assert_eq!(pool.deferred(&a.address()), 0);
+
+        // nonce of sender is taken from state again
+        assert!(pool.push(tx(&a, 1), U256::one()));
+        assert_eq!(pool.revealed(), vec![tx(&a, 1)]);
+    }
+}
@@ -0,0 +1,492 @@
+use std::{
+    collections::VecDeque,
+    io,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use crate::{
+    ApiRequest, ApiResponse, Commitment, CommitmentRoot, EncryptedPool, EncryptedSequencerApi,
+    EncryptedSequencerConfig, Error, KeyHolder, Result,
+};
+use async_trait::async_trait;
+use ethers_core::types::{BlockId, BlockNumber, U256};
+use fluct_core::{ConsensusGenesis, SequencerService, Service, Transaction, TxValidator, Web3Api};
+use fluct_service::{
+    local_rpc, AsyncStepService, AsyncStepServiceWapper1, CallError, Caller, Hander,
+};
+use futures_util::future::{select, Either};
+use tokio::{
+    sync::{broadcast, oneshot},
+    time::{self, Instant},
+};
+
+/// Commitment roots kept for slow subscribers
+const COMMITMENT_CAPACITY: usize = 64;
+
+enum Event {
+    Request(ApiRequest, Option<oneshot::Sender<ApiResponse>>),
+    Reveal,
+}
+
+/// Commitment being revealed, kept until all of its transactions are checked
+struct Revealing {
+    commitment: Commitment,
+    /// Transactions not checked yet, `None` before keys are revealed
+    opened: Option<VecDeque<Transaction>>,
+}
+
+struct EncryptedSequencer<K> {
+    handler: Hander<ApiRequest, ApiResponse>,
+    caller: Caller<ApiRequest, ApiResponse>,
+    txpool: EncryptedPool,
+    key_holder: K,
+    reveal_interval: Duration,
+    next_reveal: Instant,
+    revealing: Option<Revealing>,
+    commitments: broadcast::Sender<CommitmentRoot>,
+    validator: Arc<OnceLock<TxValidator>>,
+    web3_api: Option<Box<dyn Web3Api>>,
+}
+
+impl<K> EncryptedSequencer<K>
+where
+    K: KeyHolder,
+{
+    pub fn new(key_holder: K, config: EncryptedSequencerConfig) -> Self {
+        let (handler, caller) = local_rpc();
+
+        let (commitments, _) = broadcast::channel(COMMITMENT_CAPACITY);
+
+        Self {
+            handler,
+            caller,
+            txpool: EncryptedPool::new(config.limits),
+            key_holder,
+            reveal_interval: config.reveal_interval,
+            next_reveal: Instant::now() + config.reveal_interval,
+            revealing: None,
+            commitments,
+            validator: Arc::new(OnceLock::new()),
+            web3_api: None,
+        }
+    }
+
+    async fn next_event(&mut self) -> Result<Event> {
+        let request = Box::pin(self.handler.recv());
+        let tick = Box::pin(time::sleep_until(self.next_reveal));
+
+        let event = match select(request, tick).await {
+            Either::Left((r, _)) => {
+                let (req, resper) = r?;
+                Event::Request(req, resper)
+            }
+            Either::Right(_) => Event::Reveal,
+        };
+
+        Ok(event)
+    }
+
+    /// Check revealed transaction with account state, return account nonce if executable
+    async fn check_state(&mut self, tx: &Transaction) -> Result<Option<U256>> {
+        let validator = self.validator.get().ok_or(Error::NotInited)?;
+
+        let api = self.web3_api.as_mut().ok_or(Error::NoWeb3ApiConfiged)?;
+
+        if api.get_transaction(tx.hash).await?.is_some() {
+            return Ok(None);
+        }
+
+        let block = Some(BlockId::Number(BlockNumber::Latest));
+
+        let balance = api.balance(tx.from, block).await?;
+        let nonce = api.transaction_count(tx.from, block).await?;
+
+        if let Err(reason) = validator.validate_state(tx, balance, nonce) {
+            log::debug!("Drop revealed transaction {:?}: {}", tx.hash, reason);
+            return Ok(None);
+        }
+
+        Ok(Some(nonce))
+    }
+
+    /// Commit order of waiting envelopes and publish its root, then reveal them
+    ///
+    /// Commitment is kept on failure, and retried before committing new envelopes.
+    async fn reveal(&mut self) -> Result<()> {
+        let validator = self.validator.get().ok_or(Error::NotInited)?.clone();
+
+        if self.web3_api.is_none() {
+            return Err(Error::NoWeb3ApiConfiged);
+        }
+
+        let mut revealing = match self.revealing.take() {
+            Some(v) => v,
+            None => {
+                let Some(commitment) = self.txpool.commit() else {
+                    return Ok(());
+                };
+
+                let root = CommitmentRoot::from(&commitment);
+
+                log::debug!("Commit {:?}", root);
+
+                let _ = self.commitments.send(root);
+
+                Revealing {
+                    commitment,
+                    opened: None,
+                }
+            }
+        };
+
+        let result = self.process(&mut revealing, &validator).await;
+
+        if result.is_err() {
+            self.revealing = Some(revealing);
+        }
+
+        result
+    }
+
+    /// Reveal keys of commitment if not yet, then check its transactions in order
+    async fn process(&mut self, revealing: &mut Revealing, validator: &TxValidator) -> Result<()> {
+        let commitment = &revealing.commitment;
+
+        let opened = match &mut revealing.opened {
+            Some(v) => v,
+            None => {
+                let keys = self.key_holder.reveal(commitment)?;
+
+                let txs = EncryptedPool::open(commitment, &keys, validator);
+
+                log::debug!(
+                    "Reveal {} of {} envelopes at height {}",
+                    txs.len(),
+                    commitment.envelopes.len(),
+                    commitment.height
+                );
+
+                revealing.opened.insert(txs.into())
+            }
+        };
+
+        while let Some(tx) = opened.front() {
+            if let Some(nonce) = self.check_state(tx).await? {
+                let tx = tx.clone();
+                let hash = tx.hash;
+
+                if !self.txpool.push(tx, nonce) {
+                    log::debug!("Drop revealed transaction {:?}: nonce is used", hash);
+                }
+            }
+
+            opened.pop_front();
+        }
+
+        Ok(())
+    }
+
+    async fn _step(&mut self) -> Result<()> {
+        match self.next_event().await? {
+            Event::Request(ApiRequest::Envelope(envelope), resper) => {
+                let hash = envelope.hash();
+
+                let admission = self.txpool.add(*envelope);
+
+                log::debug!("Envelope {:?} admission: {:?}", hash, admission);
+
+                if let Some(resper) = resper {
+                    if resper.send(ApiResponse::Envelope(admission)).is_err() {
+                        log::warn!("Caller of envelope {:?} is gone", hash);
+                    }
+                }
+            }
+            Event::Request(ApiRequest::TxHash(txhash), _) => {
+                self.txpool.confirm(&txhash);
+            }
+            Event::Request(ApiRequest::GetAllTransaction, resper) => {
+                if let Some(resper) = resper {
+                    resper
+                        .send(ApiResponse::GetAllTransaction(self.txpool.revealed()))
+                        .map_err(|_| CallError::ChannelClosed)?;
+                } else {
+                    log::warn!("Use send method to get txpool")
+                }
+            }
+            Event::Reveal => {
+                self.next_reveal = Instant::now() + self.reveal_interval;
+
+                if let Err(e) = self.reveal().await {
+                    log::warn!(
+                        "Failed to reveal commitment, retry in {:?}: {}",
+                        self.reveal_interval,
+                        e
+                    );
+                }
+            }
+        }
+
+        for tx in self.txpool.expire(Instant::now().into_std()) {
+            log::debug!("Drop revealed transaction {:?}: expired", tx.hash);
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<K> AsyncStepService for EncryptedSequencer<K>
+where
+    K: KeyHolder,
+{
+    type Error = Error;
+
+    async fn step(&mut self) -> Result<()> {
+        self._step().await
+    }
+}
+
+pub struct EncryptedSequencerService<K>(AsyncStepServiceWapper1<EncryptedSequencer<K>>);
+
+impl<K> EncryptedSequencerService<K>
+where
+    K: KeyHolder,
+{
+    pub fn new(key_holder: K, config: EncryptedSequencerConfig) -> Self {
+        Self(AsyncStepServiceWapper1::new(EncryptedSequencer::new(
+            key_holder, config,
+        )))
+    }
+}
+
+impl<K> Service for EncryptedSequencerService<K>
+where
+    K: KeyHolder,
+{
+    type Error = io::Error;
+
+    fn start(&mut self) -> std::result::Result<(), Self::Error> {
+        self.0.start()
+    }
+
+    fn stop(&mut self) -> std::result::Result<(), Self::Error> {
+        self.0.stop()
+    }
+}
+
+#[async_trait]
+impl<K> SequencerService for EncryptedSequencerService<K>
+where
+    K: KeyHolder,
+{
+    type Api = EncryptedSequencerApi;
+
+    fn api(&self) -> EncryptedSequencerApi {
+        EncryptedSequencerApi {
+            caller: self.0.service0().caller.clone(),
+            validator: self.0.service0().validator.clone(),
+            public_key: self.0.service0().key_holder.public_key(),
+            commitments: self.0.service0().commitments.clone(),
+        }
+    }
+
+    fn set_api(&mut self, web3_api: impl Web3Api) {
+        self.0.service0_mut().web3_api = Some(Box::new(web3_api));
+    }
+
+    fn init(&mut self, genesis: &ConsensusGenesis<Transaction>) {
+        if self.0.service0().validator.set(genesis.into()).is_err() {
+            log::warn!("Sequencer already inited");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use async_trait::async_trait;
+    use ethers_core::{
+        k256::SecretKey,
+        rand::thread_rng,
+        types::{
+            transaction::eip2718::TypedTransaction, Block, BlockId, Bytes, SyncingStatus,
+            TransactionReceipt, TransactionRequest, H160, H256, U256,
+        },
+    };
+    use ethers_signers::{LocalWallet, Signer};
+    use fluct_core::{Transaction, TxValidator, Web3Api, Web3Error};
+
+    use crate::{EncryptedSequencerConfig, Envelope, KeyHolder, LocalKeyHolder};
+
+    use super::EncryptedSequencer;
+
+    /// Web3 api of empty chain, fails while `failures` is not zero
+    #[derive(Default)]
+    struct MockWeb3Api {
+        failures: Arc<AtomicUsize>,
+    }
+
+    impl MockWeb3Api {
+        fn check(&self) -> Result<(), Web3Error> {
+            match self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |v| v.checked_sub(1))
+            {
+                Ok(_) => Err(Web3Error::EmptyResponse),
+                Err(_) => Ok(()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Web3Api for MockWeb3Api {
+        async fn block_number(&mut self) -> Result<u64, Web3Error> {
+            self.check().map(|_| 0)
+        }
+
+        async fn chain_id(&mut self) -> Result<u64, Web3Error> {
+            self.check().map(|_| 901)
+        }
+
+        async fn get_block(
+            &mut self,
+            _block: BlockId,
+        ) -> Result<Option<Block<Transaction>>, Web3Error> {
+            self.check().map(|_| None)
+        }
+
+        async fn get_transaction(&mut self, _hash: H256) -> Result<Option<Transaction>, Web3Error> {
+            self.check().map(|_| None)
+        }
+
+        async fn get_transaction_receipt(
+            &mut self,
+            _hash: H256,
+        ) -> Result<Option<TransactionReceipt>, Web3Error> {
+            self.check().map(|_| None)
+        }
+
+        async fn syncing(&mut self) -> Result<SyncingStatus, Web3Error> {
+            self.check().map(|_| SyncingStatus::IsFalse)
+        }
+
+        async fn balance(
+            &mut self,
+            _address: H160,
+            _block: Option<BlockId>,
+        ) -> Result<U256, Web3Error> {
+            self.check().map(|_| U256::MAX)
+        }
+
+        async fn transaction_count(
+            &mut self,
+            _address: H160,
+            _block: Option<BlockId>,
+        ) -> Result<U256, Web3Error> {
+            self.check().map(|_| U256::zero())
+        }
+
+        async fn code(
+            &mut self,
+            _address: H160,
+            _block: Option<BlockId>,
+        ) -> Result<Bytes, Web3Error> {
+            self.check().map(|_| Bytes::new())
+        }
+
+        async fn storage_at(
+            &mut self,
+            _address: H160,
+            _index: H256,
+            _block: Option<BlockId>,
+        ) -> Result<H256, Web3Error> {
+            self.check().map(|_| H256::zero())
+        }
+    }
+
+    fn raw_tx(nonce: u64) -> Vec<u8> {
+        let wallet: LocalWallet =
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                .parse()
+                .unwrap();
+
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(wallet.address())
+            .nonce(nonce)
+            .gas(21000)
+            .gas_price(10)
+            .chain_id(901)
+            .into();
+
+        let signature = wallet
+            .with_chain_id(901u64)
+            .sign_transaction_sync(&tx)
+            .unwrap();
+
+        tx.rlp_signed(&signature).to_vec()
+    }
+
+    #[test]
+    fn test_reveal_retry() {
+        let key_holder = LocalKeyHolder::new(SecretKey::random(&mut thread_rng()));
+        let public = key_holder.public_key();
+
+        let mut sequencer =
+            EncryptedSequencer::new(key_holder, EncryptedSequencerConfig::default());
+
+        sequencer.web3_api = Some(Box::new(MockWeb3Api {
+            failures: Arc::new(AtomicUsize::new(1)),
+        }));
+
+        let _ = sequencer.validator.set(TxValidator {
+            chain_id: 901,
+            block_gas_limit: 30_000_000u64.into(),
+        });
+
+        let mut roots = sequencer.commitments.subscribe();
+
+        for nonce in [1, 0] {
+            sequencer
+                .txpool
+                .add(Envelope::seal(&public, &raw_tx(nonce)));
+        }
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        rt.block_on(async {
+            // keys are revealed, but checking state fails
+            assert!(sequencer.reveal().await.is_err());
+            assert!(sequencer.txpool.revealed().is_empty());
+
+            let root = roots.try_recv().unwrap();
+            assert_eq!(root.height, 0);
+
+            // retry continues with revealed keys, key holder is not asked again
+            sequencer.reveal().await.unwrap();
+            assert!(sequencer.revealing.is_none());
+            assert!(roots.try_recv().is_err());
+
+            let nonces: Vec<_> = sequencer
+                .txpool
+                .revealed()
+                .iter()
+                .map(|tx| tx.nonce.as_u64())
+                .collect();
+            assert_eq!(nonces, vec![0, 1]);
+
+            // next commitment starts at next height
+            sequencer.txpool.add(Envelope::seal(&public, &raw_tx(2)));
+            sequencer.reveal().await.unwrap();
+
+            assert_eq!(roots.try_recv().unwrap().height, 1);
+            assert_eq!(sequencer.txpool.revealed().len(), 3);
+        });
+    }
+}
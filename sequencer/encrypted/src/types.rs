@@ -0,0 +1,15 @@
+use ethers_core::types::H256;
+use fluct_core::{Transaction, TxAdmission};
+
+use crate::Envelope;
+
+pub enum ApiRequest {
+    Envelope(Box<Envelope>),
+    TxHash(H256),
+    GetAllTransaction,
+}
+
+pub enum ApiResponse {
+    Envelope(TxAdmission),
+    GetAllTransaction(Vec<Transaction>),
+}
@@ -1,23 +1,77 @@
+use std::sync::{Arc, OnceLock};
+
 use async_trait::async_trait;
-use ethers_core::types::H256;
-use fluct_core::{SequencerApi, Transaction};
+use ethers_core::types::{TxpoolContent, H256};
+use fluct_core::{
+    MempoolMetrics, PreConfirmation, SequencerApi, Transaction, TxAdmission, TxEvent, TxValidator,
+};
 use fluct_service::Caller;
+use futures_core::stream::BoxStream;
+use tokio::sync::broadcast;
+use tokio_stream::{wrappers::BroadcastStream, StreamExt};
 
 use crate::{ApiRequest, ApiResponse, Error, Result};
 
 #[derive(Clone)]
 pub struct DevSequencerApi {
     pub(crate) caller: Caller<ApiRequest, ApiResponse>,
+    pub(crate) events: broadcast::Sender<TxEvent>,
+    pub(crate) validator: Arc<OnceLock<TxValidator>>,
+}
+
+impl DevSequencerApi {
+    /// Size and eviction metrics of mempool
+    pub async fn metrics(&self) -> Result<MempoolMetrics> {
+        let resp = self.caller.call(ApiRequest::GetMetrics).await?;
+
+        match resp {
+            ApiResponse::Metrics(v) => Ok(v),
+            _ => Err(Error::UnexpectedResponse),
+        }
+    }
 }
 
 #[async_trait]
 impl SequencerApi for DevSequencerApi {
     type Error = Error;
 
-    fn broadcast_tx(&self, tx: Transaction) -> Result<()> {
-        self.caller.send(ApiRequest::Transaction(tx))?;
+    async fn broadcast_tx(&self, tx: Transaction) -> Result<Option<PreConfirmation>> {
+        let mut tx = tx;
 
-        Ok(())
+        let validator = self.validator.get().ok_or(Error::NotInited)?;
+        tx.from = validator.validate(&tx)?;
+
+        let resp = self
+            .caller
+            .call(ApiRequest::Transaction(Box::new(tx)))
+            .await?;
+
+        match resp {
+            ApiResponse::Transaction(TxAdmission::Rejected(reason), _) => Err(reason.into()),
+            ApiResponse::Transaction(_, preconf) => Ok(preconf.map(|v| *v)),
+            _ => Err(Error::UnexpectedResponse),
+        }
+    }
+
+    async fn submit_tx(&self, tx: Transaction) -> Result<TxAdmission> {
+        let mut tx = tx;
+
+        let validator = self.validator.get().ok_or(Error::NotInited)?;
+
+        match validator.validate(&tx) {
+            Ok(sender) => tx.from = sender,
+            Err(reason) => return Ok(TxAdmission::Rejected(reason)),
+        }
+
+        let resp = self
+            .caller
+            .call(ApiRequest::Transaction(Box::new(tx)))
+            .await?;
+
+        match resp {
+            ApiResponse::Transaction(v, _) => Ok(v),
+            _ => Err(Error::UnexpectedResponse),
+        }
     }
 
     fn comfirm_tx(&self, txhash: H256) -> Result<()> {
@@ -31,6 +85,49 @@ impl SequencerApi for DevSequencerApi {
 
         match resp {
             ApiResponse::GetAllTransaction(v) => Ok(v),
+            _ => Err(Error::UnexpectedResponse),
+        }
+    }
+
+    async fn content(&self) -> Result<TxpoolContent> {
+        let resp = self.caller.call(ApiRequest::GetContent).await?;
+
+        match resp {
+            ApiResponse::Content(v) => Ok(*v),
+            _ => Err(Error::UnexpectedResponse),
         }
     }
+
+    async fn get_tx(&self, txhash: H256) -> Result<Option<Transaction>> {
+        let resp = self.caller.call(ApiRequest::GetTransaction(txhash)).await?;
+
+        match resp {
+            ApiResponse::PoolTransaction(v) => Ok(v.map(|tx| *tx)),
+            _ => Err(Error::UnexpectedResponse),
+        }
+    }
+
+    async fn pre_confirmation(&self, txhash: H256) -> Result<Option<PreConfirmation>> {
+        let resp = self
+            .caller
+            .call(ApiRequest::GetPreConfirmation(txhash))
+            .await?;
+
+        match resp {
+            ApiResponse::PreConfirmation(v) => Ok(v.map(|v| *v)),
+            _ => Err(Error::UnexpectedResponse),
+        }
+    }
+
+    fn subscribe(&self) -> BoxStream<'static, TxEvent> {
+        let stream = BroadcastStream::new(self.events.subscribe()).filter_map(|r| match r {
+            Ok(event) => Some(event),
+            Err(e) => {
+                log::warn!("Subscriber of mempool events lagged: {}", e);
+                None
+            }
+        });
+
+        Box::pin(stream)
+    }
 }
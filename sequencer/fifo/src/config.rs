@@ -0,0 +1,51 @@
+use std::{path::PathBuf, time::Duration};
+
+use ethers_signers::LocalWallet;
+use fluct_core::MempoolLimits;
+
+/// Default percent of fee bump to replace a transaction
+pub const DEFAULT_PRICE_BUMP: u64 = 10;
+
+/// Default interval to compact journal
+pub const DEFAULT_REJOURNAL: Duration = Duration::from_secs(3600);
+
+/// Default interval to retry replaying journal
+pub const DEFAULT_REPLAY_RETRY: Duration = Duration::from_secs(5);
+
+/// Config of dev sequencer
+#[derive(Debug, Clone)]
+pub struct DevSequencerConfig {
+    /// Percent of both fee caps a transaction must bump to replace
+    /// another one with same sender and nonce
+    pub price_bump: u64,
+
+    /// Path of mempool journal, transactions are kept across restarts if set
+    pub journal: Option<PathBuf>,
+
+    /// Interval to compact journal
+    pub rejournal: Duration,
+
+    /// Interval to retry replaying journal, it's compacted only after replayed
+    pub replay_retry: Duration,
+
+    /// Size limits and time to live of mempool
+    ///
+    /// Only queued transactions are evicted when mempool is full, pending ones are kept.
+    pub limits: MempoolLimits,
+
+    /// Key to sign pre-confirmations, none are signed if not set
+    pub signer: Option<LocalWallet>,
+}
+
+impl Default for DevSequencerConfig {
+    fn default() -> Self {
+        Self {
+            price_bump: DEFAULT_PRICE_BUMP,
+            journal: None,
+            rejournal: DEFAULT_REJOURNAL,
+            replay_retry: DEFAULT_REPLAY_RETRY,
+            limits: MempoolLimits::default(),
+            signer: None,
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use fluct_core::Web3Error;
+use std::io;
+
+use fluct_core::{RejectReason, Web3Error};
 use fluct_service::{CallError, StepError};
 use thiserror::Error;
 
@@ -12,6 +14,21 @@ pub enum Error {
 
     #[error(transparent)]
     Web3Error(#[from] Web3Error),
+
+    #[error("Unexpected response from sequencer")]
+    UnexpectedResponse,
+
+    #[error("Sequencer not inited")]
+    NotInited,
+
+    #[error("Transaction rejected: {0}")]
+    Rejected(#[from] RejectReason),
+
+    #[error(transparent)]
+    IoError(#[from] io::Error),
+
+    #[error(transparent)]
+    FluctCoreError(#[from] fluct_core::Error),
 }
 
 impl StepError for Error {
@@ -21,6 +38,11 @@ impl StepError for Error {
             Self::CallError(CallError::SenderReject) => false,
             Self::NoWeb3ApiConfiged => true,
             Self::Web3Error(_) => true,
+            Self::UnexpectedResponse => false,
+            Self::NotInited => false,
+            Self::Rejected(_) => false,
+            Self::IoError(_) => false,
+            Self::FluctCoreError(_) => false,
         }
     }
 }
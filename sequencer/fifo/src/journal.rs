@@ -0,0 +1,332 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use ethers_core::{
+    types::H256,
+    utils::rlp::{Rlp, RlpStream},
+};
+use fluct_core::{transaction_utils, Transaction};
+
+use crate::{pool::Slot, Result};
+
+const RECORD_ADD: u8 = 0;
+const RECORD_REMOVE: u8 = 1;
+const RECORD_SEQUENCE: u8 = 2;
+const RECORD_COMMIT: u8 = 3;
+
+/// Committed transactions in order
+pub(crate) type Committed = Vec<(H256, Slot)>;
+
+/// On-disk journal of mempool
+///
+/// Each record is a RLP list `[kind, data]`, data is transaction bytes for added
+/// transaction, hash for removed transaction, big endian index of next
+/// pre-confirmation, or hash, index and block number of committed transaction.
+/// Records are appended, and the file is rewritten with live transactions, committed
+/// order and latest index only when compacted.
+pub(crate) struct Journal {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl Journal {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        let writer = Self::appender(&path)?;
+
+        Ok(Self { path, writer })
+    }
+
+    fn appender(path: &Path) -> Result<BufWriter<File>> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(BufWriter::new(file))
+    }
+
+    fn encode(kind: u8, data: &[u8]) -> Vec<u8> {
+        let mut s = RlpStream::new_list(2);
+        s.append(&kind);
+        s.append(&data);
+        s.out().to_vec()
+    }
+
+    fn write(&mut self, kind: u8, data: &[u8]) -> Result<()> {
+        self.writer.write_all(&Self::encode(kind, data))?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Record transaction added into pool
+    pub fn add(&mut self, tx: &Transaction) -> Result<()> {
+        for bytes in transaction_utils::transaction_to_bytes(std::slice::from_ref(tx)) {
+            self.write(RECORD_ADD, &bytes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Record transaction removed from pool
+    pub fn remove(&mut self, txhash: &H256) -> Result<()> {
+        self.write(RECORD_REMOVE, txhash.as_bytes())
+    }
+
+    /// Record index of next pre-confirmation
+    pub fn set_sequence(&mut self, sequence: u64) -> Result<()> {
+        self.write(RECORD_SEQUENCE, &sequence.to_be_bytes())
+    }
+
+    fn encode_commit(txhash: &H256, slot: &Slot) -> Vec<u8> {
+        let mut data = txhash.as_bytes().to_vec();
+        data.extend_from_slice(&slot.index.to_be_bytes());
+        data.extend_from_slice(&slot.block_number.to_be_bytes());
+        data
+    }
+
+    fn decode_commit(data: &[u8]) -> (H256, Slot) {
+        let mut index = [0u8; 8];
+        index.copy_from_slice(&data[32..40]);
+
+        let mut block_number = [0u8; 8];
+        block_number.copy_from_slice(&data[40..48]);
+
+        let slot = Slot {
+            index: u64::from_be_bytes(index),
+            block_number: u64::from_be_bytes(block_number),
+        };
+
+        (H256::from_slice(&data[..32]), slot)
+    }
+
+    /// Record transaction committed at slot, replacement takes slot of replaced one
+    pub fn commit(&mut self, txhash: &H256, slot: &Slot) -> Result<()> {
+        self.write(RECORD_COMMIT, &Self::encode_commit(txhash, slot))
+    }
+
+    /// Transactions still in pool in added order, index of next pre-confirmation, and
+    /// committed transactions in order
+    ///
+    /// A broken record at the end, like written partially before crash, is ignored.
+    pub fn load(&self) -> Result<(Vec<Transaction>, u64, Committed)> {
+        let data = fs::read(&self.path)?;
+
+        let mut txs: Vec<Transaction> = Vec::new();
+        let mut sequence = 0;
+
+        let mut committed: BTreeMap<u64, H256> = BTreeMap::new();
+        let mut slots: HashMap<H256, Slot> = HashMap::new();
+
+        let mut offset = 0;
+
+        while offset < data.len() {
+            let rlp = Rlp::new(&data[offset..]);
+
+            let record = rlp.payload_info().and_then(|info| {
+                let kind: u8 = rlp.val_at(0)?;
+                let data: Vec<u8> = rlp.val_at(1)?;
+
+                Ok((info.header_len + info.value_len, kind, data))
+            });
+
+            let Ok((len, kind, data)) = record else {
+                log::warn!("Broken journal record at {}, skip the rest", offset);
+                break;
+            };
+
+            let start = offset;
+            offset += len;
+
+            match kind {
+                RECORD_ADD => match transaction_utils::bytes_to_transaction(&[data.into()]) {
+                    Ok(decoded) => txs.extend(decoded),
+                    Err(err) => log::warn!("Undecodable journal transaction at {}: {}", start, err),
+                },
+                RECORD_REMOVE if data.len() == 32 => {
+                    let hash = H256::from_slice(&data);
+                    txs.retain(|tx| tx.hash != hash);
+
+                    if let Some(slot) = slots.remove(&hash) {
+                        committed.remove(&slot.index);
+                    }
+                }
+                RECORD_COMMIT if data.len() == 48 => {
+                    let (hash, slot) = Self::decode_commit(&data);
+
+                    if let Some(old) = committed.insert(slot.index, hash) {
+                        slots.remove(&old);
+                    }
+
+                    slots.insert(hash, slot);
+                }
+                RECORD_SEQUENCE if data.len() == 8 => {
+                    let mut bytes = [0u8; 8];
+                    bytes.copy_from_slice(&data);
+                    sequence = u64::from_be_bytes(bytes);
+                }
+                _ => log::warn!("Unknown journal record at {}", start),
+            }
+        }
+
+        let committed = committed
+            .into_values()
+            .map(|hash| (hash, slots[&hash]))
+            .collect();
+
+        Ok((txs, sequence, committed))
+    }
+
+    /// Rewrite journal with given transactions, index of next pre-confirmation and
+    /// committed order
+    pub fn compact(
+        &mut self,
+        txs: &[Transaction],
+        sequence: u64,
+        committed: &[(H256, Slot)],
+    ) -> Result<()> {
+        let tmp = self.path.with_extension("tmp");
+
+        {
+            let mut writer = BufWriter::new(File::create(&tmp)?);
+
+            writer.write_all(&Self::encode(RECORD_SEQUENCE, &sequence.to_be_bytes()))?;
+
+            for bytes in transaction_utils::transaction_to_bytes(txs) {
+                writer.write_all(&Self::encode(RECORD_ADD, &bytes))?;
+            }
+
+            for (hash, slot) in committed {
+                let data = Self::encode_commit(hash, slot);
+                writer.write_all(&Self::encode(RECORD_COMMIT, &data))?;
+            }
+
+            writer.flush()?;
+        }
+
+        fs::rename(&tmp, &self.path)?;
+
+        self.writer = Self::appender(&self.path)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::OpenOptions, io::Write};
+
+    use ethers_core::types::H256;
+    use fluct_core::Transaction;
+
+    use crate::pool::Slot;
+
+    use super::{Journal, RECORD_ADD};
+
+    fn tx(nonce: u64) -> Transaction {
+        let mut tx = Transaction {
+            nonce: nonce.into(),
+            gas: 21000.into(),
+            gas_price: Some(1.into()),
+            ..Default::default()
+        };
+        tx.hash = tx.hash();
+        tx
+    }
+
+    fn nonces(txs: &[Transaction]) -> Vec<u64> {
+        txs.iter().map(|tx| tx.nonce.as_u64()).collect()
+    }
+
+    #[test]
+    fn test_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transactions.rlp");
+
+        let mut journal = Journal::open(&path).unwrap();
+
+        journal.add(&tx(0)).unwrap();
+        journal.add(&tx(1)).unwrap();
+        journal.add(&tx(2)).unwrap();
+        journal.remove(&tx(1).hash).unwrap();
+        drop(journal);
+
+        let mut journal = Journal::open(&path).unwrap();
+        let (txs, sequence, _) = journal.load().unwrap();
+        assert_eq!(nonces(&txs), vec![0, 2]);
+        assert_eq!(txs[1].hash, tx(2).hash);
+        assert_eq!(sequence, 0);
+
+        journal.set_sequence(5).unwrap();
+        journal.set_sequence(7).unwrap();
+        assert_eq!(journal.load().unwrap().1, 7);
+
+        journal.compact(&txs[1..], 8, &[]).unwrap();
+        journal.add(&tx(3)).unwrap();
+
+        let (txs, sequence, _) = journal.load().unwrap();
+        assert_eq!(nonces(&txs), vec![2, 3]);
+        assert_eq!(sequence, 8);
+
+        // partial record written before crash
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[0xf8, 0xff, 0x00]).unwrap();
+        assert_eq!(nonces(&journal.load().unwrap().0), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_undecodable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transactions.rlp");
+
+        let mut journal = Journal::open(&path).unwrap();
+
+        journal.add(&tx(0)).unwrap();
+        journal.write(RECORD_ADD, &[0xc0]).unwrap();
+        journal.add(&tx(1)).unwrap();
+
+        assert_eq!(nonces(&journal.load().unwrap().0), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transactions.rlp");
+
+        let mut journal = Journal::open(&path).unwrap();
+
+        let slot = |index: u64, block_number: u64| Slot {
+            index,
+            block_number,
+        };
+
+        for nonce in 0..4 {
+            journal.add(&tx(nonce)).unwrap();
+        }
+
+        journal.commit(&tx(1).hash, &slot(1, 5)).unwrap();
+        journal.commit(&tx(0).hash, &slot(0, 5)).unwrap();
+        journal.commit(&tx(2).hash, &slot(2, 6)).unwrap();
+
+        // replacement takes the slot, removed one leaves order
+        journal.commit(&tx(3).hash, &slot(1, 5)).unwrap();
+        journal.remove(&tx(1).hash).unwrap();
+        journal.remove(&tx(2).hash).unwrap();
+
+        let (txs, _, committed) = journal.load().unwrap();
+        let expected = vec![(tx(0).hash, slot(0, 5)), (tx(3).hash, slot(1, 5))];
+        assert_eq!(committed, expected);
+
+        journal.compact(&txs, 3, &committed).unwrap();
+        assert_eq!(journal.load().unwrap().2, expected);
+
+        // short record is ignored
+        journal
+            .write(super::RECORD_COMMIT, H256::zero().as_bytes())
+            .unwrap();
+        assert_eq!(journal.load().unwrap().2, expected);
+    }
+}
@@ -1,9 +1,16 @@
 mod sequencer;
 pub use sequencer::*;
 
+mod journal;
+mod pool;
+mod sender;
+
 mod api;
 pub use api::*;
 
+mod config;
+pub use config::*;
+
 mod error;
 pub use error::*;
 
@@ -0,0 +1,907 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Instant,
+};
+
+use ethers_core::types::{TxpoolContent, H160, H256, U256};
+use fluct_core::{
+    transaction_utils, EvictReason, MempoolLimits, MempoolMetrics, RejectReason, Transaction,
+    TxAdmission,
+};
+
+use crate::{sender::SenderTxs, DEFAULT_PRICE_BUMP};
+
+/// Position of pre-confirmed transaction in committed order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Slot {
+    /// Index in sequence, never reused
+    pub index: u64,
+    /// Block transaction is expected to be included
+    pub block_number: u64,
+}
+
+#[derive(Debug)]
+struct PoolTx {
+    tx: Transaction,
+    /// Bytes of RLP encoded transaction
+    size: usize,
+    added: Instant,
+}
+
+/// Transaction pool in arrival order
+///
+/// Each transaction gets an increasing id when added, ids are never reused, so
+/// indexer entries keep valid when other transactions are removed.
+///
+/// Pre-confirmed transactions are committed to an append-only order, which comes
+/// before other executable transactions. A sender's committed transactions are always
+/// a nonce prefix of its pending ones, so committing one commits lower nonces first,
+/// and a committed transaction queued again after a nonce gap leaves the order.
+#[derive(Debug)]
+pub(crate) struct FifoPool {
+    price_bump: u64,
+    limits: MempoolLimits,
+    metrics: MempoolMetrics,
+    next_id: u64,
+    txpool: BTreeMap<u64, PoolTx>,
+    txindexer: HashMap<H256, u64>,
+    senders: HashMap<H160, SenderTxs>,
+    /// Transactions evicted or expired, not taken yet
+    evicted: Vec<(Transaction, EvictReason)>,
+    /// Index of next committed transaction
+    next_index: u64,
+    /// Committed transactions by index
+    committed: BTreeMap<u64, H256>,
+    slots: HashMap<H256, Slot>,
+    /// Last block filled by committed transactions, and gas used in it
+    tail: (u64, U256),
+}
+
+impl Default for FifoPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_PRICE_BUMP, MempoolLimits::default())
+    }
+}
+
+impl FifoPool {
+    pub fn new(price_bump: u64, limits: MempoolLimits) -> Self {
+        Self {
+            price_bump,
+            limits,
+            metrics: MempoolMetrics::default(),
+            next_id: 0,
+            txpool: BTreeMap::new(),
+            txindexer: HashMap::new(),
+            senders: HashMap::new(),
+            evicted: Vec::new(),
+            next_index: 0,
+            committed: BTreeMap::new(),
+            slots: HashMap::new(),
+            tail: (0, U256::zero()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.txpool.len()
+    }
+
+    pub fn metrics(&self) -> MempoolMetrics {
+        MempoolMetrics {
+            count: self.len(),
+            ..self.metrics.clone()
+        }
+    }
+
+    pub fn get(&self, txhash: &H256) -> Option<&Transaction> {
+        let id = self.txindexer.get(txhash)?;
+        self.txpool.get(id).map(|v| &v.tx)
+    }
+
+    fn is_pending(&self, tx: &Transaction) -> bool {
+        self.senders
+            .get(&tx.from)
+            .is_some_and(|sender| sender.is_pending(&tx.nonce))
+    }
+
+    fn push(&mut self, tx: Transaction) {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let size = tx.rlp().len();
+        self.metrics.bytes += size;
+
+        self.txindexer.insert(tx.hash, id);
+        self.txpool.insert(
+            id,
+            PoolTx {
+                tx,
+                size,
+                added: Instant::now(),
+            },
+        );
+    }
+
+    /// Replace transaction with same sender and nonce, if fee is bumped enough
+    fn replace(&mut self, old: H256, tx: Transaction) -> TxAdmission {
+        let Some(old_tx) = self.get(&old) else {
+            return TxAdmission::Rejected(RejectReason::ReplacementUnderpriced);
+        };
+
+        if !transaction_utils::price_bumped(old_tx, &tx, self.price_bump) {
+            return TxAdmission::Rejected(RejectReason::ReplacementUnderpriced);
+        }
+
+        if !self.make_room(tx.rlp().len(), Some(&old)) {
+            self.metrics.rejected_pool_full += 1;
+
+            return TxAdmission::Rejected(RejectReason::PoolFull);
+        }
+
+        if let Some(sender) = self.senders.get_mut(&tx.from) {
+            sender.replace(tx.nonce, tx.hash);
+        }
+
+        // Replacement takes position of replaced one in committed order
+        if let Some(slot) = self.slots.remove(&old) {
+            self.committed.insert(slot.index, tx.hash);
+            self.slots.insert(tx.hash, slot);
+        }
+
+        self.remove(&old);
+        self.push(tx);
+
+        TxAdmission::Replaced(old)
+    }
+
+    /// Evict newest queued transactions until new transaction fits in limits
+    ///
+    /// Pending transactions are never evicted, they are next in arrival order and
+    /// evicting them would let a later transaction take their place. A full pool of
+    /// pending transactions rejects new ones until blocks drain it, or TTL expires them.
+    ///
+    /// Transaction being replaced is not counted and never evicted. Nothing is evicted
+    /// if it can not fit even with all queued transactions evicted.
+    fn make_room(&mut self, size: usize, replacing: Option<&H256>) -> bool {
+        let mut count = self.len() + 1;
+        let mut bytes = self.metrics.bytes + size;
+
+        if let Some(v) = replacing
+            .and_then(|hash| self.txindexer.get(hash))
+            .and_then(|id| self.txpool.get(id))
+        {
+            count -= 1;
+            bytes -= v.size;
+        }
+
+        let mut victims = Vec::new();
+
+        for v in self.txpool.values().rev() {
+            if count <= self.limits.max_count && bytes <= self.limits.max_bytes {
+                break;
+            }
+
+            if self.is_pending(&v.tx) || Some(&v.tx.hash) == replacing {
+                continue;
+            }
+
+            count -= 1;
+            bytes -= v.size;
+            victims.push(v.tx.hash);
+        }
+
+        if count > self.limits.max_count || bytes > self.limits.max_bytes {
+            return false;
+        }
+
+        for victim in victims {
+            log::debug!("Mempool is full, evict transaction: {:?}", victim);
+
+            if let Some(tx) = self.evict(&victim) {
+                self.metrics.evicted += 1;
+                self.evicted.push((tx, EvictReason::PoolFull));
+            }
+        }
+
+        true
+    }
+
+    /// Add transaction
+    ///
+    /// `account_nonce` is only used when sender has no transaction in pool.
+    pub fn insert(&mut self, tx: Transaction, account_nonce: U256) -> TxAdmission {
+        if self.txindexer.contains_key(&tx.hash) {
+            return TxAdmission::AlreadyKnown;
+        }
+
+        let old = self
+            .senders
+            .get(&tx.from)
+            .and_then(|sender| sender.get(&tx.nonce))
+            .copied();
+
+        if let Some(old) = old {
+            return self.replace(old, tx);
+        }
+
+        let used = self.senders.get(&tx.from).map_or(0, |s| s.len());
+
+        if used >= self.limits.max_per_sender {
+            self.metrics.rejected_sender_full += 1;
+
+            let reason = RejectReason::SenderSlotsFull(self.limits.max_per_sender);
+            return TxAdmission::Rejected(reason);
+        }
+
+        let sender = self
+            .senders
+            .entry(tx.from)
+            .or_insert_with(|| SenderTxs::new(account_nonce));
+
+        if let Err(reason) = sender.insert(tx.nonce, tx.hash) {
+            if sender.is_empty() {
+                self.senders.remove(&tx.from);
+            }
+
+            return TxAdmission::Rejected(reason);
+        }
+
+        if !self.make_room(tx.rlp().len(), None) {
+            self.remove_sender_tx(&tx);
+
+            self.metrics.rejected_pool_full += 1;
+
+            return TxAdmission::Rejected(RejectReason::PoolFull);
+        }
+
+        self.push(tx);
+
+        TxAdmission::Added
+    }
+
+    fn remove(&mut self, txhash: &H256) -> Option<Transaction> {
+        if let Some(slot) = self.slots.remove(txhash) {
+            self.committed.remove(&slot.index);
+        }
+
+        let id = self.txindexer.remove(txhash)?;
+        let v = self.txpool.remove(&id)?;
+
+        self.metrics.bytes -= v.size;
+
+        Some(v.tx)
+    }
+
+    /// Drop transaction without execution
+    fn evict(&mut self, txhash: &H256) -> Option<Transaction> {
+        let tx = self.remove(txhash)?;
+
+        self.remove_sender_tx(&tx);
+
+        Some(tx)
+    }
+
+    fn remove_sender_tx(&mut self, tx: &Transaction) {
+        if let Some(sender) = self.senders.get_mut(&tx.from) {
+            sender.remove(&tx.nonce);
+
+            // Transactions queued after the gap leave committed order
+            for hash in sender.queued() {
+                if let Some(slot) = self.slots.remove(hash) {
+                    self.committed.remove(&slot.index);
+                }
+            }
+
+            if sender.is_empty() {
+                self.senders.remove(&tx.from);
+            }
+        }
+    }
+
+    /// Drop transactions live longer than time to live
+    pub fn expire(&mut self, now: Instant) {
+        let expired: Vec<_> = self
+            .txpool
+            .values()
+            .take_while(|v| now.saturating_duration_since(v.added) >= self.limits.ttl)
+            .map(|v| v.tx.hash)
+            .collect();
+
+        for hash in expired {
+            log::debug!("Transaction expired: {:?}", hash);
+
+            if let Some(tx) = self.evict(&hash) {
+                self.metrics.expired += 1;
+                self.evicted.push((tx, EvictReason::Expired));
+            }
+        }
+    }
+
+    /// Take transactions evicted or expired since last call
+    pub fn take_evicted(&mut self) -> Vec<(Transaction, EvictReason)> {
+        std::mem::take(&mut self.evicted)
+    }
+
+    /// All transactions in arrival order
+    pub fn all(&self) -> Vec<Transaction> {
+        self.txpool.values().map(|v| v.tx.clone()).collect()
+    }
+
+    /// Transactions grouped by sender and nonce, split into pending and queued
+    pub fn content(&self) -> TxpoolContent {
+        let mut content = TxpoolContent::default();
+
+        for PoolTx { tx, .. } in self.txpool.values() {
+            let txs = if self.is_pending(tx) {
+                &mut content.pending
+            } else {
+                &mut content.queued
+            };
+
+            txs.entry(tx.from)
+                .or_default()
+                .insert(tx.nonce.to_string(), tx.clone());
+        }
+
+        content
+    }
+
+    /// Transaction is executed, remove it and outdated transactions of sender
+    pub fn confirm(&mut self, txhash: &H256) -> Vec<Transaction> {
+        let Some((from, nonce)) = self.get(txhash).map(|tx| (tx.from, tx.nonce)) else {
+            return Vec::new();
+        };
+
+        let Some(sender) = self.senders.get_mut(&from) else {
+            return Vec::new();
+        };
+
+        let removed = sender.confirm(nonce);
+
+        if sender.is_empty() {
+            self.senders.remove(&from);
+        }
+
+        removed
+            .iter()
+            .filter_map(|hash| self.remove(hash))
+            .collect()
+    }
+
+    /// Index of next committed transaction
+    pub fn next_index(&self) -> u64 {
+        self.next_index
+    }
+
+    /// Index never goes back, journal may know a later one
+    pub fn set_next_index(&mut self, index: u64) {
+        self.next_index = self.next_index.max(index);
+    }
+
+    /// Position of committed transaction
+    pub fn slot(&self, txhash: &H256) -> Option<Slot> {
+        self.slots.get(txhash).copied()
+    }
+
+    /// Committed transactions in order
+    pub fn committed(&self) -> Vec<(H256, Slot)> {
+        self.committed
+            .values()
+            .map(|hash| (*hash, self.slots[hash]))
+            .collect()
+    }
+
+    fn append(&mut self, txhash: H256, gas: U256, head: u64, gas_limit: U256) -> Slot {
+        let (block, used) = &mut self.tail;
+
+        // Blocks filled before are on chain now
+        if *block <= head {
+            *block = head + 1;
+            *used = U256::zero();
+        }
+
+        if !used.is_zero() && used.saturating_add(gas) > gas_limit {
+            *block += 1;
+            *used = U256::zero();
+        }
+
+        *used = used.saturating_add(gas);
+
+        let slot = Slot {
+            index: self.next_index,
+            block_number: *block,
+        };
+
+        self.next_index += 1;
+        self.committed.insert(slot.index, txhash);
+        self.slots.insert(txhash, slot);
+
+        slot
+    }
+
+    /// Commit pending transaction, and pending ones of its sender with lower nonce
+    ///
+    /// Blocks after `head` are filled up to `gas_limit` in committed order. Return
+    /// transactions newly committed, empty if transaction is not pending or committed.
+    pub fn commit(&mut self, txhash: &H256, head: u64, gas_limit: U256) -> Vec<(H256, Slot)> {
+        let Some(tx) = self.get(txhash) else {
+            return Vec::new();
+        };
+
+        if self.slots.contains_key(txhash) || !self.is_pending(tx) {
+            return Vec::new();
+        }
+
+        let Some(sender) = self.senders.get(&tx.from) else {
+            return Vec::new();
+        };
+
+        let nonce = tx.nonce;
+
+        let txs: Vec<_> = sender
+            .pending()
+            .filter_map(|hash| self.get(hash))
+            .take_while(|tx| tx.nonce <= nonce)
+            .filter(|tx| !self.slots.contains_key(&tx.hash))
+            .map(|tx| (tx.hash, tx.gas))
+            .collect();
+
+        txs.into_iter()
+            .map(|(hash, gas)| (hash, self.append(hash, gas, head, gas_limit)))
+            .collect()
+    }
+
+    /// Put transaction back at its committed position, like after restart
+    ///
+    /// Return false if it can't keep the position, it's not pending or a lower nonce
+    /// of its sender is not committed.
+    pub fn restore(&mut self, txhash: H256, slot: Slot, gas_limit: U256) -> bool {
+        let Some(tx) = self.get(&txhash) else {
+            return false;
+        };
+
+        if self.slots.contains_key(&txhash) || self.committed.contains_key(&slot.index) {
+            return false;
+        }
+
+        let Some(sender) = self.senders.get(&tx.from).filter(|_| self.is_pending(tx)) else {
+            return false;
+        };
+
+        let nonce = tx.nonce;
+        let gas = tx.gas;
+
+        let prefix = sender
+            .pending()
+            .filter_map(|hash| self.get(hash))
+            .take_while(|tx| tx.nonce < nonce)
+            .all(|tx| self.slots.contains_key(&tx.hash));
+
+        if !prefix {
+            return false;
+        }
+
+        let (block, used) = &mut self.tail;
+
+        if slot.block_number > *block {
+            *block = slot.block_number;
+            *used = U256::zero();
+        }
+
+        if slot.block_number == *block {
+            *used = used.saturating_add(gas).min(gas_limit);
+        }
+
+        self.next_index = self.next_index.max(slot.index + 1);
+        self.committed.insert(slot.index, txhash);
+        self.slots.insert(txhash, slot);
+
+        true
+    }
+
+    /// Executable transactions, committed ones first in committed order
+    ///
+    /// Other senders are served in arrival order, but transactions of one sender
+    /// always come out in nonce order.
+    pub fn executable(&self) -> Vec<Transaction> {
+        let mut txs: Vec<_> = self
+            .committed
+            .values()
+            .filter_map(|hash| self.get(hash))
+            .cloned()
+            .collect();
+
+        let mut pending = HashMap::new();
+
+        for PoolTx { tx, .. } in self.txpool.values() {
+            if self.slots.contains_key(&tx.hash) {
+                continue;
+            }
+
+            let Some(sender) = self.senders.get(&tx.from) else {
+                continue;
+            };
+
+            if !sender.is_pending(&tx.nonce) {
+                continue;
+            }
+
+            let iter = pending.entry(tx.from).or_insert_with(|| {
+                sender
+                    .pending()
+                    .filter(|hash| !self.slots.contains_key(*hash))
+            });
+
+            if let Some(tx) = iter.next().and_then(|hash| self.get(hash)) {
+                txs.push(tx.clone());
+            }
+        }
+
+        txs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        time::{Duration, Instant},
+    };
+
+    use ethers_core::types::{H160, H256, U256};
+    use fluct_core::{EvictReason, MempoolLimits, RejectReason, Transaction, TxAdmission};
+    use proptest::prelude::*;
+
+    use super::FifoPool;
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Broadcast { sender: u64, nonce: u64, price: u64 },
+        Confirm(usize),
+        Commit(usize),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            (0..4u64, 0..8u64, 1..4u64).prop_map(|(sender, nonce, price)| Op::Broadcast {
+                sender,
+                nonce,
+                price
+            }),
+            any::<usize>().prop_map(Op::Confirm),
+            any::<usize>().prop_map(Op::Commit),
+        ]
+    }
+
+    fn check_invariants(pool: &FifoPool) {
+        assert_eq!(pool.txpool.len(), pool.txindexer.len());
+
+        for (hash, id) in &pool.txindexer {
+            assert_eq!(&pool.txpool[id].tx.hash, hash);
+        }
+
+        let bytes: usize = pool.txpool.values().map(|v| v.size).sum();
+        assert_eq!(pool.metrics.bytes, bytes);
+
+        let count: usize = pool.senders.values().map(|s| s.len()).sum();
+        assert_eq!(count, pool.len());
+
+        // committed transactions are pending, and come first in executable order
+        assert_eq!(pool.committed.len(), pool.slots.len());
+
+        let executable = pool.executable();
+
+        for ((index, hash), tx) in pool.committed.iter().zip(&executable) {
+            assert_eq!(&tx.hash, hash);
+            assert_eq!(pool.slots[hash].index, *index);
+            assert!(pool.is_pending(tx));
+            assert!(*index < pool.next_index);
+        }
+
+        // executable transactions of each sender have contiguous nonces in order
+        let mut last_nonce = HashMap::new();
+
+        for tx in executable {
+            assert!(pool.get(&tx.hash).is_some());
+
+            if let Some(last) = last_nonce.insert(tx.from, tx.nonce) {
+                assert_eq!(last + 1, tx.nonce);
+            }
+        }
+    }
+
+    fn tx(id: u64, nonce: u64, price: u64) -> Transaction {
+        Transaction {
+            hash: H256::from_low_u64_be(id),
+            from: H160::from_low_u64_be(1),
+            nonce: nonce.into(),
+            gas_price: Some(price.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_replace() {
+        let mut pool = FifoPool::default();
+
+        assert_eq!(pool.insert(tx(1, 0, 100), U256::zero()), TxAdmission::Added);
+        assert_eq!(pool.insert(tx(2, 1, 100), U256::zero()), TxAdmission::Added);
+
+        assert_eq!(
+            pool.insert(tx(3, 0, 109), U256::zero()),
+            TxAdmission::Rejected(RejectReason::ReplacementUnderpriced)
+        );
+        assert_eq!(
+            pool.insert(tx(3, 0, 110), U256::zero()),
+            TxAdmission::Replaced(H256::from_low_u64_be(1))
+        );
+
+        assert!(pool.get(&H256::from_low_u64_be(1)).is_none());
+        assert_eq!(pool.len(), 2);
+
+        let hashes: Vec<_> = pool.executable().iter().map(|tx| tx.hash).collect();
+        assert_eq!(
+            hashes,
+            vec![H256::from_low_u64_be(3), H256::from_low_u64_be(2)]
+        );
+
+        assert_eq!(pool.insert(tx(4, 3, 100), U256::zero()), TxAdmission::Added);
+
+        let content = pool.content();
+        let from = H160::from_low_u64_be(1);
+        assert_eq!(content.pending[&from].len(), 2);
+        assert_eq!(content.queued[&from]["3"].hash, H256::from_low_u64_be(4));
+    }
+
+    #[test]
+    fn test_commit() {
+        let mut pool = FifoPool::default();
+        let gas_limit = U256::from(2);
+
+        let tx = |id: u64, from: u64, nonce: u64| Transaction {
+            hash: H256::from_low_u64_be(id),
+            from: H160::from_low_u64_be(from),
+            nonce: nonce.into(),
+            gas: 1.into(),
+            gas_price: Some(100.into()),
+            ..Default::default()
+        };
+
+        let hash = H256::from_low_u64_be;
+
+        assert_eq!(pool.insert(tx(1, 1, 0), U256::zero()), TxAdmission::Added);
+        assert_eq!(pool.insert(tx(2, 2, 0), U256::zero()), TxAdmission::Added);
+        assert_eq!(pool.insert(tx(3, 1, 1), U256::zero()), TxAdmission::Added);
+        assert_eq!(pool.insert(tx(4, 1, 2), U256::zero()), TxAdmission::Added);
+
+        // lower nonces of sender are committed first, blocks after head are filled
+        let committed = pool.commit(&hash(3), 5, gas_limit);
+        let slots: Vec<_> = committed
+            .iter()
+            .map(|(h, s)| (*h, s.index, s.block_number))
+            .collect();
+        assert_eq!(slots, vec![(hash(1), 0, 6), (hash(3), 1, 6)]);
+
+        assert!(pool.commit(&hash(3), 5, gas_limit).is_empty());
+        assert_eq!(pool.commit(&hash(2), 5, gas_limit)[0].1.block_number, 7);
+
+        let hashes =
+            |pool: &FifoPool| -> Vec<_> { pool.executable().iter().map(|tx| tx.hash).collect() };
+        assert_eq!(hashes(&pool), vec![hash(1), hash(3), hash(2), hash(4)]);
+
+        // replacement takes the slot
+        let mut replacement = tx(5, 1, 1);
+        replacement.gas_price = Some(200.into());
+        assert_eq!(
+            pool.insert(replacement, U256::zero()),
+            TxAdmission::Replaced(hash(3))
+        );
+        assert_eq!(pool.slot(&hash(5)).unwrap().index, 1);
+        assert_eq!(hashes(&pool), vec![hash(1), hash(5), hash(2), hash(4)]);
+
+        // evicted transaction leaves order, one queued after the gap too
+        assert_eq!(pool.commit(&hash(4), 5, gas_limit)[0].1.index, 3);
+        assert!(pool.evict(&hash(5)).is_some());
+        assert!(pool.slot(&hash(4)).is_none());
+        assert_eq!(hashes(&pool), vec![hash(1), hash(2)]);
+
+        assert_eq!(pool.confirm(&hash(1)).len(), 1);
+        assert_eq!(pool.committed().len(), 1);
+
+        // index is never reused
+        assert_eq!(pool.insert(tx(6, 3, 0), U256::zero()), TxAdmission::Added);
+        assert_eq!(pool.commit(&hash(6), 7, gas_limit)[0].1.index, 4);
+
+        check_invariants(&pool);
+    }
+
+    #[test]
+    fn test_limits() {
+        let limits = MempoolLimits {
+            max_count: 3,
+            max_per_sender: 2,
+            ttl: Duration::from_secs(60),
+            ..Default::default()
+        };
+
+        let mut pool = FifoPool::new(10, limits);
+
+        let tx = |id: u64, from: u64, nonce: u64| Transaction {
+            hash: H256::from_low_u64_be(id),
+            from: H160::from_low_u64_be(from),
+            nonce: nonce.into(),
+            ..Default::default()
+        };
+
+        assert_eq!(pool.insert(tx(1, 1, 0), U256::zero()), TxAdmission::Added);
+        assert_eq!(pool.insert(tx(2, 1, 2), U256::zero()), TxAdmission::Added);
+        assert_eq!(
+            pool.insert(tx(3, 1, 1), U256::zero()),
+            TxAdmission::Rejected(RejectReason::SenderSlotsFull(2))
+        );
+
+        // queued transaction 2 is evicted for a pending one
+        assert_eq!(pool.insert(tx(4, 2, 0), U256::zero()), TxAdmission::Added);
+        assert_eq!(pool.insert(tx(5, 3, 0), U256::zero()), TxAdmission::Added);
+        assert!(pool.get(&H256::from_low_u64_be(2)).is_none());
+
+        assert_eq!(
+            pool.insert(tx(6, 4, 0), U256::zero()),
+            TxAdmission::Rejected(RejectReason::PoolFull)
+        );
+
+        pool.expire(Instant::now() + Duration::from_secs(61));
+        assert_eq!(pool.len(), 0);
+
+        let evicted = pool.take_evicted();
+        assert_eq!(evicted.len(), 4);
+        assert_eq!(evicted[0].0.hash, H256::from_low_u64_be(2));
+        assert_eq!(evicted[0].1, EvictReason::PoolFull);
+        assert_eq!(evicted[1].1, EvictReason::Expired);
+
+        let metrics = pool.metrics();
+        assert_eq!(metrics.count, 0);
+        assert_eq!(metrics.bytes, 0);
+        assert_eq!(metrics.expired, 3);
+        assert_eq!(metrics.evicted, 1);
+        assert_eq!(metrics.rejected_pool_full, 1);
+        assert_eq!(metrics.rejected_sender_full, 1);
+    }
+
+    #[test]
+    fn test_replace_limits() {
+        let small = tx(1, 0, 100);
+
+        let queued = Transaction {
+            hash: H256::from_low_u64_be(2),
+            from: H160::from_low_u64_be(2),
+            nonce: 1.into(),
+            ..Default::default()
+        };
+
+        let limits = MempoolLimits {
+            max_bytes: small.rlp().len() + queued.rlp().len() + 8,
+            ..Default::default()
+        };
+
+        let mut pool = FifoPool::new(10, limits.clone());
+
+        assert_eq!(pool.insert(small, U256::zero()), TxAdmission::Added);
+        assert_eq!(pool.insert(queued, U256::zero()), TxAdmission::Added);
+
+        // larger replacement evicts queued transaction of other sender
+        let mut large = tx(3, 0, 110);
+        large.input = vec![0u8; 16].into();
+
+        assert_eq!(
+            pool.insert(large, U256::zero()),
+            TxAdmission::Replaced(H256::from_low_u64_be(1))
+        );
+        assert_eq!(pool.len(), 1);
+        assert!(pool.metrics().bytes <= limits.max_bytes);
+
+        let evicted = pool.take_evicted();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].0.hash, H256::from_low_u64_be(2));
+        assert_eq!(evicted[0].1, EvictReason::PoolFull);
+
+        let mut huge = tx(4, 0, 121);
+        huge.input = vec![0u8; limits.max_bytes].into();
+
+        assert_eq!(
+            pool.insert(huge, U256::zero()),
+            TxAdmission::Rejected(RejectReason::PoolFull)
+        );
+        assert!(pool.get(&H256::from_low_u64_be(3)).is_some());
+        assert_eq!(pool.metrics().rejected_pool_full, 1);
+
+        check_invariants(&pool);
+    }
+
+    proptest! {
+        #[test]
+        fn test_broadcast_confirm(ops in prop::collection::vec(op(), 1..64)) {
+            let limits = MempoolLimits {
+                max_count: 16,
+                max_per_sender: 6,
+                ..Default::default()
+            };
+
+            let mut pool = FifoPool::new(10, limits);
+            let mut id = 0u64;
+
+            for op in ops {
+                match op {
+                    Op::Broadcast { sender, nonce, price } => {
+                        id += 1;
+
+                        let tx = Transaction {
+                            hash: H256::from_low_u64_be(id),
+                            from: H160::from_low_u64_be(sender),
+                            nonce: nonce.into(),
+                            gas_price: Some(price.into()),
+                            ..Default::default()
+                        };
+
+                        let len = pool.len();
+
+                        match pool.insert(tx.clone(), U256::zero()) {
+                            // queued transactions may be evicted to make room
+                            TxAdmission::Added => prop_assert!(pool.len() <= len + 1),
+                            TxAdmission::Replaced(old) => {
+                                prop_assert_eq!(pool.len(), len);
+                                prop_assert!(pool.get(&old).is_none());
+                            }
+                            _ => {
+                                prop_assert_eq!(pool.len(), len);
+                                prop_assert!(pool.get(&tx.hash).is_none());
+                                continue;
+                            }
+                        }
+
+                        prop_assert!(pool.get(&tx.hash).is_some());
+                    }
+                    Op::Confirm(i) => {
+                        let txs = pool.executable();
+
+                        if txs.is_empty() {
+                            continue;
+                        }
+
+                        let tx = &txs[i % txs.len()];
+
+                        let len = pool.len();
+                        let removed = pool.confirm(&tx.hash);
+
+                        prop_assert!(removed.iter().any(|t| t.hash == tx.hash));
+                        prop_assert_eq!(pool.len(), len - removed.len());
+
+                        for t in removed {
+                            prop_assert!(pool.get(&t.hash).is_none());
+                            prop_assert_eq!(t.from, tx.from);
+                            prop_assert!(t.nonce <= tx.nonce);
+                        }
+                    }
+                    Op::Commit(i) => {
+                        let txs = pool.executable();
+
+                        if txs.is_empty() {
+                            continue;
+                        }
+
+                        let tx = &txs[i % txs.len()];
+                        let next_index = pool.next_index();
+
+                        for (hash, slot) in pool.commit(&tx.hash, 0, 21000.into()) {
+                            prop_assert_eq!(pool.slot(&hash), Some(slot));
+                            prop_assert!(slot.index >= next_index);
+                        }
+
+                        prop_assert!(pool.slot(&tx.hash).is_some());
+                    }
+                }
+
+                check_invariants(&pool);
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+
+use ethers_core::types::{H256, U256};
+use fluct_core::RejectReason;
+
+/// Transactions of one sender, split by executable
+///
+/// `pending` holds contiguous nonces start from account nonce, they can be executed in order.
+/// `queued` holds transactions after a nonce gap, they are promoted when the gap is filled.
+#[derive(Debug, Default)]
+pub(crate) struct SenderTxs {
+    /// Account nonce, nonce of first pending transaction
+    nonce: U256,
+    pending: BTreeMap<U256, H256>,
+    queued: BTreeMap<U256, H256>,
+}
+
+impl SenderTxs {
+    pub fn new(nonce: U256) -> Self {
+        Self {
+            nonce,
+            pending: BTreeMap::new(),
+            queued: BTreeMap::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty() && self.queued.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len() + self.queued.len()
+    }
+
+    pub fn is_pending(&self, nonce: &U256) -> bool {
+        self.pending.contains_key(nonce)
+    }
+
+    /// Hash of transaction with nonce
+    pub fn get(&self, nonce: &U256) -> Option<&H256> {
+        self.pending.get(nonce).or_else(|| self.queued.get(nonce))
+    }
+
+    /// Replace transaction with same nonce, return replaced hash
+    pub fn replace(&mut self, nonce: U256, hash: H256) -> Option<H256> {
+        let old = self
+            .pending
+            .get_mut(&nonce)
+            .or_else(|| self.queued.get_mut(&nonce))?;
+
+        Some(std::mem::replace(old, hash))
+    }
+
+    /// Pending transaction hashes in nonce order
+    pub fn pending(&self) -> impl Iterator<Item = &H256> {
+        self.pending.values()
+    }
+
+    /// Queued transaction hashes in nonce order
+    pub fn queued(&self) -> impl Iterator<Item = &H256> {
+        self.queued.values()
+    }
+
+    fn next_pending_nonce(&self) -> U256 {
+        self.nonce + self.pending.len()
+    }
+
+    fn promote(&mut self) {
+        let mut next = self.next_pending_nonce();
+
+        while let Some(hash) = self.queued.remove(&next) {
+            self.pending.insert(next, hash);
+            next += U256::one();
+        }
+    }
+
+    /// Insert transaction, nonce must not be executed or used
+    pub fn insert(&mut self, nonce: U256, hash: H256) -> Result<(), RejectReason> {
+        if nonce < self.nonce {
+            return Err(RejectReason::NonceTooLow(self.nonce, nonce));
+        }
+
+        if self.pending.contains_key(&nonce) || self.queued.contains_key(&nonce) {
+            return Err(RejectReason::ReplacementUnderpriced);
+        }
+
+        if nonce == self.next_pending_nonce() {
+            self.pending.insert(nonce, hash);
+            self.promote();
+        } else {
+            self.queued.insert(nonce, hash);
+        }
+
+        Ok(())
+    }
+
+    /// Drop transaction without execution, pending transactions after it are queued
+    pub fn remove(&mut self, nonce: &U256) -> Option<H256> {
+        if let Some(hash) = self.queued.remove(nonce) {
+            return Some(hash);
+        }
+
+        let hash = self.pending.remove(nonce)?;
+
+        let mut later = self.pending.split_off(nonce);
+        self.queued.append(&mut later);
+
+        Some(hash)
+    }
+
+    /// Transaction of nonce is executed, account nonce moves to `nonce + 1`
+    ///
+    /// Return hashes of transactions which are outdated, include the executed one.
+    pub fn confirm(&mut self, nonce: U256) -> Vec<H256> {
+        let mut removed = Vec::new();
+
+        if nonce < self.nonce {
+            return removed;
+        }
+
+        self.nonce = nonce + 1;
+
+        let mut txs = std::mem::take(&mut self.pending);
+        txs.append(&mut self.queued);
+
+        for (n, hash) in txs {
+            if n < self.nonce {
+                removed.push(hash);
+            } else {
+                self.queued.insert(n, hash);
+            }
+        }
+
+        self.promote();
+
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers_core::types::H256;
+    use fluct_core::RejectReason;
+
+    use super::SenderTxs;
+
+    fn h(v: u64) -> H256 {
+        H256::from_low_u64_be(v)
+    }
+
+    #[test]
+    fn test_pending_queued() {
+        let mut txs = SenderTxs::new(4.into());
+
+        assert_eq!(
+            txs.insert(3.into(), h(3)),
+            Err(RejectReason::NonceTooLow(4.into(), 3.into()))
+        );
+
+        assert!(txs.insert(5.into(), h(5)).is_ok());
+        assert!(txs.insert(7.into(), h(7)).is_ok());
+        assert_eq!(txs.pending().count(), 0);
+
+        assert!(txs.insert(4.into(), h(4)).is_ok());
+        assert_eq!(
+            txs.insert(4.into(), h(40)),
+            Err(RejectReason::ReplacementUnderpriced)
+        );
+        assert_eq!(txs.pending().copied().collect::<Vec<_>>(), vec![h(4), h(5)]);
+        assert!(!txs.is_pending(&7.into()));
+
+        assert_eq!(txs.confirm(5.into()), vec![h(4), h(5)]);
+        assert_eq!(txs.pending().count(), 0);
+
+        assert!(txs.insert(6.into(), h(6)).is_ok());
+        assert_eq!(txs.pending().copied().collect::<Vec<_>>(), vec![h(6), h(7)]);
+
+        assert_eq!(txs.remove(&6.into()), Some(h(6)));
+        assert_eq!(txs.pending().count(), 0);
+        assert_eq!(txs.len(), 1);
+
+        txs.confirm(7.into());
+        assert!(txs.is_empty());
+    }
+}
@@ -1,70 +1,475 @@
-use std::{collections::HashMap, io};
+use std::{
+    collections::HashMap,
+    io,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
 
-use crate::{ApiRequest, ApiResponse, DevSequencerApi, Error, Result};
+use crate::{
+    journal::Journal,
+    pool::{FifoPool, Slot},
+    ApiRequest, ApiResponse, DevSequencerApi, DevSequencerConfig, Error, Result,
+};
 use async_trait::async_trait;
-use ethers_core::types::H256;
-use fluct_core::{SequencerService, Service, Transaction, Web3Api};
+use ethers_core::types::{BlockId, BlockNumber, H256, U256};
+use ethers_signers::{LocalWallet, Signer};
+use fluct_core::{
+    ConsensusGenesis, EvictReason, PreConfirmation, SequencerService, Service, Transaction,
+    TxAdmission, TxEvent, TxValidator, Web3Api,
+};
 use fluct_service::{
     local_rpc, AsyncStepService, AsyncStepServiceWapper1, CallError, Caller, Hander,
 };
+use futures_util::future::{select, Either};
+use tokio::{
+    sync::{broadcast, oneshot},
+    time,
+};
+
+/// Events kept for slow subscribers
+const EVENT_CAPACITY: usize = 1024;
 
 struct DevSequencer {
     handler: Hander<ApiRequest, ApiResponse>,
     caller: Caller<ApiRequest, ApiResponse>,
-    txpool: Vec<Transaction>,
-    txindexer: HashMap<H256, usize>,
+    txpool: FifoPool,
+    events: broadcast::Sender<TxEvent>,
+    validator: Arc<OnceLock<TxValidator>>,
     web3_api: Option<Box<dyn Web3Api>>,
+    journal: Option<Journal>,
+    rejournal: Duration,
+    last_compact: Instant,
+    /// Journal is compacted only after replayed, or it loses transactions not replayed yet
+    replayed: bool,
+    replay_retry: Duration,
+    next_replay: time::Instant,
+    signer: Option<LocalWallet>,
+    preconfs: HashMap<H256, PreConfirmation>,
 }
 
 impl Default for DevSequencer {
     fn default() -> Self {
-        Self::new()
+        Self::new(DevSequencerConfig::default())
     }
 }
 
 impl DevSequencer {
-    pub fn new() -> Self {
+    pub fn new(config: DevSequencerConfig) -> Self {
         let (handler, caller) = local_rpc();
 
+        let journal = config.journal.and_then(|path| match Journal::open(&path) {
+            Ok(journal) => Some(journal),
+            Err(e) => {
+                log::error!("Failed to open journal {}: {}", path.display(), e);
+                None
+            }
+        });
+
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+
         Self {
             handler,
             caller,
-            txpool: Vec::new(),
-            txindexer: HashMap::new(),
+            txpool: FifoPool::new(config.price_bump, config.limits),
+            events,
+            validator: Arc::new(OnceLock::new()),
             web3_api: None,
+            journal,
+            rejournal: config.rejournal,
+            last_compact: Instant::now(),
+            replayed: false,
+            replay_retry: config.replay_retry,
+            next_replay: time::Instant::now(),
+            signer: config.signer,
+            preconfs: HashMap::new(),
+        }
+    }
+
+    /// Send event, it's dropped if no subscriber
+    ///
+    /// Pre-confirmation of transaction leaving mempool is dropped too.
+    fn emit(&mut self, event: TxEvent) {
+        match &event {
+            TxEvent::Added(_) => {}
+            TxEvent::Replaced { old, .. } => {
+                self.preconfs.remove(old);
+            }
+            TxEvent::Confirmed(hash) | TxEvent::Evicted(hash, _) => {
+                self.preconfs.remove(hash);
+            }
+        }
+
+        let _ = self.events.send(event);
+    }
+
+    /// Sign pre-confirmation of transaction at its committed position
+    async fn sign(&mut self, txhash: H256, slot: Slot) -> Result<Option<PreConfirmation>> {
+        let Some(signer) = &self.signer else {
+            return Ok(None);
+        };
+
+        let chain_id = self.validator.get().ok_or(Error::NotInited)?.chain_id;
+
+        let digest = PreConfirmation::digest(chain_id, txhash, slot.index, slot.block_number);
+
+        let signature = match signer.sign_message(digest).await {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("Failed to sign pre-confirmation of {:?}: {}", txhash, e);
+                return Ok(None);
+            }
+        };
+
+        let preconf = PreConfirmation {
+            chain_id,
+            tx_hash: txhash,
+            index: slot.index,
+            block_number: slot.block_number,
+            signature,
+        };
+
+        self.preconfs.insert(txhash, preconf.clone());
+
+        Ok(Some(preconf))
+    }
+
+    /// Commit admitted transaction to its position, and sign pre-confirmation of it
+    ///
+    /// Pending transactions of sender with lower nonce are committed and signed first.
+    /// Replacement keeps position of replaced one. Transaction after a nonce gap has no
+    /// position yet, so none is signed. Nothing is committed before journal is
+    /// replayed, committed order is unknown.
+    async fn pre_confirm(&mut self, txhash: H256) -> Result<Option<PreConfirmation>> {
+        if self.signer.is_none() || !self.replayed {
+            return Ok(None);
+        }
+
+        let gas_limit = self
+            .validator
+            .get()
+            .ok_or(Error::NotInited)?
+            .block_gas_limit;
+
+        let api = self.web3_api.as_mut().ok_or(Error::NoWeb3ApiConfiged)?;
+        let head = api.block_number().await?;
+
+        let mut committed = self.txpool.commit(&txhash, head, gas_limit);
+
+        let Some(slot) = self.txpool.slot(&txhash) else {
+            return Ok(None);
+        };
+
+        // Replacement takes slot of replaced one, it's recorded again under its hash
+        if committed.last().map(|(hash, _)| hash) != Some(&txhash) {
+            committed.push((txhash, slot));
+        }
+
+        let next_index = self.txpool.next_index();
+
+        self.journal(|j| {
+            committed
+                .iter()
+                .try_for_each(|(hash, slot)| j.commit(hash, slot))?;
+            j.set_sequence(next_index)
+        });
+
+        let mut preconf = None;
+
+        for (hash, slot) in committed {
+            let signed = self.sign(hash, slot).await?;
+
+            if hash == txhash {
+                preconf = signed;
+            }
+        }
+
+        Ok(preconf)
+    }
+
+    /// Write journal, failure is logged and mempool keeps working
+    fn journal(&mut self, f: impl FnOnce(&mut Journal) -> Result<()>) {
+        if let Some(journal) = &mut self.journal {
+            if let Err(e) = f(journal) {
+                log::error!("Failed to write journal: {}", e);
+            }
         }
     }
 
+    fn compact_journal(&mut self) {
+        if !self.replayed {
+            return;
+        }
+
+        let txs = self.txpool.all();
+        let sequence = self.txpool.next_index();
+        let committed = self.txpool.committed();
+
+        self.journal(|journal| journal.compact(&txs, sequence, &committed));
+        self.last_compact = Instant::now();
+    }
+
+    /// Put transactions in journal back into pool, they are checked again
+    async fn replay(&mut self) -> Result<()> {
+        let Some(journal) = &self.journal else {
+            return Ok(());
+        };
+
+        let (txs, sequence, committed) = journal.load()?;
+        let total = txs.len();
+
+        self.txpool.set_next_index(sequence);
+
+        let validator = self.validator.get().ok_or(Error::NotInited)?.clone();
+
+        let mut added = 0;
+
+        for mut tx in txs {
+            match validator.validate(&tx) {
+                Ok(sender) => tx.from = sender,
+                Err(reason) => {
+                    log::debug!("Drop transaction {:?} in journal: {}", tx.hash, reason);
+                    continue;
+                }
+            }
+
+            let hash = tx.hash;
+
+            match self.admit(tx).await? {
+                TxAdmission::Added => self.emit(TxEvent::Added(hash)),
+                TxAdmission::Replaced(old) => self.emit(TxEvent::Replaced { old, new: hash }),
+                _ => continue,
+            }
+
+            added += 1;
+        }
+
+        log::info!("Replay {} of {} transactions in journal", added, total);
+
+        // Committed order is kept, pre-confirmations are signed again the same
+        let mut restored = 0;
+
+        for (hash, slot) in committed {
+            if self.txpool.restore(hash, slot, validator.block_gas_limit) {
+                self.sign(hash, slot).await?;
+                restored += 1;
+            }
+        }
+
+        log::info!("Restore {} committed transactions", restored);
+
+        Ok(())
+    }
+
+    /// Replay journal if it's not replayed and retry is due, failure is retried later
+    async fn try_replay(&mut self) {
+        if self.replayed || time::Instant::now() < self.next_replay {
+            return;
+        }
+
+        match self.replay().await {
+            Ok(()) => {
+                self.replayed = true;
+                self.compact_journal();
+            }
+            Err(e) => {
+                log::warn!(
+                    "Failed to replay journal, retry in {:?}: {}",
+                    self.replay_retry,
+                    e
+                );
+                self.next_replay = time::Instant::now() + self.replay_retry;
+            }
+        }
+    }
+
+    /// Wait for next request, return none if it's time to retry replaying journal
+    async fn next_request(
+        &mut self,
+    ) -> Result<Option<(ApiRequest, Option<oneshot::Sender<ApiResponse>>)>> {
+        if self.replayed {
+            return Ok(Some(self.handler.recv().await?));
+        }
+
+        let request = Box::pin(self.handler.recv());
+        let tick = Box::pin(time::sleep_until(self.next_replay));
+
+        match select(request, tick).await {
+            Either::Left((r, _)) => Ok(Some(r?)),
+            Either::Right(_) => Ok(None),
+        }
+    }
+
+    /// Check balance and nonce of sender, return account nonce
+    async fn validate_state(&mut self, tx: &Transaction) -> Result<U256> {
+        let validator = self.validator.get().ok_or(Error::NotInited)?;
+
+        let api = self.web3_api.as_mut().ok_or(Error::NoWeb3ApiConfiged)?;
+
+        let block = Some(BlockId::Number(BlockNumber::Latest));
+
+        let balance = api.balance(tx.from, block).await?;
+        let nonce = api.transaction_count(tx.from, block).await?;
+
+        validator.validate_state(tx, balance, nonce)?;
+
+        Ok(nonce)
+    }
+
+    /// Check transaction and put it into pool
+    async fn admit(&mut self, tx: Transaction) -> Result<TxAdmission> {
+        let api = self.web3_api.as_mut().ok_or(Error::NoWeb3ApiConfiged)?;
+
+        if api.get_transaction(tx.hash).await?.is_some() {
+            return Ok(TxAdmission::AlreadyKnown);
+        }
+
+        let nonce = match self.validate_state(&tx).await {
+            Ok(nonce) => nonce,
+            Err(Error::Rejected(reason)) => return Ok(TxAdmission::Rejected(reason)),
+            Err(e) => return Err(e),
+        };
+
+        Ok(self.txpool.insert(tx, nonce))
+    }
+
     async fn _step(&mut self) -> Result<()> {
-        let (rep, resper) = self.handler.recv().await?;
+        self.try_replay().await;
+
+        let Some((rep, resper)) = self.next_request().await? else {
+            return Ok(());
+        };
 
         match rep {
             ApiRequest::Transaction(tx) => {
-                let hash = tx.hash();
+                let hash = tx.hash;
 
-                let api = self.web3_api.as_mut().ok_or(Error::NoWeb3ApiConfiged)?;
+                let record = self.journal.is_some().then(|| (*tx).clone());
 
-                let r = api.get_transaction(hash).await?;
+                let admission = self.admit(*tx).await?;
 
-                if r.is_none() {
-                    self.txindexer.insert(tx.hash, self.txpool.len());
-                    self.txpool.push(tx);
+                if let Some(tx) = record {
+                    match &admission {
+                        TxAdmission::Added => self.journal(|j| j.add(&tx)),
+                        TxAdmission::Replaced(old) => {
+                            self.journal(|j| j.remove(old).and_then(|_| j.add(&tx)))
+                        }
+                        _ => {}
+                    }
+                }
+
+                let preconf = match &admission {
+                    TxAdmission::Added => {
+                        self.emit(TxEvent::Added(hash));
+                        self.pre_confirm(hash).await?
+                    }
+                    TxAdmission::Replaced(old) => {
+                        self.emit(TxEvent::Replaced {
+                            old: *old,
+                            new: hash,
+                        });
+                        self.pre_confirm(hash).await?
+                    }
+                    _ => None,
+                };
+
+                log::debug!("Transaction {:?} admission: {:?}", hash, admission);
+
+                if let Some(resper) = resper {
+                    let resp = ApiResponse::Transaction(admission, preconf.map(Box::new));
+
+                    if resper.send(resp).is_err() {
+                        log::warn!("Caller of transaction {:?} is gone", hash);
+                    }
                 }
             }
             ApiRequest::TxHash(txhash) => {
-                if let Some(index) = self.txindexer.get(&txhash) {
-                    self.txpool.remove(*index);
+                let removed = self.txpool.confirm(&txhash);
+
+                self.journal(|j| removed.iter().try_for_each(|tx| j.remove(&tx.hash)));
+
+                for tx in &removed {
+                    if tx.hash == txhash {
+                        self.emit(TxEvent::Confirmed(tx.hash));
+                    } else {
+                        self.emit(TxEvent::Evicted(tx.hash, EvictReason::Outdated));
+                    }
                 }
+
+                log::debug!(
+                    "Confirm {:?}, remove {} transactions, {} left",
+                    txhash,
+                    removed.len(),
+                    self.txpool.len()
+                );
             }
             ApiRequest::GetAllTransaction => {
                 if let Some(resper) = resper {
                     resper
-                        .send(ApiResponse::GetAllTransaction(self.txpool.clone()))
+                        .send(ApiResponse::GetAllTransaction(self.txpool.executable()))
                         .map_err(|_| CallError::ChannelClosed)?;
                 } else {
                     log::warn!("Use send method to get txpool")
                 }
             }
+            ApiRequest::GetMetrics => {
+                if let Some(resper) = resper {
+                    resper
+                        .send(ApiResponse::Metrics(self.txpool.metrics()))
+                        .map_err(|_| CallError::ChannelClosed)?;
+                } else {
+                    log::warn!("Use send method to get metrics")
+                }
+            }
+            ApiRequest::GetContent => {
+                if let Some(resper) = resper {
+                    resper
+                        .send(ApiResponse::Content(Box::new(self.txpool.content())))
+                        .map_err(|_| CallError::ChannelClosed)?;
+                } else {
+                    log::warn!("Use send method to get txpool content")
+                }
+            }
+            ApiRequest::GetPreConfirmation(txhash) => {
+                if let Some(resper) = resper {
+                    let preconf = self.preconfs.get(&txhash).cloned().map(Box::new);
+
+                    resper
+                        .send(ApiResponse::PreConfirmation(preconf))
+                        .map_err(|_| CallError::ChannelClosed)?;
+                } else {
+                    log::warn!("Use send method to get pre-confirmation")
+                }
+            }
+            ApiRequest::GetTransaction(txhash) => {
+                if let Some(resper) = resper {
+                    let tx = self.txpool.get(&txhash).cloned().map(Box::new);
+
+                    resper
+                        .send(ApiResponse::PoolTransaction(tx))
+                        .map_err(|_| CallError::ChannelClosed)?;
+                } else {
+                    log::warn!("Use send method to get transaction")
+                }
+            }
+        }
+
+        self.txpool.expire(Instant::now());
+
+        let evicted = self.txpool.take_evicted();
+
+        if !evicted.is_empty() {
+            log::debug!("Drop {} expired or evicted transactions", evicted.len());
+
+            self.journal(|j| evicted.iter().try_for_each(|(tx, _)| j.remove(&tx.hash)));
+
+            for (tx, reason) in evicted {
+                self.emit(TxEvent::Evicted(tx.hash, reason));
+            }
+        }
+
+        if self.journal.is_some() && self.replayed && self.last_compact.elapsed() >= self.rejournal
+        {
+            self.compact_journal();
         }
 
         Ok(())
@@ -84,7 +489,13 @@ pub struct DevSequencerService(AsyncStepServiceWapper1<DevSequencer>);
 
 impl Default for DevSequencerService {
     fn default() -> Self {
-        Self(AsyncStepServiceWapper1::new(DevSequencer::new()))
+        Self::new(DevSequencerConfig::default())
+    }
+}
+
+impl DevSequencerService {
+    pub fn new(config: DevSequencerConfig) -> Self {
+        Self(AsyncStepServiceWapper1::new(DevSequencer::new(config)))
     }
 }
 
@@ -107,10 +518,314 @@ impl SequencerService for DevSequencerService {
     fn api(&self) -> DevSequencerApi {
         DevSequencerApi {
             caller: self.0.service0().caller.clone(),
+            events: self.0.service0().events.clone(),
+            validator: self.0.service0().validator.clone(),
         }
     }
 
     fn set_api(&mut self, web3_api: impl Web3Api) {
         self.0.service0_mut().web3_api = Some(Box::new(web3_api));
     }
+
+    fn init(&mut self, genesis: &ConsensusGenesis<Transaction>) {
+        if self.0.service0().validator.set(genesis.into()).is_err() {
+            log::warn!("Sequencer already inited");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::Path,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use ethers_core::{
+        types::{
+            transaction::eip2718::TypedTransaction, Block, BlockId, Bytes, SyncingStatus,
+            TransactionReceipt, TransactionRequest, H160, H256, U256,
+        },
+        utils::rlp::Rlp,
+    };
+    use ethers_signers::{LocalWallet, Signer};
+    use fluct_core::{Transaction, TxEvent, TxValidator, Web3Api, Web3Error};
+
+    use crate::{ApiRequest, DevSequencerConfig};
+
+    use super::DevSequencer;
+
+    /// Web3 api of empty chain, fails while `failures` is not zero
+    #[derive(Default)]
+    struct MockWeb3Api {
+        failures: Arc<AtomicUsize>,
+        /// Nonce of every account
+        nonce: u64,
+    }
+
+    impl MockWeb3Api {
+        fn check(&self) -> Result<(), Web3Error> {
+            match self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |v| v.checked_sub(1))
+            {
+                Ok(_) => Err(Web3Error::EmptyResponse),
+                Err(_) => Ok(()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Web3Api for MockWeb3Api {
+        async fn block_number(&mut self) -> Result<u64, Web3Error> {
+            self.check().map(|_| 0)
+        }
+
+        async fn chain_id(&mut self) -> Result<u64, Web3Error> {
+            self.check().map(|_| 901)
+        }
+
+        async fn get_block(
+            &mut self,
+            _block: BlockId,
+        ) -> Result<Option<Block<Transaction>>, Web3Error> {
+            self.check().map(|_| None)
+        }
+
+        async fn get_transaction(&mut self, _hash: H256) -> Result<Option<Transaction>, Web3Error> {
+            self.check().map(|_| None)
+        }
+
+        async fn get_transaction_receipt(
+            &mut self,
+            _hash: H256,
+        ) -> Result<Option<TransactionReceipt>, Web3Error> {
+            self.check().map(|_| None)
+        }
+
+        async fn syncing(&mut self) -> Result<SyncingStatus, Web3Error> {
+            self.check().map(|_| SyncingStatus::IsFalse)
+        }
+
+        async fn balance(
+            &mut self,
+            _address: H160,
+            _block: Option<BlockId>,
+        ) -> Result<U256, Web3Error> {
+            self.check().map(|_| U256::MAX)
+        }
+
+        async fn transaction_count(
+            &mut self,
+            _address: H160,
+            _block: Option<BlockId>,
+        ) -> Result<U256, Web3Error> {
+            self.check().map(|_| self.nonce.into())
+        }
+
+        async fn code(
+            &mut self,
+            _address: H160,
+            _block: Option<BlockId>,
+        ) -> Result<Bytes, Web3Error> {
+            self.check().map(|_| Bytes::new())
+        }
+
+        async fn storage_at(
+            &mut self,
+            _address: H160,
+            _index: H256,
+            _block: Option<BlockId>,
+        ) -> Result<H256, Web3Error> {
+            self.check().map(|_| H256::zero())
+        }
+    }
+
+    fn signed_tx(nonce: u64) -> Transaction {
+        let wallet: LocalWallet =
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                .parse()
+                .unwrap();
+
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(wallet.address())
+            .nonce(nonce)
+            .gas(21000)
+            .gas_price(10)
+            .chain_id(901)
+            .into();
+
+        let from = wallet.address();
+        let signature = wallet
+            .with_chain_id(901u64)
+            .sign_transaction_sync(&tx)
+            .unwrap();
+
+        let mut tx: Transaction = Rlp::new(&tx.rlp_signed(&signature)).as_val().unwrap();
+        tx.from = from;
+        tx
+    }
+
+    fn new_sequencer(journal: &Path, failures: usize) -> DevSequencer {
+        let signer = LocalWallet::from_bytes(H256::from_low_u64_be(1).as_bytes()).unwrap();
+
+        let mut sequencer = DevSequencer::new(DevSequencerConfig {
+            journal: Some(journal.to_path_buf()),
+            rejournal: Duration::ZERO,
+            replay_retry: Duration::from_millis(10),
+            signer: Some(signer),
+            ..Default::default()
+        });
+
+        sequencer.web3_api = Some(Box::new(MockWeb3Api {
+            failures: Arc::new(AtomicUsize::new(failures)),
+            ..Default::default()
+        }));
+
+        let _ = sequencer.validator.set(TxValidator {
+            chain_id: 901,
+            block_gas_limit: 42_000u64.into(),
+        });
+
+        sequencer
+    }
+
+    #[test]
+    fn test_replay_retry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transactions.rlp");
+
+        let txs = [signed_tx(0), signed_tx(1)];
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+
+        rt.block_on(async {
+            let mut sequencer = new_sequencer(&path, 0);
+
+            for tx in &txs {
+                let request = ApiRequest::Transaction(Box::new(tx.clone()));
+                sequencer.caller.send(request).unwrap();
+                sequencer._step().await.unwrap();
+            }
+
+            assert_eq!(sequencer.txpool.len(), 2);
+            drop(sequencer);
+
+            // restart, first replay fails
+            let mut sequencer = new_sequencer(&path, 1);
+            let mut events = sequencer.events.subscribe();
+
+            sequencer
+                .caller
+                .send(ApiRequest::TxHash(H256::zero()))
+                .unwrap();
+            sequencer._step().await.unwrap();
+
+            assert!(!sequencer.replayed);
+            assert_eq!(sequencer.txpool.len(), 0);
+            assert_eq!(
+                sequencer.journal.as_ref().unwrap().load().unwrap().0.len(),
+                2
+            );
+
+            // wait for retry
+            sequencer._step().await.unwrap();
+
+            sequencer
+                .caller
+                .send(ApiRequest::TxHash(H256::zero()))
+                .unwrap();
+            sequencer._step().await.unwrap();
+
+            assert!(sequencer.replayed);
+            assert_eq!(sequencer.txpool.len(), 2);
+            assert_eq!(
+                sequencer.journal.as_ref().unwrap().load().unwrap().0.len(),
+                2
+            );
+
+            for tx in &txs {
+                assert_eq!(events.try_recv().unwrap(), TxEvent::Added(tx.hash));
+            }
+        });
+    }
+
+    #[test]
+    fn test_pre_confirm() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transactions.rlp");
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+
+        rt.block_on(async {
+            let mut sequencer = new_sequencer(&path, 0);
+            let signer = sequencer.signer.clone().unwrap().address();
+
+            // nonce 3 is queued after a gap, it has no position
+            for nonce in [0, 1, 2, 3, 4] {
+                let tx = signed_tx(if nonce == 2 { 5 } else { nonce });
+                let request = ApiRequest::Transaction(Box::new(tx));
+                sequencer.caller.send(request).unwrap();
+                sequencer._step().await.unwrap();
+            }
+
+            let preconf = |sequencer: &DevSequencer, nonce: u64| {
+                sequencer.preconfs.get(&signed_tx(nonce).hash).cloned()
+            };
+
+            // two transfers fill a block
+            let p0 = preconf(&sequencer, 0).unwrap();
+            assert_eq!((p0.index, p0.block_number), (0, 1));
+            assert_eq!(p0.chain_id, 901);
+            assert!(p0.verify(signer).is_ok());
+
+            let p1 = preconf(&sequencer, 1).unwrap();
+            assert_eq!((p1.index, p1.block_number), (1, 1));
+            assert!(preconf(&sequencer, 5).is_none());
+            assert!(preconf(&sequencer, 3).is_none());
+
+            let request = ApiRequest::TxHash(signed_tx(0).hash);
+            sequencer.caller.send(request).unwrap();
+            sequencer._step().await.unwrap();
+            drop(sequencer);
+
+            // index keeps counting after restart, nonce 0 is executed
+            let mut sequencer = new_sequencer(&path, 0);
+            sequencer.web3_api = Some(Box::new(MockWeb3Api {
+                nonce: 1,
+                ..Default::default()
+            }));
+
+            let request = ApiRequest::Transaction(Box::new(signed_tx(2)));
+            sequencer.caller.send(request).unwrap();
+            sequencer._step().await.unwrap();
+
+            // nonce 1 keeps its position and signature, nonce 2 comes after it
+            assert_eq!(preconf(&sequencer, 1), Some(p1));
+            assert!(preconf(&sequencer, 0).is_none());
+
+            let p2 = preconf(&sequencer, 2).unwrap();
+            assert_eq!((p2.index, p2.block_number), (2, 1));
+            assert_eq!(sequencer.txpool.next_index(), 3);
+
+            let nonces: Vec<_> = sequencer
+                .txpool
+                .executable()
+                .iter()
+                .map(|tx| tx.nonce.as_u64())
+                .collect();
+            assert_eq!(nonces, vec![1, 2, 3, 4, 5]);
+        });
+    }
 }
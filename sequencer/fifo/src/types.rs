@@ -1,12 +1,21 @@
-use ethers_core::types::H256;
-use fluct_core::Transaction;
+use ethers_core::types::{TxpoolContent, H256};
+use fluct_core::{MempoolMetrics, PreConfirmation, Transaction, TxAdmission};
 
 pub enum ApiRequest {
-    Transaction(Transaction),
+    Transaction(Box<Transaction>),
     TxHash(H256),
     GetAllTransaction,
+    GetMetrics,
+    GetContent,
+    GetTransaction(H256),
+    GetPreConfirmation(H256),
 }
 
 pub enum ApiResponse {
+    Transaction(TxAdmission, Option<Box<PreConfirmation>>),
     GetAllTransaction(Vec<Transaction>),
+    Metrics(MempoolMetrics),
+    Content(Box<TxpoolContent>),
+    PoolTransaction(Option<Box<Transaction>>),
+    PreConfirmation(Option<Box<PreConfirmation>>),
 }
@@ -0,0 +1,24 @@
+use fluct_core::MempoolLimits;
+
+/// Default percent of fee bump to replace a transaction
+pub const DEFAULT_PRICE_BUMP: u64 = 10;
+
+/// Config of gas price sequencer
+#[derive(Debug, Clone)]
+pub struct GasPriceSequencerConfig {
+    /// Percent of both fee caps a transaction must bump to replace
+    /// another one with same sender and nonce
+    pub price_bump: u64,
+
+    /// Size limits and time to live of mempool
+    pub limits: MempoolLimits,
+}
+
+impl Default for GasPriceSequencerConfig {
+    fn default() -> Self {
+        Self {
+            price_bump: DEFAULT_PRICE_BUMP,
+            limits: MempoolLimits::default(),
+        }
+    }
+}
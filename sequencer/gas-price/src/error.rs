@@ -0,0 +1,40 @@
+use fluct_core::{RejectReason, Web3Error};
+use fluct_service::{CallError, StepError};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    CallError(#[from] CallError),
+
+    #[error("No web3 api configed")]
+    NoWeb3ApiConfiged,
+
+    #[error(transparent)]
+    Web3Error(#[from] Web3Error),
+
+    #[error("Unexpected response from sequencer")]
+    UnexpectedResponse,
+
+    #[error("Sequencer not inited")]
+    NotInited,
+
+    #[error("Transaction rejected: {0}")]
+    Rejected(#[from] RejectReason),
+}
+
+impl StepError for Error {
+    fn is_exit(&self) -> bool {
+        match self {
+            Self::CallError(CallError::ChannelClosed) => true,
+            Self::CallError(CallError::SenderReject) => false,
+            Self::NoWeb3ApiConfiged => true,
+            Self::Web3Error(_) => false,
+            Self::UnexpectedResponse => false,
+            Self::NotInited => false,
+            Self::Rejected(_) => false,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
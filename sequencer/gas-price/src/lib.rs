@@ -0,0 +1,17 @@
+mod sequencer;
+pub use sequencer::*;
+
+mod pool;
+pub use pool::*;
+
+mod api;
+pub use api::*;
+
+mod config;
+pub use config::*;
+
+mod error;
+pub use error::*;
+
+mod types;
+pub use types::*;
@@ -0,0 +1,649 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap, HashMap},
+    time::Instant,
+};
+
+use ethers_core::types::{H160, H256, U256};
+use fluct_core::{transaction_utils, EvictReason, MempoolLimits, MempoolMetrics, Transaction};
+
+use crate::DEFAULT_PRICE_BUMP;
+
+/// Tip paid to block producer per gas under base fee
+///
+/// EIP-1559 transaction pays `min(max_priority_fee_per_gas, max_fee_per_gas - base_fee)`,
+/// legacy transaction pays `gas_price - base_fee`. Return `None` if fee cap is below base fee.
+pub fn effective_tip(tx: &Transaction, base_fee: U256) -> Option<U256> {
+    let (max_fee, max_tip) = transaction_utils::fee_caps(tx);
+
+    max_fee.checked_sub(base_fee).map(|v| v.min(max_tip))
+}
+
+/// Result of inserting transaction into pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Insert {
+    /// Transaction is added, cheaper ones may be evicted to make room
+    Added,
+    /// Same transaction in pool
+    AlreadyKnown,
+    /// Transaction replaced another one of sender with same nonce, hold hash of replaced one
+    Replaced(H256),
+    /// Another transaction of sender with same nonce in pool, and fee isn't bumped enough
+    NonceTaken,
+    /// Nonce is below account nonce of sender
+    NonceTooLow(U256),
+    /// Pool is full and transaction is not better than cheapest ones
+    Underpriced,
+    /// Sender has too many transactions in pool
+    SenderSlotsFull(usize),
+}
+
+#[derive(Debug)]
+struct PoolTx {
+    /// Arrival counter, break ties of same tip
+    seq: u64,
+    /// Bytes of RLP encoded transaction
+    size: usize,
+    added: Instant,
+    tx: Transaction,
+}
+
+/// Transactions of one sender
+#[derive(Debug)]
+struct SenderQueue {
+    /// Account nonce, only transactions from it are executable
+    nonce: U256,
+    txs: BTreeMap<U256, PoolTx>,
+}
+
+/// Transaction pool ordered by effective tip
+///
+/// Transactions of one sender are ordered by nonce. When pool is full, only the highest
+/// nonce transaction of a sender can be evicted, so nonce sequences keep contiguous.
+#[derive(Debug)]
+pub struct GasPricePool {
+    price_bump: u64,
+    limits: MempoolLimits,
+    metrics: MempoolMetrics,
+    seq: u64,
+    senders: HashMap<H160, SenderQueue>,
+    txindexer: HashMap<H256, (H160, U256)>,
+    /// Transactions evicted, expired or outdated, not taken yet
+    evicted: Vec<(Transaction, EvictReason)>,
+}
+
+impl Default for GasPricePool {
+    fn default() -> Self {
+        Self::new(DEFAULT_PRICE_BUMP, MempoolLimits::default())
+    }
+}
+
+impl GasPricePool {
+    /// Create pool, replacement must bump both fee caps by `price_bump` percent
+    pub fn new(price_bump: u64, limits: MempoolLimits) -> Self {
+        Self {
+            price_bump,
+            limits,
+            metrics: MempoolMetrics::default(),
+            seq: 0,
+            senders: HashMap::new(),
+            txindexer: HashMap::new(),
+            evicted: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.txindexer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.txindexer.is_empty()
+    }
+
+    pub fn contains(&self, hash: &H256) -> bool {
+        self.txindexer.contains_key(hash)
+    }
+
+    pub fn metrics(&self) -> MempoolMetrics {
+        MempoolMetrics {
+            count: self.len(),
+            ..self.metrics.clone()
+        }
+    }
+
+    /// Cheapest transactions to evict so that a transaction of `size` bytes fits in limits
+    ///
+    /// Return `None` if they are not cheaper than `tip`, or not enough. Transaction being
+    /// replaced is not counted, and its sender is never evicted for the replacement.
+    fn victims(
+        &self,
+        size: usize,
+        tip: Option<U256>,
+        base_fee: U256,
+        replacing: Option<&PoolTx>,
+    ) -> Option<Vec<H256>> {
+        let mut count = self.len() + 1;
+        let mut bytes = self.metrics.bytes + size;
+
+        if let Some(v) = replacing {
+            count -= 1;
+            bytes -= v.size;
+        }
+
+        // Count of evicted tail transactions of each sender
+        let mut taken: HashMap<H160, usize> = HashMap::new();
+        let mut victims = Vec::new();
+
+        while count > self.limits.max_count || bytes > self.limits.max_bytes {
+            let (cheapest, from, v) = self
+                .senders
+                .iter()
+                .filter(|(from, _)| replacing.is_none_or(|v| v.tx.from != **from))
+                .filter_map(|(from, sender)| {
+                    let n = taken.get(from).copied().unwrap_or_default();
+                    let v = sender.txs.values().nth_back(n)?;
+
+                    Some(((effective_tip(&v.tx, base_fee), v.tx.hash), *from, v))
+                })
+                .min_by(|a, b| a.0.cmp(&b.0))?;
+
+            if tip <= cheapest.0 {
+                return None;
+            }
+
+            *taken.entry(from).or_default() += 1;
+
+            count -= 1;
+            bytes -= v.size;
+            victims.push(v.tx.hash);
+        }
+
+        Some(victims)
+    }
+
+    /// Insert transaction, `base_fee` is used to price transactions when pool is full
+    ///
+    /// `account_nonce` is nonce of sender in latest state, transactions of sender below
+    /// it are dropped.
+    pub fn insert(&mut self, tx: Transaction, account_nonce: U256, base_fee: U256) -> Insert {
+        if self.contains(&tx.hash) {
+            return Insert::AlreadyKnown;
+        }
+
+        for tx in self.advance(tx.from, account_nonce) {
+            self.evicted.push((tx, EvictReason::Outdated));
+        }
+
+        let mut replacing = None;
+
+        if let Some(sender) = self.senders.get(&tx.from) {
+            if tx.nonce < sender.nonce {
+                return Insert::NonceTooLow(sender.nonce);
+            }
+
+            replacing = sender.txs.get(&tx.nonce);
+
+            if let Some(old) = replacing {
+                if !transaction_utils::price_bumped(&old.tx, &tx, self.price_bump) {
+                    return Insert::NonceTaken;
+                }
+            } else if sender.txs.len() >= self.limits.max_per_sender {
+                self.metrics.rejected_sender_full += 1;
+
+                return Insert::SenderSlotsFull(self.limits.max_per_sender);
+            }
+        }
+
+        let size = tx.rlp().len();
+        let tip = effective_tip(&tx, base_fee);
+
+        let Some(victims) = self.victims(size, tip, base_fee, replacing) else {
+            self.metrics.rejected_pool_full += 1;
+
+            return Insert::Underpriced;
+        };
+
+        let replaced = replacing.map(|v| v.tx.hash);
+
+        for hash in victims {
+            log::debug!("Txpool is full, evict transaction: {:?}", hash);
+
+            if let Some(tx) = self.remove(&hash) {
+                self.metrics.evicted += 1;
+                self.evicted.push((tx, EvictReason::PoolFull));
+            }
+        }
+
+        if let Some(old) = &replaced {
+            self.remove(old);
+        }
+
+        self.seq += 1;
+        self.metrics.bytes += size;
+
+        self.txindexer.insert(tx.hash, (tx.from, tx.nonce));
+
+        let sender = self.senders.entry(tx.from).or_insert_with(|| SenderQueue {
+            nonce: account_nonce,
+            txs: BTreeMap::new(),
+        });
+
+        sender.txs.insert(
+            tx.nonce,
+            PoolTx {
+                seq: self.seq,
+                size,
+                added: Instant::now(),
+                tx,
+            },
+        );
+
+        match replaced {
+            Some(old) => Insert::Replaced(old),
+            None => Insert::Added,
+        }
+    }
+
+    /// Raise account nonce of sender, return dropped transactions below it
+    fn advance(&mut self, from: H160, nonce: U256) -> Vec<Transaction> {
+        let Some(sender) = self.senders.get_mut(&from) else {
+            return Vec::new();
+        };
+
+        if nonce <= sender.nonce {
+            return Vec::new();
+        }
+
+        sender.nonce = nonce;
+
+        let keep = sender.txs.split_off(&nonce);
+        let outdated = std::mem::replace(&mut sender.txs, keep);
+
+        if sender.txs.is_empty() {
+            self.senders.remove(&from);
+        }
+
+        outdated
+            .into_values()
+            .map(|v| {
+                self.txindexer.remove(&v.tx.hash);
+                self.metrics.bytes -= v.size;
+                v.tx
+            })
+            .collect()
+    }
+
+    /// Remove transaction by hash, account nonce of sender is kept
+    pub fn remove(&mut self, hash: &H256) -> Option<Transaction> {
+        let (from, nonce) = self.txindexer.remove(hash)?;
+
+        let sender = self.senders.get_mut(&from)?;
+        let v = sender.txs.remove(&nonce)?;
+
+        if sender.txs.is_empty() {
+            self.senders.remove(&from);
+        }
+
+        self.metrics.bytes -= v.size;
+
+        Some(v.tx)
+    }
+
+    /// Transaction is executed, remove it with lower nonces of sender
+    pub fn confirm(&mut self, hash: &H256) -> Vec<Transaction> {
+        match self.txindexer.get(hash) {
+            Some((from, nonce)) => self.advance(*from, *nonce + 1),
+            None => Vec::new(),
+        }
+    }
+
+    /// Drop transactions live longer than time to live
+    pub fn expire(&mut self, now: Instant) {
+        let expired: Vec<_> = self
+            .senders
+            .values()
+            .flat_map(|sender| sender.txs.values())
+            .filter(|v| now.saturating_duration_since(v.added) >= self.limits.ttl)
+            .map(|v| v.tx.hash)
+            .collect();
+
+        for hash in expired {
+            log::debug!("Transaction expired: {:?}", hash);
+
+            if let Some(tx) = self.remove(&hash) {
+                self.metrics.expired += 1;
+                self.evicted.push((tx, EvictReason::Expired));
+            }
+        }
+    }
+
+    /// Take transactions evicted, expired or outdated since last call
+    pub fn take_evicted(&mut self) -> Vec<(Transaction, EvictReason)> {
+        std::mem::take(&mut self.evicted)
+    }
+
+    /// Transactions in executable order under base fee
+    ///
+    /// Highest tip goes first, but a transaction never goes before lower nonce of
+    /// same sender. Sequence of sender starts at its account nonce, and stops at a
+    /// nonce gap or a transaction whose fee cap is below base fee.
+    pub fn ordered(&self, base_fee: U256) -> Vec<Transaction> {
+        let mut sequences = Vec::with_capacity(self.senders.len());
+
+        for sender in self.senders.values() {
+            let mut sequence = Vec::new();
+            let mut next_nonce = sender.nonce;
+
+            for (nonce, v) in &sender.txs {
+                if *nonce != next_nonce {
+                    break;
+                }
+
+                let Some(tip) = effective_tip(&v.tx, base_fee) else {
+                    break;
+                };
+
+                sequence.push((tip, v.seq, &v.tx));
+                next_nonce = *nonce + 1;
+            }
+
+            sequences.push(sequence);
+        }
+
+        let mut heads = BinaryHeap::new();
+
+        for (i, sequence) in sequences.iter().enumerate() {
+            if let Some((tip, seq, _)) = sequence.first() {
+                heads.push((*tip, Reverse(*seq), i, 0));
+            }
+        }
+
+        let mut res = Vec::with_capacity(self.len());
+
+        while let Some((_, _, i, pos)) = heads.pop() {
+            let sequence = &sequences[i];
+
+            res.push(sequence[pos].2.clone());
+
+            if let Some((tip, seq, _)) = sequence.get(pos + 1) {
+                heads.push((*tip, Reverse(*seq), i, pos + 1));
+            }
+        }
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use ethers_core::types::{H160, H256, U256};
+    use fluct_core::{EvictReason, MempoolLimits, Transaction};
+
+    use crate::{effective_tip, GasPricePool, Insert, DEFAULT_PRICE_BUMP};
+
+    fn tx(id: u64, from: u64, nonce: u64, max_fee: u64, max_tip: u64) -> Transaction {
+        Transaction {
+            hash: H256::from_low_u64_be(id),
+            from: H160::from_low_u64_be(from),
+            nonce: nonce.into(),
+            max_fee_per_gas: Some(max_fee.into()),
+            max_priority_fee_per_gas: Some(max_tip.into()),
+            ..Default::default()
+        }
+    }
+
+    fn hashes(txs: &[Transaction]) -> Vec<u64> {
+        txs.iter().map(|tx| tx.hash.to_low_u64_be()).collect()
+    }
+
+    #[test]
+    fn test_effective_tip() {
+        let base_fee = U256::from(10);
+
+        assert_eq!(effective_tip(&tx(1, 1, 0, 20, 5), base_fee), Some(5.into()));
+        assert_eq!(effective_tip(&tx(1, 1, 0, 12, 5), base_fee), Some(2.into()));
+        assert_eq!(effective_tip(&tx(1, 1, 0, 9, 5), base_fee), None);
+
+        let legacy = Transaction {
+            gas_price: Some(15.into()),
+            ..Default::default()
+        };
+        assert_eq!(effective_tip(&legacy, base_fee), Some(5.into()));
+    }
+
+    #[test]
+    fn test_ordered() {
+        let mut pool = GasPricePool::default();
+        let base_fee = U256::from(10);
+
+        // sender 1 pays little on nonce 0, much on nonce 1
+        assert_eq!(
+            pool.insert(tx(1, 1, 0, 100, 1), U256::zero(), base_fee),
+            Insert::Added
+        );
+        assert_eq!(
+            pool.insert(tx(2, 1, 1, 100, 9), U256::zero(), base_fee),
+            Insert::Added
+        );
+        assert_eq!(
+            pool.insert(tx(3, 2, 0, 100, 5), U256::zero(), base_fee),
+            Insert::Added
+        );
+        assert_eq!(
+            pool.insert(tx(4, 2, 1, 100, 5), U256::zero(), base_fee),
+            Insert::Added
+        );
+        // nonce gap of sender 3
+        assert_eq!(
+            pool.insert(tx(5, 3, 0, 100, 3), U256::zero(), base_fee),
+            Insert::Added
+        );
+        assert_eq!(
+            pool.insert(tx(6, 3, 2, 100, 8), U256::zero(), base_fee),
+            Insert::Added
+        );
+        // fee cap below base fee
+        assert_eq!(
+            pool.insert(tx(7, 4, 0, 5, 5), U256::zero(), base_fee),
+            Insert::Added
+        );
+
+        assert_eq!(
+            pool.insert(tx(1, 1, 0, 100, 1), U256::zero(), base_fee),
+            Insert::AlreadyKnown
+        );
+        assert_eq!(
+            pool.insert(tx(8, 1, 0, 100, 1), U256::zero(), base_fee),
+            Insert::NonceTaken
+        );
+
+        assert_eq!(hashes(&pool.ordered(base_fee)), vec![3, 4, 5, 1, 2]);
+
+        pool.confirm(&H256::from_low_u64_be(3));
+        assert_eq!(hashes(&pool.ordered(base_fee)), vec![4, 5, 1, 2]);
+        assert_eq!(pool.len(), 6);
+
+        // removed without execution, later nonces of sender are not executable
+        pool.remove(&H256::from_low_u64_be(1));
+        assert_eq!(hashes(&pool.ordered(base_fee)), vec![4, 5]);
+    }
+
+    #[test]
+    fn test_replace() {
+        let mut pool = GasPricePool::new(
+            DEFAULT_PRICE_BUMP,
+            MempoolLimits {
+                max_count: 2,
+                max_per_sender: 1,
+                ..Default::default()
+            },
+        );
+        let base_fee = U256::zero();
+
+        pool.insert(tx(1, 1, 0, 100, 10), U256::zero(), base_fee);
+        pool.insert(tx(2, 2, 0, 100, 1), U256::zero(), base_fee);
+
+        // fee is bumped, but not enough
+        assert_eq!(
+            pool.insert(tx(3, 1, 0, 105, 20), U256::zero(), base_fee),
+            Insert::NonceTaken
+        );
+
+        // sender slots and pool are full, replacement takes slot of old one
+        assert_eq!(
+            pool.insert(tx(4, 1, 0, 110, 11), U256::zero(), base_fee),
+            Insert::Replaced(H256::from_low_u64_be(1))
+        );
+        assert_eq!(pool.len(), 2);
+        assert_eq!(hashes(&pool.ordered(base_fee)), vec![4, 2]);
+        assert!(pool.take_evicted().is_empty());
+    }
+
+    #[test]
+    fn test_evict() {
+        let mut pool = GasPricePool::new(
+            DEFAULT_PRICE_BUMP,
+            MempoolLimits {
+                max_count: 2,
+                ..Default::default()
+            },
+        );
+        let base_fee = U256::zero();
+
+        pool.insert(tx(1, 1, 0, 100, 1), U256::zero(), base_fee);
+        pool.insert(tx(2, 1, 1, 100, 9), U256::zero(), base_fee);
+
+        assert_eq!(
+            pool.insert(tx(3, 2, 0, 100, 9), U256::zero(), base_fee),
+            Insert::Underpriced
+        );
+
+        // only tail of sender can be evicted
+        assert_eq!(
+            pool.insert(tx(4, 2, 0, 100, 10), U256::zero(), base_fee),
+            Insert::Added
+        );
+        assert_eq!(pool.len(), 2);
+        assert_eq!(hashes(&pool.ordered(base_fee)), vec![4, 1]);
+
+        let evicted = pool.take_evicted();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].0.hash, H256::from_low_u64_be(2));
+        assert_eq!(evicted[0].1, EvictReason::PoolFull);
+        assert_eq!(pool.metrics().evicted, 1);
+        assert_eq!(pool.metrics().rejected_pool_full, 1);
+    }
+
+    #[test]
+    fn test_limits() {
+        let size = tx(1, 1, 0, 100, 1).rlp().len();
+
+        let mut pool = GasPricePool::new(
+            DEFAULT_PRICE_BUMP,
+            MempoolLimits {
+                max_bytes: size * 3,
+                max_per_sender: 2,
+                ttl: Duration::from_secs(60),
+                ..Default::default()
+            },
+        );
+        let base_fee = U256::zero();
+
+        assert_eq!(
+            pool.insert(tx(1, 1, 0, 100, 1), U256::zero(), base_fee),
+            Insert::Added
+        );
+        assert_eq!(
+            pool.insert(tx(2, 1, 1, 100, 2), U256::zero(), base_fee),
+            Insert::Added
+        );
+        assert_eq!(
+            pool.insert(tx(3, 1, 2, 100, 3), U256::zero(), base_fee),
+            Insert::SenderSlotsFull(2)
+        );
+        assert_eq!(
+            pool.insert(tx(4, 2, 0, 100, 1), U256::zero(), base_fee),
+            Insert::Added
+        );
+        assert_eq!(pool.metrics().bytes, size * 3);
+
+        // two tails are cheaper, but only one needs to go
+        assert_eq!(
+            pool.insert(tx(5, 3, 0, 100, 3), U256::zero(), base_fee),
+            Insert::Added
+        );
+        assert_eq!(hashes(&pool.ordered(base_fee)), vec![5, 1, 2]);
+        assert!(pool.metrics().bytes <= size * 3);
+
+        // outdated transactions are dropped when state moves ahead
+        assert_eq!(
+            pool.insert(tx(6, 1, 3, 100, 1), 2.into(), base_fee),
+            Insert::Added
+        );
+
+        pool.expire(Instant::now() + Duration::from_secs(61));
+        assert!(pool.is_empty());
+        assert_eq!(pool.metrics().bytes, 0);
+
+        let reasons: Vec<_> = pool
+            .take_evicted()
+            .into_iter()
+            .map(|(tx, reason)| (tx.hash.to_low_u64_be(), reason))
+            .collect();
+        assert_eq!(reasons.len(), 5);
+        assert_eq!(reasons[0], (4, EvictReason::PoolFull));
+        assert_eq!(reasons[1], (1, EvictReason::Outdated));
+        assert_eq!(reasons[2], (2, EvictReason::Outdated));
+        assert!(reasons[3..].iter().all(|(_, r)| *r == EvictReason::Expired));
+
+        let metrics = pool.metrics();
+        assert_eq!(metrics.expired, 2);
+        assert_eq!(metrics.evicted, 1);
+        assert_eq!(metrics.rejected_sender_full, 1);
+    }
+
+    #[test]
+    fn test_account_nonce() {
+        let mut pool = GasPricePool::default();
+        let base_fee = U256::zero();
+        let nonce = U256::from(3);
+
+        assert_eq!(
+            pool.insert(tx(1, 1, 5, 100, 1), nonce, base_fee),
+            Insert::Added
+        );
+        assert!(pool.ordered(base_fee).is_empty());
+
+        assert_eq!(
+            pool.insert(tx(2, 1, 3, 100, 1), nonce, base_fee),
+            Insert::Added
+        );
+        assert_eq!(hashes(&pool.ordered(base_fee)), vec![2]);
+
+        assert_eq!(
+            pool.insert(tx(3, 1, 4, 100, 1), nonce, base_fee),
+            Insert::Added
+        );
+        assert_eq!(hashes(&pool.ordered(base_fee)), vec![2, 3, 1]);
+
+        // nonce 3 and 4 are executed
+        assert_eq!(hashes(&pool.confirm(&H256::from_low_u64_be(3))), vec![2, 3]);
+        assert_eq!(hashes(&pool.ordered(base_fee)), vec![1]);
+
+        assert_eq!(
+            pool.insert(tx(4, 1, 4, 100, 1), nonce, base_fee),
+            Insert::NonceTooLow(5.into())
+        );
+
+        // state moves ahead of pool
+        assert_eq!(
+            pool.insert(tx(5, 1, 7, 100, 1), 6.into(), base_fee),
+            Insert::Added
+        );
+        assert!(!pool.contains(&H256::from_low_u64_be(1)));
+        assert!(pool.ordered(base_fee).is_empty());
+        assert_eq!(pool.len(), 1);
+    }
+}
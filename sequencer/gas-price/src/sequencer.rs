@@ -0,0 +1,453 @@
+use std::{
+    io,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    ApiRequest, ApiResponse, Error, GasPricePool, GasPriceSequencerApi, GasPriceSequencerConfig,
+    Insert, Result,
+};
+use async_trait::async_trait;
+use ethers_core::types::{BlockId, BlockNumber, U256};
+use fluct_core::{
+    transaction_utils, ConsensusGenesis, EvictReason, RejectReason, SequencerService, Service,
+    Transaction, TxAdmission, TxEvent, TxValidator, Web3Api,
+};
+use fluct_service::{
+    local_rpc, AsyncStepService, AsyncStepServiceWapper1, CallError, Caller, Hander,
+};
+use futures_util::future::{select, Either};
+use tokio::{
+    sync::{broadcast, oneshot},
+    time,
+};
+
+/// Events kept for slow subscribers
+const EVENT_CAPACITY: usize = 1024;
+
+/// Interval to drop expired transactions when no request comes
+const EXPIRE_INTERVAL: Duration = Duration::from_secs(1);
+
+struct GasPriceSequencer {
+    handler: Hander<ApiRequest, ApiResponse>,
+    caller: Caller<ApiRequest, ApiResponse>,
+    txpool: GasPricePool,
+    events: broadcast::Sender<TxEvent>,
+    /// Base fee of latest block, updated when building block
+    base_fee: U256,
+    validator: Arc<OnceLock<TxValidator>>,
+    web3_api: Option<Box<dyn Web3Api>>,
+}
+
+impl GasPriceSequencer {
+    pub fn new(config: GasPriceSequencerConfig) -> Self {
+        let (handler, caller) = local_rpc();
+
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+
+        Self {
+            handler,
+            caller,
+            txpool: GasPricePool::new(config.price_bump, config.limits),
+            events,
+            base_fee: U256::zero(),
+            validator: Arc::new(OnceLock::new()),
+            web3_api: None,
+        }
+    }
+
+    /// Send event, it's dropped if no subscriber
+    fn emit(&self, event: TxEvent) {
+        let _ = self.events.send(event);
+    }
+
+    async fn update_base_fee(&mut self) -> Result<()> {
+        let api = self.web3_api.as_mut().ok_or(Error::NoWeb3ApiConfiged)?;
+
+        let block = api.get_block(BlockId::Number(BlockNumber::Latest)).await?;
+
+        if let Some(base_fee) = block.and_then(|b| b.base_fee_per_gas) {
+            self.base_fee = base_fee;
+        }
+
+        Ok(())
+    }
+
+    /// Check balance and nonce of sender, return account nonce
+    async fn validate_state(&mut self, tx: &Transaction) -> Result<U256> {
+        let validator = self.validator.get().ok_or(Error::NotInited)?;
+
+        let api = self.web3_api.as_mut().ok_or(Error::NoWeb3ApiConfiged)?;
+
+        let block = Some(BlockId::Number(BlockNumber::Latest));
+
+        let balance = api.balance(tx.from, block).await?;
+        let nonce = api.transaction_count(tx.from, block).await?;
+
+        validator.validate_state(tx, balance, nonce)?;
+
+        Ok(nonce)
+    }
+
+    /// Check transaction and put it into pool
+    async fn admit(&mut self, tx: Transaction) -> Result<TxAdmission> {
+        let api = self.web3_api.as_mut().ok_or(Error::NoWeb3ApiConfiged)?;
+
+        if api.get_transaction(tx.hash).await?.is_some() {
+            return Ok(TxAdmission::AlreadyKnown);
+        }
+
+        let account_nonce = match self.validate_state(&tx).await {
+            Ok(v) => v,
+            Err(Error::Rejected(reason)) => return Ok(TxAdmission::Rejected(reason)),
+            Err(e) => return Err(e),
+        };
+
+        let nonce = tx.nonce;
+
+        let admission = match self.txpool.insert(tx, account_nonce, self.base_fee) {
+            Insert::Added => TxAdmission::Added,
+            Insert::Replaced(old) => TxAdmission::Replaced(old),
+            Insert::AlreadyKnown => TxAdmission::AlreadyKnown,
+            Insert::NonceTaken => TxAdmission::Rejected(RejectReason::ReplacementUnderpriced),
+            Insert::NonceTooLow(n) => TxAdmission::Rejected(RejectReason::NonceTooLow(n, nonce)),
+            Insert::Underpriced => TxAdmission::Rejected(RejectReason::Underpriced),
+            Insert::SenderSlotsFull(n) => TxAdmission::Rejected(RejectReason::SenderSlotsFull(n)),
+        };
+
+        Ok(admission)
+    }
+
+    /// Wait for next request, return none if it's time to expire transactions
+    async fn next_request(
+        &mut self,
+    ) -> Result<Option<(ApiRequest, Option<oneshot::Sender<ApiResponse>>)>> {
+        let request = Box::pin(self.handler.recv());
+        let tick = Box::pin(time::sleep(EXPIRE_INTERVAL));
+
+        match select(request, tick).await {
+            Either::Left((r, _)) => Ok(Some(r?)),
+            Either::Right(_) => Ok(None),
+        }
+    }
+
+    async fn handle(
+        &mut self,
+        rep: ApiRequest,
+        resper: Option<oneshot::Sender<ApiResponse>>,
+    ) -> Result<()> {
+        match rep {
+            ApiRequest::Transaction(tx) => {
+                let hash = tx.hash;
+
+                let admission = self.admit(*tx).await?;
+
+                match &admission {
+                    TxAdmission::Added => self.emit(TxEvent::Added(hash)),
+                    TxAdmission::Replaced(old) => self.emit(TxEvent::Replaced {
+                        old: *old,
+                        new: hash,
+                    }),
+                    _ => {}
+                }
+
+                log::debug!("Transaction {:?} admission: {:?}", hash, admission);
+
+                if let Some(resper) = resper {
+                    if resper.send(ApiResponse::Transaction(admission)).is_err() {
+                        log::warn!("Caller of transaction {:?} is gone", hash);
+                    }
+                }
+            }
+            ApiRequest::TxHash(txhash) => {
+                for tx in self.txpool.confirm(&txhash) {
+                    if tx.hash == txhash {
+                        self.emit(TxEvent::Confirmed(tx.hash));
+                    } else {
+                        self.emit(TxEvent::Evicted(tx.hash, EvictReason::Outdated));
+                    }
+                }
+            }
+            ApiRequest::GetAllTransaction => {
+                if let Some(resper) = resper {
+                    self.update_base_fee().await?;
+
+                    resper
+                        .send(ApiResponse::GetAllTransaction(
+                            self.txpool.ordered(self.base_fee),
+                        ))
+                        .map_err(|_| CallError::ChannelClosed)?;
+                } else {
+                    log::warn!("Use send method to get txpool")
+                }
+            }
+            ApiRequest::GetTransactionsForBlock(gas_limit, byte_limit, base_fee) => {
+                if let Some(resper) = resper {
+                    self.update_base_fee().await?;
+
+                    // Base fee of next block if known, otherwise of head block
+                    let base_fee = if base_fee.is_zero() {
+                        self.base_fee
+                    } else {
+                        base_fee
+                    };
+
+                    let txs = transaction_utils::select_for_block(
+                        self.txpool.ordered(base_fee),
+                        gas_limit,
+                        byte_limit,
+                        base_fee,
+                    );
+
+                    resper
+                        .send(ApiResponse::GetAllTransaction(txs))
+                        .map_err(|_| CallError::ChannelClosed)?;
+                } else {
+                    log::warn!("Use send method to get txpool")
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn _step(&mut self) -> Result<()> {
+        if let Some((rep, resper)) = self.next_request().await? {
+            self.handle(rep, resper).await?;
+        }
+
+        self.txpool.expire(Instant::now());
+
+        for (tx, reason) in self.txpool.take_evicted() {
+            self.emit(TxEvent::Evicted(tx.hash, reason));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncStepService for GasPriceSequencer {
+    type Error = Error;
+
+    async fn step(&mut self) -> Result<()> {
+        self._step().await
+    }
+}
+
+pub struct GasPriceSequencerService(AsyncStepServiceWapper1<GasPriceSequencer>);
+
+impl Default for GasPriceSequencerService {
+    fn default() -> Self {
+        Self::new(GasPriceSequencerConfig::default())
+    }
+}
+
+impl GasPriceSequencerService {
+    pub fn new(config: GasPriceSequencerConfig) -> Self {
+        Self(AsyncStepServiceWapper1::new(GasPriceSequencer::new(config)))
+    }
+}
+
+impl Service for GasPriceSequencerService {
+    type Error = io::Error;
+
+    fn start(&mut self) -> std::result::Result<(), Self::Error> {
+        self.0.start()
+    }
+
+    fn stop(&mut self) -> std::result::Result<(), Self::Error> {
+        self.0.stop()
+    }
+}
+
+#[async_trait]
+impl SequencerService for GasPriceSequencerService {
+    type Api = GasPriceSequencerApi;
+
+    fn api(&self) -> GasPriceSequencerApi {
+        GasPriceSequencerApi {
+            caller: self.0.service0().caller.clone(),
+            events: self.0.service0().events.clone(),
+            validator: self.0.service0().validator.clone(),
+        }
+    }
+
+    fn set_api(&mut self, web3_api: impl Web3Api) {
+        self.0.service0_mut().web3_api = Some(Box::new(web3_api));
+    }
+
+    fn init(&mut self, genesis: &ConsensusGenesis<Transaction>) {
+        if self.0.service0().validator.set(genesis.into()).is_err() {
+            log::warn!("Sequencer already inited");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use ethers_core::types::{
+        Block, BlockId, Bytes, SyncingStatus, TransactionReceipt, H160, H256, U256,
+    };
+    use fluct_core::{
+        EvictReason, MempoolLimits, Transaction, TxEvent, TxValidator, Web3Api, Web3Error,
+    };
+    use fluct_service::StepError;
+
+    use crate::{ApiRequest, GasPriceSequencerConfig};
+
+    use super::GasPriceSequencer;
+
+    /// Web3 api of empty chain, fails while `failures` is not zero
+    #[derive(Default)]
+    struct MockWeb3Api {
+        failures: Arc<AtomicUsize>,
+    }
+
+    impl MockWeb3Api {
+        fn check(&self) -> Result<(), Web3Error> {
+            match self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |v| v.checked_sub(1))
+            {
+                Ok(_) => Err(Web3Error::EmptyResponse),
+                Err(_) => Ok(()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Web3Api for MockWeb3Api {
+        async fn block_number(&mut self) -> Result<u64, Web3Error> {
+            self.check().map(|_| 0)
+        }
+
+        async fn chain_id(&mut self) -> Result<u64, Web3Error> {
+            self.check().map(|_| 901)
+        }
+
+        async fn get_block(
+            &mut self,
+            _block: BlockId,
+        ) -> Result<Option<Block<Transaction>>, Web3Error> {
+            self.check().map(|_| None)
+        }
+
+        async fn get_transaction(&mut self, _hash: H256) -> Result<Option<Transaction>, Web3Error> {
+            self.check().map(|_| None)
+        }
+
+        async fn get_transaction_receipt(
+            &mut self,
+            _hash: H256,
+        ) -> Result<Option<TransactionReceipt>, Web3Error> {
+            self.check().map(|_| None)
+        }
+
+        async fn syncing(&mut self) -> Result<SyncingStatus, Web3Error> {
+            self.check().map(|_| SyncingStatus::IsFalse)
+        }
+
+        async fn balance(
+            &mut self,
+            _address: H160,
+            _block: Option<BlockId>,
+        ) -> Result<U256, Web3Error> {
+            self.check().map(|_| U256::MAX)
+        }
+
+        async fn transaction_count(
+            &mut self,
+            _address: H160,
+            _block: Option<BlockId>,
+        ) -> Result<U256, Web3Error> {
+            self.check().map(|_| U256::zero())
+        }
+
+        async fn code(
+            &mut self,
+            _address: H160,
+            _block: Option<BlockId>,
+        ) -> Result<Bytes, Web3Error> {
+            self.check().map(|_| Bytes::new())
+        }
+
+        async fn storage_at(
+            &mut self,
+            _address: H160,
+            _index: H256,
+            _block: Option<BlockId>,
+        ) -> Result<H256, Web3Error> {
+            self.check().map(|_| H256::zero())
+        }
+    }
+
+    #[test]
+    fn test_idle_expire() {
+        let mut sequencer = GasPriceSequencer::new(GasPriceSequencerConfig {
+            limits: MempoolLimits {
+                ttl: Duration::from_millis(100),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        sequencer.web3_api = Some(Box::new(MockWeb3Api {
+            failures: Arc::new(AtomicUsize::new(1)),
+        }));
+
+        let _ = sequencer.validator.set(TxValidator {
+            chain_id: 901,
+            block_gas_limit: 30_000_000u64.into(),
+        });
+
+        let tx = Transaction {
+            hash: H256::from_low_u64_be(1),
+            from: H160::from_low_u64_be(2),
+            gas: 21000.into(),
+            gas_price: Some(10.into()),
+            ..Default::default()
+        };
+
+        let mut events = sequencer.events.subscribe();
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+
+        rt.block_on(async {
+            let request = || ApiRequest::Transaction(Box::new(tx.clone()));
+
+            // state of sender is unavailable, sequencer keeps running
+            sequencer.caller.send(request()).unwrap();
+            let err = sequencer._step().await.unwrap_err();
+            assert!(!err.is_exit());
+            assert!(sequencer.txpool.is_empty());
+
+            sequencer.caller.send(request()).unwrap();
+            sequencer._step().await.unwrap();
+            assert_eq!(sequencer.txpool.len(), 1);
+
+            // no request comes, transaction expires anyway
+            sequencer._step().await.unwrap();
+            assert!(sequencer.txpool.is_empty());
+        });
+
+        assert_eq!(events.try_recv().unwrap(), TxEvent::Added(tx.hash));
+        assert_eq!(
+            events.try_recv().unwrap(),
+            TxEvent::Evicted(tx.hash, EvictReason::Expired)
+        );
+    }
+}
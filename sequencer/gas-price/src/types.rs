@@ -0,0 +1,15 @@
+use ethers_core::types::{H256, U256};
+use fluct_core::{Transaction, TxAdmission};
+
+pub enum ApiRequest {
+    Transaction(Box<Transaction>),
+    TxHash(H256),
+    GetAllTransaction,
+    /// Gas limit, byte limit and base fee of block
+    GetTransactionsForBlock(U256, u64, U256),
+}
+
+pub enum ApiResponse {
+    Transaction(TxAdmission),
+    GetAllTransaction(Vec<Transaction>),
+}
@@ -0,0 +1,89 @@
+use std::sync::{Arc, OnceLock};
+
+use async_trait::async_trait;
+use ethers_core::types::H256;
+use fluct_core::{PreConfirmation, SequencerApi, Transaction, TxAdmission, TxEvent, TxValidator};
+use fluct_service::Caller;
+use futures_core::stream::BoxStream;
+use tokio::sync::broadcast;
+use tokio_stream::{wrappers::BroadcastStream, StreamExt};
+
+use crate::{ApiRequest, ApiResponse, Error, Result};
+
+#[derive(Clone)]
+pub struct SharedSequencerApi {
+    pub(crate) caller: Caller<ApiRequest, ApiResponse>,
+    pub(crate) events: broadcast::Sender<TxEvent>,
+    pub(crate) validator: Arc<OnceLock<TxValidator>>,
+}
+
+#[async_trait]
+impl SequencerApi for SharedSequencerApi {
+    type Error = Error;
+
+    async fn broadcast_tx(&self, tx: Transaction) -> Result<Option<PreConfirmation>> {
+        let mut tx = tx;
+
+        let validator = self.validator.get().ok_or(Error::NotInited)?;
+        tx.from = validator.validate(&tx)?;
+
+        let resp = self
+            .caller
+            .call(ApiRequest::Transaction(Box::new(tx)))
+            .await?;
+
+        match resp {
+            ApiResponse::Transaction(TxAdmission::Rejected(reason)) => Err(reason.into()),
+            ApiResponse::Transaction(_) => Ok(None),
+            _ => Err(Error::UnexpectedResponse),
+        }
+    }
+
+    async fn submit_tx(&self, tx: Transaction) -> Result<TxAdmission> {
+        let mut tx = tx;
+
+        let validator = self.validator.get().ok_or(Error::NotInited)?;
+
+        match validator.validate(&tx) {
+            Ok(sender) => tx.from = sender,
+            Err(reason) => return Ok(TxAdmission::Rejected(reason)),
+        }
+
+        let resp = self
+            .caller
+            .call(ApiRequest::Transaction(Box::new(tx)))
+            .await?;
+
+        match resp {
+            ApiResponse::Transaction(v) => Ok(v),
+            _ => Err(Error::UnexpectedResponse),
+        }
+    }
+
+    fn comfirm_tx(&self, txhash: H256) -> Result<()> {
+        self.caller.send(ApiRequest::TxHash(txhash))?;
+
+        Ok(())
+    }
+
+    async fn txs(&self) -> Result<Vec<Transaction>> {
+        let resp = self.caller.call(ApiRequest::GetAllTransaction).await?;
+
+        match resp {
+            ApiResponse::GetAllTransaction(v) => Ok(v),
+            _ => Err(Error::UnexpectedResponse),
+        }
+    }
+
+    fn subscribe(&self) -> BoxStream<'static, TxEvent> {
+        let stream = BroadcastStream::new(self.events.subscribe()).filter_map(|r| match r {
+            Ok(event) => Some(event),
+            Err(e) => {
+                log::warn!("Subscriber of mempool events lagged: {}", e);
+                None
+            }
+        });
+
+        Box::pin(stream)
+    }
+}
@@ -0,0 +1,78 @@
+use ethers_core::types::H160;
+use ethers_signers::{LocalWallet, Signer};
+
+use crate::{Batch, Error, Result, SignedBatch};
+
+/// Backend to agree order of transactions
+pub trait OrderingBackend: Send + 'static {
+    /// Can local node propose batch of height
+    fn is_proposer(&self, height: u64) -> bool;
+
+    /// Sign batch proposed by local node
+    fn sign(&self, batch: Batch) -> Result<SignedBatch>;
+
+    /// Check batch is proposed by the node allowed at its height
+    fn verify(&self, batch: &SignedBatch) -> Result<()>;
+}
+
+/// Sequencers take turns to propose batch by height
+///
+/// There is no view change, sequence stalls if leader of a height is offline.
+pub struct RoundRobin {
+    chain_id: u64,
+    sequencers: Vec<H160>,
+    signer: Option<LocalWallet>,
+}
+
+impl RoundRobin {
+    /// Create backend of sequencers on chain, node only follows batches without signer
+    pub fn new(chain_id: u64, sequencers: Vec<H160>, signer: Option<LocalWallet>) -> Result<Self> {
+        if sequencers.is_empty() {
+            return Err(Error::NoSequencer);
+        }
+
+        if let Some(signer) = &signer {
+            if !sequencers.contains(&signer.address()) {
+                return Err(Error::NotSequencer(signer.address()));
+            }
+        }
+
+        Ok(Self {
+            chain_id,
+            sequencers,
+            signer,
+        })
+    }
+
+    /// Leader of height
+    pub fn leader(&self, height: u64) -> H160 {
+        self.sequencers[(height % self.sequencers.len() as u64) as usize]
+    }
+}
+
+impl OrderingBackend for RoundRobin {
+    fn is_proposer(&self, height: u64) -> bool {
+        self.signer
+            .as_ref()
+            .is_some_and(|s| s.address() == self.leader(height))
+    }
+
+    fn sign(&self, batch: Batch) -> Result<SignedBatch> {
+        let signer = self.signer.as_ref().ok_or(Error::NoSigner)?;
+
+        let signature = signer.sign_hash(batch.digest(self.chain_id))?;
+
+        Ok(SignedBatch { batch, signature })
+    }
+
+    fn verify(&self, batch: &SignedBatch) -> Result<()> {
+        let height = batch.batch.height;
+        let leader = self.leader(height);
+
+        if batch.signer(self.chain_id) != Some(leader) {
+            return Err(Error::NotSignedByLeader(height, leader));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use ethers_core::{
+    types::{Signature, H160, H256},
+    utils::{keccak256, rlp::RlpStream},
+};
+use fluct_core::Transaction;
+
+/// Domain of batch digest, a signature can't be replayed as another message
+pub const BATCH_DOMAIN: &[u8] = b"fluct-batch-v1";
+
+/// Transactions ordered at a height
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Batch {
+    pub height: u64,
+    pub txs: Vec<Transaction>,
+}
+
+impl Batch {
+    /// Hash to sign, `keccak256(rlp([BATCH_DOMAIN, chain_id, height, [tx_hash, ...]]))`
+    pub fn digest(&self, chain_id: u64) -> H256 {
+        let mut s = RlpStream::new_list(4);
+        s.append(&BATCH_DOMAIN);
+        s.append(&chain_id);
+        s.append(&self.height);
+        s.begin_list(self.txs.len());
+
+        for tx in &self.txs {
+            s.append(&tx.hash);
+        }
+
+        keccak256(s.out()).into()
+    }
+
+    /// Is hash of each transaction match its content
+    pub fn is_intact(&self) -> bool {
+        self.txs.iter().all(|tx| tx.hash == tx.hash())
+    }
+}
+
+/// Batch signed by its proposer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedBatch {
+    pub batch: Batch,
+    pub signature: Signature,
+}
+
+impl SignedBatch {
+    /// Address signed the batch on chain
+    pub fn signer(&self, chain_id: u64) -> Option<H160> {
+        self.signature.recover(self.batch.digest(chain_id)).ok()
+    }
+}
@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+use fluct_core::MempoolLimits;
+
+/// Default interval to propose batch
+pub const DEFAULT_BATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default max count of transactions in a batch
+pub const DEFAULT_MAX_BATCH_TXS: usize = 1024;
+
+/// Config of shared sequencer
+#[derive(Debug, Clone)]
+pub struct SharedSequencerConfig {
+    /// Interval to propose batch when local node is proposer, empty batch is proposed
+    /// to pass the turn
+    pub batch_interval: Duration,
+
+    /// Max count of transactions in a batch
+    pub max_batch_txs: usize,
+
+    /// Size limits and time to live of local transactions waiting for a batch
+    pub limits: MempoolLimits,
+}
+
+impl Default for SharedSequencerConfig {
+    fn default() -> Self {
+        Self {
+            batch_interval: DEFAULT_BATCH_INTERVAL,
+            max_batch_txs: DEFAULT_MAX_BATCH_TXS,
+            limits: MempoolLimits::default(),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use ethers_core::types::H160;
+use ethers_signers::WalletError;
+use fluct_core::{RejectReason, Web3Error};
+use fluct_service::{CallError, StepError};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    CallError(#[from] CallError),
+
+    #[error("No web3 api configed")]
+    NoWeb3ApiConfiged,
+
+    #[error(transparent)]
+    Web3Error(#[from] Web3Error),
+
+    #[error("Unexpected response from sequencer")]
+    UnexpectedResponse,
+
+    #[error("Sequencer not inited")]
+    NotInited,
+
+    #[error("Transaction rejected: {0}")]
+    Rejected(#[from] RejectReason),
+
+    #[error("No sequencer configed")]
+    NoSequencer,
+
+    #[error("Signer {0:?} is not a sequencer")]
+    NotSequencer(H160),
+
+    #[error("No signer configed, node can't propose batch")]
+    NoSigner,
+
+    #[error(transparent)]
+    WalletError(#[from] WalletError),
+
+    #[error("Batch of height {0} is not signed by leader {1:?}")]
+    NotSignedByLeader(u64, H160),
+
+    #[error("Batch of height {0} has transaction not match its hash")]
+    BrokenBatch(u64),
+
+    #[error("Batch transport closed")]
+    TransportClosed,
+}
+
+impl StepError for Error {
+    fn is_exit(&self) -> bool {
+        match self {
+            Self::CallError(CallError::ChannelClosed) => true,
+            Self::CallError(CallError::SenderReject) => false,
+            Self::NoWeb3ApiConfiged => true,
+            Self::Web3Error(_) => false,
+            Self::UnexpectedResponse => false,
+            Self::NotInited => false,
+            Self::Rejected(_) => false,
+            Self::NoSequencer => true,
+            Self::NotSequencer(_) => true,
+            Self::NoSigner => false,
+            Self::WalletError(_) => false,
+            Self::NotSignedByLeader(_, _) => false,
+            Self::BrokenBatch(_) => false,
+            Self::TransportClosed => true,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
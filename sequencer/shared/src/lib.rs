@@ -0,0 +1,32 @@
+//! Sequencer with transaction order agreed by an ordering backend
+//!
+//! Each sequencer node collects transactions locally, a batch of transactions is
+//! proposed by the node chosen by backend, and committed by all nodes once verified.
+//! Only committed transactions are returned as sequence.
+
+mod batch;
+pub use batch::*;
+
+mod backend;
+pub use backend::*;
+
+mod transport;
+pub use transport::*;
+
+mod order;
+pub use order::*;
+
+mod config;
+pub use config::*;
+
+mod sequencer;
+pub use sequencer::*;
+
+mod api;
+pub use api::*;
+
+mod error;
+pub use error::*;
+
+mod types;
+pub use types::*;
@@ -0,0 +1,544 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    time::Instant,
+};
+
+use ethers_core::types::H256;
+use fluct_core::{EvictReason, MempoolLimits, RejectReason, Transaction, TxAdmission, TxValidator};
+
+use crate::{Batch, Error, OrderingBackend, Result, SignedBatch};
+
+struct PendingTx {
+    tx: Transaction,
+    /// Bytes of RLP encoded transaction
+    size: usize,
+    added: Instant,
+}
+
+/// Order of transactions agreed by backend
+///
+/// Local transactions wait in `pending` until a batch including them is committed.
+/// Batches are committed by height, later ones are held until the gap is filled.
+///
+/// Limits only apply to `pending`, committed transactions are agreed by all nodes
+/// and only dropped when confirmed, outdated or invalid on account state.
+pub struct SharedOrder<B> {
+    backend: B,
+    max_batch_txs: usize,
+    limits: MempoolLimits,
+    /// Next height to commit
+    height: u64,
+    pending: Vec<PendingTx>,
+    /// Bytes of pending transactions
+    pending_bytes: usize,
+    /// Hash of transactions in pending or committed
+    known: HashSet<H256>,
+    /// Hash of committed transactions
+    committed_hashes: HashSet<H256>,
+    /// Verified batches received ahead of height
+    future: BTreeMap<u64, Batch>,
+    /// Committed transactions not confirmed yet
+    committed: Vec<Transaction>,
+    /// Hash of transactions first known from batch of other node, not taken yet
+    added: Vec<H256>,
+}
+
+impl<B> SharedOrder<B>
+where
+    B: OrderingBackend,
+{
+    pub fn new(backend: B, max_batch_txs: usize, limits: MempoolLimits) -> Self {
+        Self {
+            backend,
+            max_batch_txs,
+            limits,
+            height: 0,
+            pending: Vec::new(),
+            pending_bytes: 0,
+            known: HashSet::new(),
+            committed_hashes: HashSet::new(),
+            future: BTreeMap::new(),
+            committed: Vec::new(),
+            added: Vec::new(),
+        }
+    }
+
+    /// Next height to commit
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// Add local transaction, it waits for a batch
+    pub fn add(&mut self, tx: Transaction) -> TxAdmission {
+        if self.known.contains(&tx.hash) {
+            return TxAdmission::AlreadyKnown;
+        }
+
+        let used = self.pending.iter().filter(|v| v.tx.from == tx.from).count();
+
+        if used >= self.limits.max_per_sender {
+            let reason = RejectReason::SenderSlotsFull(self.limits.max_per_sender);
+            return TxAdmission::Rejected(reason);
+        }
+
+        let size = tx.rlp().len();
+
+        if self.pending.len() >= self.limits.max_count
+            || self.pending_bytes + size > self.limits.max_bytes
+        {
+            return TxAdmission::Rejected(RejectReason::PoolFull);
+        }
+
+        self.known.insert(tx.hash);
+        self.pending_bytes += size;
+        self.pending.push(PendingTx {
+            tx,
+            size,
+            added: Instant::now(),
+        });
+
+        TxAdmission::Added
+    }
+
+    /// Remove pending transactions matching `f`, return removed ones
+    fn remove_pending(&mut self, f: impl Fn(&PendingTx) -> bool) -> Vec<Transaction> {
+        let (removed, kept) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition::<Vec<_>, _>(f);
+
+        self.pending = kept;
+
+        removed
+            .into_iter()
+            .map(|v| {
+                self.pending_bytes -= v.size;
+                v.tx
+            })
+            .collect()
+    }
+
+    /// Drop pending transactions live longer than time to live
+    pub fn expire(&mut self, now: Instant) -> Vec<Transaction> {
+        let ttl = self.limits.ttl;
+
+        let expired = self.remove_pending(|v| now.saturating_duration_since(v.added) >= ttl);
+
+        for tx in &expired {
+            self.known.remove(&tx.hash);
+        }
+
+        expired
+    }
+
+    /// Propose batch of next height if local node is proposer
+    ///
+    /// Batch is committed locally, it should be sent to other nodes.
+    pub fn propose(&mut self) -> Result<Option<SignedBatch>> {
+        if !self.backend.is_proposer(self.height) {
+            return Ok(None);
+        }
+
+        let len = self.pending.len().min(self.max_batch_txs);
+
+        let batch = Batch {
+            height: self.height,
+            txs: self.pending[..len].iter().map(|v| v.tx.clone()).collect(),
+        };
+
+        let batch = self.backend.sign(batch)?;
+
+        self.commit(batch.batch.clone());
+
+        Ok(Some(batch))
+    }
+
+    /// Receive batch from other node, commit it when all lower heights are committed
+    ///
+    /// Transactions failing `validator` are dropped, same on every node, sender of
+    /// others is set to the recovered one.
+    pub fn receive(&mut self, batch: SignedBatch, validator: &TxValidator) -> Result<()> {
+        let height = batch.batch.height;
+
+        if height < self.height || self.future.contains_key(&height) {
+            return Ok(());
+        }
+
+        self.backend.verify(&batch)?;
+
+        if !batch.batch.is_intact() {
+            return Err(Error::BrokenBatch(height));
+        }
+
+        let mut batch = batch.batch;
+
+        batch.txs.retain_mut(|tx| match validator.validate(tx) {
+            Ok(sender) => {
+                tx.from = sender;
+                true
+            }
+            Err(reason) => {
+                log::warn!(
+                    "Drop transaction {:?} of batch {}: {}",
+                    tx.hash,
+                    height,
+                    reason
+                );
+                false
+            }
+        });
+
+        self.future.insert(height, batch);
+
+        while let Some(batch) = self.future.remove(&self.height) {
+            self.commit(batch);
+        }
+
+        Ok(())
+    }
+
+    fn commit(&mut self, batch: Batch) {
+        log::debug!(
+            "Commit batch {} with {} transactions",
+            batch.height,
+            batch.txs.len()
+        );
+
+        let hashes: HashSet<_> = batch.txs.iter().map(|tx| tx.hash).collect();
+
+        self.remove_pending(|v| hashes.contains(&v.tx.hash));
+
+        for tx in batch.txs {
+            if self.committed_hashes.insert(tx.hash) {
+                if self.known.insert(tx.hash) {
+                    self.added.push(tx.hash);
+                }
+
+                self.committed.push(tx);
+            }
+        }
+
+        self.height = batch.height + 1;
+    }
+
+    /// Take hash of transactions first known from batch of other node since last call
+    pub fn take_added(&mut self) -> Vec<H256> {
+        std::mem::take(&mut self.added)
+    }
+
+    /// Committed transactions in order
+    pub fn committed(&self) -> &[Transaction] {
+        &self.committed
+    }
+
+    /// Drop committed transactions `f` gives a reason, return dropped ones
+    pub fn drop_committed(
+        &mut self,
+        f: impl Fn(&Transaction) -> Option<EvictReason>,
+    ) -> Vec<(Transaction, EvictReason)> {
+        let mut dropped = Vec::new();
+
+        for tx in std::mem::take(&mut self.committed) {
+            match f(&tx) {
+                Some(reason) => {
+                    self.committed_hashes.remove(&tx.hash);
+                    self.known.remove(&tx.hash);
+                    dropped.push((tx, reason));
+                }
+                None => self.committed.push(tx),
+            }
+        }
+
+        dropped
+    }
+
+    /// Transaction is executed, remove it and transactions of its sender with
+    /// nonce not above, return removed ones, empty if it's unknown
+    pub fn confirm(&mut self, txhash: &H256) -> Vec<Transaction> {
+        let confirmed = self
+            .committed
+            .iter()
+            .chain(self.pending.iter().map(|v| &v.tx))
+            .find(|tx| &tx.hash == txhash);
+
+        let Some((from, nonce)) = confirmed.map(|tx| (tx.from, tx.nonce)) else {
+            return Vec::new();
+        };
+
+        let outdated = |tx: &Transaction| tx.from == from && tx.nonce <= nonce;
+
+        let mut removed = self.remove_pending(|v| outdated(&v.tx));
+
+        for tx in &removed {
+            self.known.remove(&tx.hash);
+        }
+
+        removed.extend(
+            self.drop_committed(|tx| outdated(tx).then_some(EvictReason::Outdated))
+                .into_iter()
+                .map(|(tx, _)| tx),
+        );
+
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use ethers_core::{
+        types::{transaction::eip2718::TypedTransaction, TransactionRequest, H256},
+        utils::rlp::Rlp,
+    };
+    use ethers_signers::{LocalWallet, Signer};
+    use fluct_core::{
+        EvictReason, MempoolLimits, RejectReason, Transaction, TxAdmission, TxValidator,
+    };
+
+    use crate::{
+        Batch, BatchTransport, Error, LocalNetwork, OrderingBackend, RoundRobin, SharedOrder,
+    };
+
+    fn wallet(i: u64) -> LocalWallet {
+        LocalWallet::from_bytes(H256::from_low_u64_be(i).as_bytes()).unwrap()
+    }
+
+    const CHAIN_ID: u64 = 901;
+
+    fn validator() -> TxValidator {
+        TxValidator {
+            chain_id: CHAIN_ID,
+            block_gas_limit: 30_000_000u64.into(),
+        }
+    }
+
+    /// Transaction signed by wallet `key`, sender is recovered
+    fn signed_tx(key: u64, nonce: u64, chain_id: u64) -> Transaction {
+        let wallet = wallet(key).with_chain_id(chain_id);
+
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(wallet.address())
+            .nonce(nonce)
+            .gas(21000)
+            .gas_price(1)
+            .chain_id(chain_id)
+            .into();
+
+        let signature = wallet.sign_transaction_sync(&tx).unwrap();
+        let mut tx: Transaction = Rlp::new(&tx.rlp_signed(&signature)).as_val().unwrap();
+        tx.from = wallet.address();
+        tx
+    }
+
+    fn tx(nonce: u64) -> Transaction {
+        signed_tx(100, nonce, CHAIN_ID)
+    }
+
+    fn hashes(txs: &[Transaction]) -> Vec<H256> {
+        txs.iter().map(|tx| tx.hash).collect()
+    }
+
+    #[test]
+    fn test_agreement() {
+        let wallets: Vec<_> = (1..=3).map(wallet).collect();
+        let sequencers: Vec<_> = wallets.iter().map(|w| w.address()).collect();
+
+        let network = LocalNetwork::new(16);
+
+        let mut nodes: Vec<_> = wallets
+            .into_iter()
+            .map(|w| {
+                let backend = RoundRobin::new(CHAIN_ID, sequencers.clone(), Some(w)).unwrap();
+                (
+                    SharedOrder::new(backend, 2, MempoolLimits::default()),
+                    network.join(),
+                )
+            })
+            .collect();
+
+        let backend = RoundRobin::new(CHAIN_ID, sequencers, None).unwrap();
+        nodes.push((
+            SharedOrder::new(backend, 2, MempoolLimits::default()),
+            network.join(),
+        ));
+
+        for (i, (order, _)) in nodes.iter_mut().enumerate().take(3) {
+            for k in 0..3 {
+                order.add(tx(i as u64 * 10 + k));
+            }
+        }
+        nodes[0].0.add(tx(100));
+        nodes[1].0.add(tx(100));
+        assert_eq!(nodes[1].0.add(tx(100)), TxAdmission::AlreadyKnown);
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        rt.block_on(async {
+            for height in 0..9 {
+                for (order, transport) in nodes.iter_mut() {
+                    if let Some(batch) = order.propose().unwrap() {
+                        assert_eq!(batch.batch.height, height);
+                        transport.broadcast(batch).unwrap();
+                    }
+                }
+
+                for (order, transport) in nodes.iter_mut() {
+                    let batch = transport.recv().await.unwrap();
+                    order.receive(batch, &validator()).unwrap();
+                    assert_eq!(order.height(), height + 1);
+                }
+            }
+        });
+
+        let committed = hashes(nodes[0].0.committed());
+        assert_eq!(committed.len(), 10);
+
+        for (order, _) in &nodes {
+            assert_eq!(hashes(order.committed()), committed);
+            assert!(order.pending.is_empty());
+        }
+
+        nodes[3].0.confirm(&committed[0]);
+        assert_eq!(hashes(nodes[3].0.committed()), committed[1..]);
+    }
+
+    #[test]
+    fn test_receive() {
+        let wallets: Vec<_> = (1..=2).map(wallet).collect();
+        let sequencers: Vec<_> = wallets.iter().map(|w| w.address()).collect();
+
+        let leaders: Vec<_> = wallets
+            .iter()
+            .map(|w| RoundRobin::new(CHAIN_ID, sequencers.clone(), Some(w.clone())).unwrap())
+            .collect();
+
+        let batch = |height: u64, nonce: u64| Batch {
+            height,
+            txs: vec![tx(nonce)],
+        };
+
+        let backend = RoundRobin::new(CHAIN_ID, sequencers.clone(), None).unwrap();
+        let mut follower = SharedOrder::new(backend, 8, MempoolLimits::default());
+
+        let forged = leaders[1].sign(batch(0, 1)).unwrap();
+        assert!(matches!(
+            follower.receive(forged, &validator()),
+            Err(Error::NotSignedByLeader(0, _))
+        ));
+
+        // signature of another chain can't be replayed
+        let other_chain =
+            RoundRobin::new(CHAIN_ID + 1, sequencers.clone(), Some(wallets[0].clone()));
+        let replayed = other_chain.unwrap().sign(batch(0, 1)).unwrap();
+        assert!(matches!(
+            follower.receive(replayed, &validator()),
+            Err(Error::NotSignedByLeader(0, _))
+        ));
+
+        let mut broken = leaders[0].sign(batch(0, 1)).unwrap();
+        broken.batch.txs[0].nonce = 2.into();
+        assert!(follower.receive(broken, &validator()).is_err());
+
+        // later height is held until the gap is filled
+        follower
+            .receive(leaders[1].sign(batch(1, 2)).unwrap(), &validator())
+            .unwrap();
+        assert_eq!(follower.height(), 0);
+
+        follower
+            .receive(leaders[0].sign(batch(0, 1)).unwrap(), &validator())
+            .unwrap();
+        assert_eq!(follower.height(), 2);
+        assert_eq!(hashes(follower.committed()), vec![tx(1).hash, tx(2).hash]);
+        assert_eq!(follower.take_added(), vec![tx(1).hash, tx(2).hash]);
+
+        // invalid transaction is dropped, sender is recovered
+        let mut unsigned = tx(4);
+        unsigned.from = Default::default();
+
+        let batch = Batch {
+            height: 2,
+            txs: vec![signed_tx(100, 3, CHAIN_ID + 1), unsigned],
+        };
+        follower
+            .receive(leaders[0].sign(batch).unwrap(), &validator())
+            .unwrap();
+        assert_eq!(follower.height(), 3);
+        assert_eq!(follower.committed()[2], tx(4));
+        assert_eq!(follower.committed().len(), 3);
+    }
+
+    #[test]
+    fn test_confirm() {
+        let wallet = wallet(1);
+        let backend = RoundRobin::new(CHAIN_ID, vec![wallet.address()], Some(wallet)).unwrap();
+
+        let mut order = SharedOrder::new(backend, 2, MempoolLimits::default());
+
+        for nonce in 0..4 {
+            order.add(tx(nonce));
+        }
+        order.add(signed_tx(101, 0, CHAIN_ID));
+        order.propose().unwrap().unwrap();
+
+        // executed transaction outdates lower nonces of sender, committed or not
+        let removed = order.confirm(&tx(2).hash);
+        assert_eq!(hashes(&removed), vec![tx(2).hash, tx(0).hash, tx(1).hash]);
+        assert!(order.confirm(&tx(2).hash).is_empty());
+        assert_eq!(order.add(tx(1)), TxAdmission::Added);
+
+        order.propose().unwrap().unwrap();
+        assert_eq!(
+            hashes(order.committed()),
+            vec![tx(3).hash, signed_tx(101, 0, CHAIN_ID).hash]
+        );
+
+        let dropped =
+            order.drop_committed(|tx| (tx.nonce == 3.into()).then_some(EvictReason::Invalid));
+        assert_eq!(dropped, vec![(tx(3), EvictReason::Invalid)]);
+        assert_eq!(order.add(tx(3)), TxAdmission::Added);
+    }
+
+    #[test]
+    fn test_limits() {
+        let wallet = wallet(1);
+        let backend = RoundRobin::new(CHAIN_ID, vec![wallet.address()], Some(wallet)).unwrap();
+
+        let limits = MempoolLimits {
+            max_count: 2,
+            max_per_sender: 2,
+            ttl: Duration::from_secs(60),
+            ..Default::default()
+        };
+
+        let mut order = SharedOrder::new(backend, 1, limits);
+
+        assert_eq!(order.add(tx(0)), TxAdmission::Added);
+        assert_eq!(order.add(tx(1)), TxAdmission::Added);
+        assert_eq!(
+            order.add(tx(2)),
+            TxAdmission::Rejected(RejectReason::SenderSlotsFull(2))
+        );
+
+        let other = signed_tx(101, 2, CHAIN_ID);
+        assert_eq!(
+            order.add(other),
+            TxAdmission::Rejected(RejectReason::PoolFull)
+        );
+
+        // committed transaction leaves room and never expires
+        order.propose().unwrap().unwrap();
+        assert_eq!(hashes(order.committed()), vec![tx(0).hash]);
+        assert_eq!(order.add(tx(2)), TxAdmission::Added);
+
+        let expired = order.expire(Instant::now() + Duration::from_secs(61));
+        assert_eq!(hashes(&expired), vec![tx(1).hash, tx(2).hash]);
+        assert_eq!(order.pending_bytes, 0);
+        assert_eq!(hashes(order.committed()), vec![tx(0).hash]);
+
+        assert_eq!(hashes(&order.confirm(&tx(0).hash)), vec![tx(0).hash]);
+        assert!(order.confirm(&tx(1).hash).is_empty());
+    }
+}
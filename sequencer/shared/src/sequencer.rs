@@ -0,0 +1,298 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    io,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use crate::{
+    ApiRequest, ApiResponse, BatchTransport, Error, OrderingBackend, Result, SharedOrder,
+    SharedSequencerApi, SharedSequencerConfig, SignedBatch,
+};
+use async_trait::async_trait;
+use ethers_core::types::{BlockId, BlockNumber};
+use fluct_core::{
+    ConsensusGenesis, EvictReason, RejectReason, SequencerService, Service, Transaction,
+    TxAdmission, TxEvent, TxValidator, Web3Api,
+};
+use fluct_service::{
+    local_rpc, AsyncStepService, AsyncStepServiceWapper1, CallError, Caller, Hander,
+};
+use futures_util::future::{select, Either};
+use tokio::{
+    sync::{broadcast, oneshot},
+    time::{self, Instant},
+};
+
+/// Events kept for slow subscribers
+const EVENT_CAPACITY: usize = 1024;
+
+enum Event {
+    Request(ApiRequest, Option<oneshot::Sender<ApiResponse>>),
+    Batch(SignedBatch),
+    Tick,
+}
+
+struct SharedSequencer<B, T> {
+    handler: Hander<ApiRequest, ApiResponse>,
+    caller: Caller<ApiRequest, ApiResponse>,
+    order: SharedOrder<B>,
+    events: broadcast::Sender<TxEvent>,
+    transport: T,
+    batch_interval: Duration,
+    next_batch: Instant,
+    validator: Arc<OnceLock<TxValidator>>,
+    web3_api: Option<Box<dyn Web3Api>>,
+}
+
+impl<B, T> SharedSequencer<B, T>
+where
+    B: OrderingBackend,
+    T: BatchTransport,
+{
+    pub fn new(backend: B, transport: T, config: SharedSequencerConfig) -> Self {
+        let (handler, caller) = local_rpc();
+
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+
+        Self {
+            handler,
+            caller,
+            order: SharedOrder::new(backend, config.max_batch_txs, config.limits),
+            events,
+            transport,
+            batch_interval: config.batch_interval,
+            next_batch: Instant::now() + config.batch_interval,
+            validator: Arc::new(OnceLock::new()),
+            web3_api: None,
+        }
+    }
+
+    /// Send event, it's dropped if no subscriber
+    fn emit(&self, event: TxEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Check transaction with account state
+    async fn admit(&mut self, tx: Transaction) -> Result<TxAdmission> {
+        let validator = self.validator.get().ok_or(Error::NotInited)?;
+
+        let api = self.web3_api.as_mut().ok_or(Error::NoWeb3ApiConfiged)?;
+
+        if api.get_transaction(tx.hash).await?.is_some() {
+            return Ok(TxAdmission::AlreadyKnown);
+        }
+
+        let block = Some(BlockId::Number(BlockNumber::Latest));
+
+        let balance = api.balance(tx.from, block).await?;
+        let nonce = api.transaction_count(tx.from, block).await?;
+
+        if let Err(reason) = validator.validate_state(&tx, balance, nonce) {
+            return Ok(TxAdmission::Rejected(reason));
+        }
+
+        Ok(self.order.add(tx))
+    }
+
+    /// Drop committed transactions can't be executed on latest state
+    ///
+    /// Executing them fails, so they would never be confirmed.
+    async fn prune(&mut self) -> Result<()> {
+        if self.order.committed().is_empty() {
+            return Ok(());
+        }
+
+        let validator = self.validator.get().ok_or(Error::NotInited)?;
+
+        let api = self.web3_api.as_mut().ok_or(Error::NoWeb3ApiConfiged)?;
+
+        let block = Some(BlockId::Number(BlockNumber::Latest));
+
+        let mut states = HashMap::new();
+
+        for tx in self.order.committed() {
+            if let Entry::Vacant(entry) = states.entry(tx.from) {
+                let balance = api.balance(tx.from, block).await?;
+                let nonce = api.transaction_count(tx.from, block).await?;
+
+                entry.insert((balance, nonce));
+            }
+        }
+
+        let dropped = self.order.drop_committed(|tx| {
+            let (balance, nonce) = states[&tx.from];
+
+            match validator.validate_state(tx, balance, nonce) {
+                Ok(()) => None,
+                Err(RejectReason::NonceTooLow(_, _)) => Some(EvictReason::Outdated),
+                Err(_) => Some(EvictReason::Invalid),
+            }
+        });
+
+        for (tx, reason) in dropped {
+            log::debug!("Drop committed transaction {:?}: {:?}", tx.hash, reason);
+
+            self.emit(TxEvent::Evicted(tx.hash, reason));
+        }
+
+        Ok(())
+    }
+
+    async fn next_event(&mut self) -> Result<Event> {
+        let request = Box::pin(self.handler.recv());
+        let batch = Box::pin(self.transport.recv());
+        let tick = Box::pin(time::sleep_until(self.next_batch));
+
+        let event = match select(request, select(batch, tick)).await {
+            Either::Left((r, _)) => {
+                let (req, resper) = r?;
+                Event::Request(req, resper)
+            }
+            Either::Right((Either::Left((batch, _)), _)) => Event::Batch(batch?),
+            Either::Right((Either::Right(_), _)) => Event::Tick,
+        };
+
+        Ok(event)
+    }
+
+    async fn _step(&mut self) -> Result<()> {
+        match self.next_event().await? {
+            Event::Request(ApiRequest::Transaction(tx), resper) => {
+                let hash = tx.hash;
+
+                let admission = self.admit(*tx).await?;
+
+                if admission == TxAdmission::Added {
+                    self.emit(TxEvent::Added(hash));
+                }
+
+                log::debug!("Transaction {:?} admission: {:?}", hash, admission);
+
+                if let Some(resper) = resper {
+                    if resper.send(ApiResponse::Transaction(admission)).is_err() {
+                        log::warn!("Caller of transaction {:?} is gone", hash);
+                    }
+                }
+            }
+            Event::Request(ApiRequest::TxHash(txhash), _) => {
+                for tx in self.order.confirm(&txhash) {
+                    if tx.hash == txhash {
+                        self.emit(TxEvent::Confirmed(tx.hash));
+                    } else {
+                        self.emit(TxEvent::Evicted(tx.hash, EvictReason::Outdated));
+                    }
+                }
+            }
+            Event::Request(ApiRequest::GetAllTransaction, resper) => {
+                if let Some(resper) = resper {
+                    resper
+                        .send(ApiResponse::GetAllTransaction(
+                            self.order.committed().to_vec(),
+                        ))
+                        .map_err(|_| CallError::ChannelClosed)?;
+                } else {
+                    log::warn!("Use send method to get txpool")
+                }
+            }
+            Event::Batch(batch) => {
+                let height = batch.batch.height;
+
+                let validator = self.validator.get().ok_or(Error::NotInited)?;
+
+                if let Err(e) = self.order.receive(batch, validator) {
+                    log::warn!("Drop batch of height {}: {}", height, e);
+                }
+            }
+            Event::Tick => {
+                self.next_batch = Instant::now() + self.batch_interval;
+
+                if let Some(batch) = self.order.propose()? {
+                    self.transport.broadcast(batch)?;
+                }
+
+                self.prune().await?;
+            }
+        }
+
+        for hash in self.order.take_added() {
+            self.emit(TxEvent::Added(hash));
+        }
+
+        for tx in self.order.expire(Instant::now().into_std()) {
+            self.emit(TxEvent::Evicted(tx.hash, EvictReason::Expired));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<B, T> AsyncStepService for SharedSequencer<B, T>
+where
+    B: OrderingBackend + Sync,
+    T: BatchTransport + Sync,
+{
+    type Error = Error;
+
+    async fn step(&mut self) -> Result<()> {
+        self._step().await
+    }
+}
+
+pub struct SharedSequencerService<B, T>(AsyncStepServiceWapper1<SharedSequencer<B, T>>);
+
+impl<B, T> SharedSequencerService<B, T>
+where
+    B: OrderingBackend,
+    T: BatchTransport,
+{
+    pub fn new(backend: B, transport: T, config: SharedSequencerConfig) -> Self {
+        Self(AsyncStepServiceWapper1::new(SharedSequencer::new(
+            backend, transport, config,
+        )))
+    }
+}
+
+impl<B, T> Service for SharedSequencerService<B, T>
+where
+    B: OrderingBackend + Sync,
+    T: BatchTransport + Sync,
+{
+    type Error = io::Error;
+
+    fn start(&mut self) -> std::result::Result<(), Self::Error> {
+        self.0.start()
+    }
+
+    fn stop(&mut self) -> std::result::Result<(), Self::Error> {
+        self.0.stop()
+    }
+}
+
+#[async_trait]
+impl<B, T> SequencerService for SharedSequencerService<B, T>
+where
+    B: OrderingBackend + Sync,
+    T: BatchTransport + Sync,
+{
+    type Api = SharedSequencerApi;
+
+    fn api(&self) -> SharedSequencerApi {
+        SharedSequencerApi {
+            caller: self.0.service0().caller.clone(),
+            events: self.0.service0().events.clone(),
+            validator: self.0.service0().validator.clone(),
+        }
+    }
+
+    fn set_api(&mut self, web3_api: impl Web3Api) {
+        self.0.service0_mut().web3_api = Some(Box::new(web3_api));
+    }
+
+    fn init(&mut self, genesis: &ConsensusGenesis<Transaction>) {
+        if self.0.service0().validator.set(genesis.into()).is_err() {
+            log::warn!("Sequencer already inited");
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+use tokio::sync::broadcast;
+
+use crate::{Error, Result, SignedBatch};
+
+/// Transport of batches between sequencer nodes
+#[async_trait]
+pub trait BatchTransport: Send + 'static {
+    /// Send batch to all nodes
+    fn broadcast(&mut self, batch: SignedBatch) -> Result<()>;
+
+    /// Receive next batch from any node
+    async fn recv(&mut self) -> Result<SignedBatch>;
+}
+
+/// In-process network of sequencer nodes
+#[derive(Clone)]
+pub struct LocalNetwork {
+    sender: broadcast::Sender<SignedBatch>,
+}
+
+impl LocalNetwork {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+
+        Self { sender }
+    }
+
+    /// Transport of a node joining network
+    pub fn join(&self) -> LocalTransport {
+        LocalTransport {
+            sender: self.sender.clone(),
+            receiver: self.sender.subscribe(),
+        }
+    }
+}
+
+/// Transport over `LocalNetwork`, node receives batches sent by itself too
+pub struct LocalTransport {
+    sender: broadcast::Sender<SignedBatch>,
+    receiver: broadcast::Receiver<SignedBatch>,
+}
+
+#[async_trait]
+impl BatchTransport for LocalTransport {
+    fn broadcast(&mut self, batch: SignedBatch) -> Result<()> {
+        self.sender
+            .send(batch)
+            .map_err(|_| Error::TransportClosed)?;
+
+        Ok(())
+    }
+
+    async fn recv(&mut self) -> Result<SignedBatch> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(batch) => return Ok(batch),
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    log::warn!("Transport lagged, {} batches lost", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => return Err(Error::TransportClosed),
+            }
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use ethers_core::types::H256;
+use fluct_core::{Transaction, TxAdmission};
+
+pub enum ApiRequest {
+    Transaction(Box<Transaction>),
+    TxHash(H256),
+    GetAllTransaction,
+}
+
+pub enum ApiResponse {
+    Transaction(TxAdmission),
+    GetAllTransaction(Vec<Transaction>),
+}